@@ -0,0 +1,43 @@
+/* A desk calculator, in the style of the bison manual. */
+
+%{
+#include <math.h>
+#include <stdio.h>
+int yylex (void);
+void yyerror (char const *);
+%}
+
+%define api.value.type {double}
+%token NUM
+%token POW "**"
+%left '-' '+'
+%left '*' '/'
+%precedence NEG
+
+%% /* The grammar follows. */
+
+input:
+  %empty
+| input line
+;
+
+line:
+  '\n'
+| exp '\n'      { printf ("%.10g\n", $1); }
+| error '\n'    { yyerrok; }
+;
+
+exp:
+  NUM
+| exp '+' exp        { $$ = $1 + $3;      }
+| exp '-' exp        { $$ = $1 - $3;      }
+| exp '*' exp        { $$ = $1 * $3;      }
+| exp '/' exp        { $$ = $1 / $3;      }
+| '-' exp  %prec NEG { $$ = -$2;          }
+| exp "**" exp       { $$ = pow ($1, $3); }
+| '(' { depth++; } exp ')' { $$ = $3; depth--; }
+;
+
+%%
+
+int main (void) { return yyparse (); }   /* '%%' and { are not lexed here */
@@ -0,0 +1,221 @@
+//! Semantic actions, which compute a value for a sentence while it is parsed.
+//!
+//! An [`Actions`] holds a closure for each production of a grammar. The parsers
+//! call the closure of a production as soon as all of its children are parsed,
+//! with the terminals and the values of the nonterminals of its rhs, so that
+//! the value of a sentence is computed without building its [`ParseTree`]:
+//!
+//! ```
+//! # use parsing::{actions::Actions, grammar::build_grammar, ll1};
+//! let grammar = build_grammar(
+//!     "E E' N",
+//!     "+ 1 2",
+//!     vec![("E", "N E'"), ("E'", "+ N E' | "), ("N", "1 | 2")],
+//!     "E",
+//! );
+//! let mut actions = Actions::new(&grammar);
+//! actions
+//!     .on("E -> N E'", |c| c[0].value() + c[1].value())
+//!     .on("E' -> + N E'", |c| c[1].value() + c[2].value())
+//!     .on("E' ->", |_| 0)
+//!     .on("N -> 1", |_| 1)
+//!     .on("N -> 2", |_| 2);
+//! let result = ll1::evaluate(&actions, &["2", "+", "1", "+", "2"]);
+//! assert!(matches!(result, ll1::EvalResult::Value(5)));
+//! ```
+
+use std::fmt;
+
+use crate::{
+    grammar::{Grammar, Terminal},
+    parse_tree::ParseTree,
+};
+
+/// A child of a production, as passed to its action.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Child<'a, T> {
+    Terminal(&'a Terminal),
+    /// The value of a nonterminal child.
+    NonTerminal(T),
+}
+
+impl<'a, T> Child<'a, T> {
+    /// The value of a nonterminal child.
+    ///
+    /// # Panics
+    ///
+    /// If the child is a terminal.
+    pub fn value(&self) -> &T {
+        match self {
+            Child::NonTerminal(value) => value,
+            Child::Terminal(t) => panic!("terminal `{}` has no value", t),
+        }
+    }
+
+    /// Like [`value`](Self::value), but takes the value out of the child.
+    pub fn into_value(self) -> T {
+        match self {
+            Child::NonTerminal(value) => value,
+            Child::Terminal(t) => panic!("terminal `{}` has no value", t),
+        }
+    }
+
+    /// The terminal of a terminal child.
+    ///
+    /// # Panics
+    ///
+    /// If the child is a nonterminal.
+    pub fn terminal(&self) -> &'a Terminal {
+        match self {
+            Child::Terminal(t) => t,
+            Child::NonTerminal(_) => panic!("child is not a terminal"),
+        }
+    }
+}
+
+type Action<'a, T> = Box<dyn Fn(Vec<Child<'a, T>>) -> T + 'a>;
+
+/// A semantic action for each production of a grammar.
+///
+/// A production without an action passes on the value of its only nonterminal
+/// child, so chains like `F -> ID` need no action.
+pub struct Actions<'a, T> {
+    grammar: &'a Grammar,
+    actions: Vec<Option<Action<'a, T>>>,
+}
+
+impl<'a, T> fmt::Debug for Actions<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Actions")
+            .field(
+                "productions",
+                &self
+                    .actions
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, action)| action.is_some())
+                    .map(|(idx, _)| idx)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<'a, T> Actions<'a, T> {
+    pub fn new(grammar: &'a Grammar) -> Self {
+        Self {
+            grammar,
+            actions: grammar.productions().iter().map(|_| None).collect(),
+        }
+    }
+
+    pub fn grammar(&self) -> &'a Grammar {
+        self.grammar
+    }
+
+    /// Sets the action of the production written as `production`; see
+    /// [`Grammar::find_production`].
+    ///
+    /// # Panics
+    ///
+    /// If the grammar has no such production.
+    pub fn on(
+        &mut self,
+        production: &str,
+        action: impl Fn(Vec<Child<'a, T>>) -> T + 'a,
+    ) -> &mut Self {
+        let idx = self
+            .grammar
+            .find_production(production)
+            .unwrap_or_else(|| panic!("no production `{}` in the grammar", production));
+        self.on_index(idx, action)
+    }
+
+    /// Sets the action of the production at `idx` in
+    /// [`Grammar::productions`].
+    pub fn on_index(
+        &mut self,
+        idx: usize,
+        action: impl Fn(Vec<Child<'a, T>>) -> T + 'a,
+    ) -> &mut Self {
+        self.actions[idx] = Some(Box::new(action));
+        self
+    }
+
+    /// Calls the action of the production at `idx` with `children`.
+    ///
+    /// # Panics
+    ///
+    /// If the production has no action and not exactly one nonterminal child.
+    pub fn apply(&self, idx: usize, children: Vec<Child<'a, T>>) -> T {
+        if let Some(action) = &self.actions[idx] {
+            return action(children);
+        }
+        let mut values = children.into_iter().filter_map(|child| match child {
+            Child::NonTerminal(value) => Some(value),
+            Child::Terminal(_) => None,
+        });
+        match (values.next(), values.next()) {
+            (Some(value), None) => value,
+            _ => panic!(
+                "no action for production `{}`",
+                self.grammar.productions()[idx].display(self.grammar)
+            ),
+        }
+    }
+
+    /// Evaluates `tree` bottom-up. Returns `None` if a node of the tree is not a
+    /// production of the grammar.
+    pub fn evaluate(&self, tree: &ParseTree<'a>) -> Option<T> {
+        let ParseTree::NonTerminal(_, children) = tree else {
+            return None;
+        };
+        let idx = self.grammar.tree_production(tree)?;
+        let children = children
+            .iter()
+            .map(|child| match child {
+                ParseTree::Terminal(t) => Some(Child::Terminal(t)),
+                ParseTree::NonTerminal(..) => self.evaluate(child).map(Child::NonTerminal),
+            })
+            .collect::<Option<_>>()?;
+        Some(self.apply(idx, children))
+    }
+}
+
+/// What a parser builds for each node of the parse tree, bottom-up.
+pub(crate) trait Reduce<'a> {
+    type Value;
+
+    fn terminal(&self, t: &'a Terminal) -> Self::Value;
+
+    /// The value of a node of the production at `idx`.
+    fn production(&self, idx: usize, children: Vec<Self::Value>) -> Self::Value;
+}
+
+impl<'a, T> Reduce<'a> for Actions<'a, T> {
+    type Value = Child<'a, T>;
+
+    fn terminal(&self, t: &'a Terminal) -> Self::Value {
+        Child::Terminal(t)
+    }
+
+    fn production(&self, idx: usize, children: Vec<Self::Value>) -> Self::Value {
+        Child::NonTerminal(self.apply(idx, children))
+    }
+}
+
+/// Builds the [`ParseTree`].
+pub(crate) struct Trees<'a>(pub(crate) &'a Grammar);
+
+impl<'a> Reduce<'a> for Trees<'a> {
+    type Value = ParseTree<'a>;
+
+    fn terminal(&self, t: &'a Terminal) -> Self::Value {
+        ParseTree::Terminal(t)
+    }
+
+    fn production(&self, idx: usize, children: Vec<Self::Value>) -> Self::Value {
+        let lhs = self.0.productions()[idx].lhs();
+        ParseTree::NonTerminal(self.0.nonterminal(lhs), children)
+    }
+}
@@ -0,0 +1,67 @@
+//! Analyses that search the sentences of a [`Grammar`].
+
+use crate::{
+    generate::Sentences,
+    grammar::{transform::Derivation, Grammar, NonTerminalId},
+    parse_tree::ParseTree,
+};
+
+/// A sentence with two different parse trees.
+#[derive(Debug, Clone)]
+pub struct Ambiguity<'a> {
+    pub tokens: Vec<&'a str>,
+    pub trees: [ParseTree<'a>; 2],
+    /// The first nonterminal of the leftmost derivations of the trees that they
+    /// expand with different productions.
+    pub nonterminal: NonTerminalId,
+    /// The indices of the productions that the two trees apply to
+    /// `nonterminal`.
+    pub productions: [usize; 2],
+}
+
+/// Returns the first production of each tree at the first node, in preorder,
+/// where they differ.
+fn divergence(a: &Derivation, b: &Derivation) -> Option<(usize, usize)> {
+    let (Derivation::Node(a, a_children), Derivation::Node(b, b_children)) = (a, b) else {
+        return None;
+    };
+    if a != b {
+        return Some((*a, *b));
+    }
+    a_children
+        .iter()
+        .zip(b_children)
+        .find_map(|(a, b)| divergence(a, b))
+}
+
+/// Searches the sentences of at most `max_len` tokens for one with two parse
+/// trees, and returns the shortest such sentence, the first in lexicographic
+/// order of the terminal names among those of the same length.
+///
+/// `None` does not mean that the grammar is unambiguous, only that no longer
+/// sentence was tried.
+pub fn find_ambiguity(grammar: &Grammar, max_len: usize) -> Option<Ambiguity<'_>> {
+    let sentences = Sentences::new(grammar, max_len);
+    let start = grammar.start();
+    let (tokens, string) = sentences.strings[start.index()]
+        .iter()
+        .filter(|(_, derivations)| derivations.ambiguous)
+        .map(|(string, _)| {
+            let tokens = string
+                .iter()
+                .map(|&t| grammar.terminal(t).0.as_str())
+                .collect::<Vec<_>>();
+            (tokens, string)
+        })
+        .min_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))?;
+
+    let first = sentences.derivation(grammar, start, string);
+    let second = sentences.alternative_derivation(grammar, start, string);
+    let (a, b) = divergence(&first, &second).expect("Ambiguous derivations are equal");
+    Some(Ambiguity {
+        tokens,
+        trees: [first.to_parse_tree(grammar), second.to_parse_tree(grammar)],
+        nonterminal: grammar.productions()[a].lhs(),
+        productions: [a, b],
+    })
+}
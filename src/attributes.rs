@@ -0,0 +1,792 @@
+//! Attribute grammars, evaluated over a [`ParseTree`].
+//!
+//! Each nonterminal can have synthesized attributes, computed from its children,
+//! and inherited attributes, passed down from its parent. The equations of a
+//! production refer to the attributes of its symbols as in yacc: `$$.name` for
+//! the lhs and `$k.name` for the `k`-th symbol of the rhs, counting from 1. A
+//! production defines the synthesized attributes of its lhs and the inherited
+//! attributes of the nonterminals in its rhs, and its equations can read any
+//! attribute of its symbols:
+//!
+//! ```
+//! # use parsing::{attributes::AttributeGrammar, grammar::build_grammar, ll1};
+//! let grammar = build_grammar(
+//!     "S P",
+//!     "( )",
+//!     vec![("S", "P"), ("P", "( P ) P | ")],
+//!     "S",
+//! );
+//! let mut builder = AttributeGrammar::builder(&grammar);
+//! builder
+//!     .inherited("P", "depth")
+//!     .synthesized("P", "max")
+//!     .synthesized("S", "max")
+//!     .equation("S -> P", "$1.depth", &[], |_| 0)
+//!     .equation("S -> P", "$$.max", &["$1.max"], |v| *v[0])
+//!     .equation("P -> ( P ) P", "$2.depth", &["$$.depth"], |v| v[0] + 1)
+//!     .equation("P -> ( P ) P", "$4.depth", &["$$.depth"], |v| *v[0])
+//!     .equation("P -> ( P ) P", "$$.max", &["$2.max", "$4.max"], |v| *v[0].max(v[1]))
+//!     .equation("P ->", "$$.max", &["$$.depth"], |v| *v[0]);
+//! let attributes = builder.build().unwrap();
+//!
+//! let ll1::ParseResult::Parse(tree) = ll1::parse(&grammar, &["(", "(", ")", ")", "(", ")"])
+//! else {
+//!     panic!()
+//! };
+//! let evaluation = attributes.evaluate(&tree, vec![]).unwrap();
+//! assert_eq!(evaluation.root("max"), Some(&2));
+//! ```
+//!
+//! [`AttributeGrammarBuilder::build`] checks that every attribute is defined by
+//! exactly one equation and, with Knuth's test, that no tree of the grammar has
+//! circular attribute dependencies. The evaluator then computes the attributes
+//! of a tree in the order of their dependencies.
+
+use std::{
+    collections::{BTreeSet, HashSet, VecDeque},
+    error, fmt,
+};
+
+use crate::{
+    grammar::{Grammar, NonTerminalId, Symbol},
+    parse_tree::ParseTree,
+};
+
+/// Whether an attribute is computed from the children of its node or from its
+/// parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeKind {
+    Inherited,
+    Synthesized,
+}
+
+/// The attributes of a production are written like `$$.name` or `$k.name`, see
+/// the [module](self) documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttributeError {
+    /// Attributes are declared for a symbol that is not a nonterminal.
+    UndeclaredNonTerminal(String),
+    /// An attribute is declared twice for the same nonterminal.
+    DuplicateAttribute {
+        nonterminal: String,
+        attribute: String,
+    },
+    /// An equation is given for a production that is not in the grammar.
+    UnknownProduction(String),
+    /// An equation of the production at the given index refers to an attribute
+    /// that is malformed, out of range, of a terminal or not declared.
+    InvalidAttribute {
+        production: usize,
+        attribute: String,
+    },
+    /// An equation defines an inherited attribute of the lhs or a synthesized
+    /// attribute of the rhs, which other productions define.
+    NotDefinable {
+        production: usize,
+        attribute: String,
+    },
+    DuplicateEquation {
+        production: usize,
+        attribute: String,
+    },
+    MissingEquation {
+        production: usize,
+        attribute: String,
+    },
+    /// In some tree, an attribute of the production at the given index depends
+    /// on itself. The cycle lists the attributes of the production involved,
+    /// each depending on the one before it and the first on the last, possibly
+    /// through the subtrees of its rhs.
+    Circular {
+        production: usize,
+        cycle: Vec<String>,
+    },
+}
+
+impl fmt::Display for AttributeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttributeError::UndeclaredNonTerminal(s) => {
+                write!(f, "`{}` is not a nonterminal of the grammar", s)
+            }
+            AttributeError::DuplicateAttribute {
+                nonterminal,
+                attribute,
+            } => write!(
+                f,
+                "attribute `{}` of `{}` is declared twice",
+                attribute, nonterminal
+            ),
+            AttributeError::UnknownProduction(s) => {
+                write!(f, "`{}` is not a production of the grammar", s)
+            }
+            AttributeError::InvalidAttribute {
+                production,
+                attribute,
+            } => write!(
+                f,
+                "`{}` is not an attribute of production {}",
+                attribute, production
+            ),
+            AttributeError::NotDefinable {
+                production,
+                attribute,
+            } => write!(
+                f,
+                "production {} cannot define `{}`, which is defined by other productions",
+                production, attribute
+            ),
+            AttributeError::DuplicateEquation {
+                production,
+                attribute,
+            } => write!(
+                f,
+                "production {} has two equations for `{}`",
+                production, attribute
+            ),
+            AttributeError::MissingEquation {
+                production,
+                attribute,
+            } => write!(
+                f,
+                "production {} has no equation for `{}`",
+                production, attribute
+            ),
+            AttributeError::Circular { production, cycle } => write!(
+                f,
+                "attribute `{}` of production {} depends on itself: {} -> {}",
+                cycle[0],
+                production,
+                cycle.join(" -> "),
+                cycle[0]
+            ),
+        }
+    }
+}
+
+impl error::Error for AttributeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvaluationError {
+    /// A node of the tree is not a production of the grammar.
+    NotAProduction(String),
+    /// No value was given for an inherited attribute of the root.
+    MissingInherited(String),
+    /// A value was given for an attribute that is not an inherited attribute
+    /// of the root.
+    UnknownInherited(String),
+}
+
+impl fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvaluationError::NotAProduction(s) => {
+                write!(f, "node `{}` is not a production of the grammar", s)
+            }
+            EvaluationError::MissingInherited(s) => {
+                write!(f, "no value for inherited attribute `{}` of the root", s)
+            }
+            EvaluationError::UnknownInherited(s) => {
+                write!(f, "`{}` is not an inherited attribute of the root", s)
+            }
+        }
+    }
+}
+
+impl error::Error for EvaluationError {}
+
+type Function<'a, V> = Box<dyn Fn(&[&V]) -> V + 'a>;
+
+/// An attribute of a symbol of a production: the position of the symbol, 0
+/// for the lhs, and the index of the attribute in the attributes of its
+/// nonterminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Slot {
+    position: usize,
+    attribute: usize,
+}
+
+struct Equation<'a, V> {
+    target: Slot,
+    dependencies: Vec<Slot>,
+    function: Function<'a, V>,
+}
+
+/// Builds an [`AttributeGrammar`] from declarations and equations given by
+/// name, which are checked by [`build`](Self::build).
+pub struct AttributeGrammarBuilder<'a, V> {
+    grammar: &'a Grammar,
+    attributes: Vec<(String, String, AttributeKind)>,
+    equations: Vec<(String, String, Vec<String>, Function<'a, V>)>,
+}
+
+impl<'a, V> AttributeGrammarBuilder<'a, V> {
+    pub fn new(grammar: &'a Grammar) -> Self {
+        Self {
+            grammar,
+            attributes: vec![],
+            equations: vec![],
+        }
+    }
+
+    pub fn inherited(
+        &mut self,
+        nonterminal: impl Into<String>,
+        attribute: impl Into<String>,
+    ) -> &mut Self {
+        self.attributes.push((
+            nonterminal.into(),
+            attribute.into(),
+            AttributeKind::Inherited,
+        ));
+        self
+    }
+
+    pub fn synthesized(
+        &mut self,
+        nonterminal: impl Into<String>,
+        attribute: impl Into<String>,
+    ) -> &mut Self {
+        self.attributes.push((
+            nonterminal.into(),
+            attribute.into(),
+            AttributeKind::Synthesized,
+        ));
+        self
+    }
+
+    /// Adds the equation `target = function(dependencies)` to the production
+    /// written as `production` (see [`Grammar::find_production`]). `function`
+    /// gets the values of the `dependencies` in order.
+    pub fn equation(
+        &mut self,
+        production: impl Into<String>,
+        target: impl Into<String>,
+        dependencies: &[&str],
+        function: impl Fn(&[&V]) -> V + 'a,
+    ) -> &mut Self {
+        self.equations.push((
+            production.into(),
+            target.into(),
+            dependencies.iter().map(ToString::to_string).collect(),
+            Box::new(function),
+        ));
+        self
+    }
+
+    pub fn build(self) -> Result<AttributeGrammar<'a, V>, AttributeError> {
+        let grammar = self.grammar;
+        let mut attributes = vec![vec![]; grammar.nonterminal_count()];
+        for (nt, attribute, kind) in self.attributes {
+            let Some(id) = grammar.nonterminal_id(&nt) else {
+                return Err(AttributeError::UndeclaredNonTerminal(nt));
+            };
+            let declared: &mut Vec<(String, AttributeKind)> = &mut attributes[id.index()];
+            if declared.iter().any(|(name, _)| *name == attribute) {
+                return Err(AttributeError::DuplicateAttribute {
+                    nonterminal: nt,
+                    attribute,
+                });
+            }
+            declared.push((attribute, kind));
+        }
+
+        let mut result = AttributeGrammar {
+            grammar,
+            attributes,
+            equations: grammar.productions().iter().map(|_| vec![]).collect(),
+        };
+        for (production, target, dependencies, function) in self.equations {
+            let Some(idx) = grammar.find_production(&production) else {
+                return Err(AttributeError::UnknownProduction(production));
+            };
+            let invalid = |attribute: &str| AttributeError::InvalidAttribute {
+                production: idx,
+                attribute: attribute.to_string(),
+            };
+            let target_slot = result.slot(idx, &target).ok_or_else(|| invalid(&target))?;
+            let dependencies = dependencies
+                .iter()
+                .map(|dependency| {
+                    result
+                        .slot(idx, dependency)
+                        .ok_or_else(|| invalid(dependency))
+                })
+                .collect::<Result<_, _>>()?;
+            let definable = match result.kind(idx, target_slot) {
+                AttributeKind::Synthesized => target_slot.position == 0,
+                AttributeKind::Inherited => target_slot.position > 0,
+            };
+            if !definable {
+                return Err(AttributeError::NotDefinable {
+                    production: idx,
+                    attribute: target,
+                });
+            }
+            if result.equations[idx]
+                .iter()
+                .any(|equation| equation.target == target_slot)
+            {
+                return Err(AttributeError::DuplicateEquation {
+                    production: idx,
+                    attribute: target,
+                });
+            }
+            result.equations[idx].push(Equation {
+                target: target_slot,
+                dependencies,
+                function,
+            });
+        }
+
+        for idx in 0..grammar.productions().len() {
+            for slot in result.slots(idx) {
+                let defined_here = match result.kind(idx, slot) {
+                    AttributeKind::Synthesized => slot.position == 0,
+                    AttributeKind::Inherited => slot.position > 0,
+                };
+                if defined_here
+                    && !result.equations[idx]
+                        .iter()
+                        .any(|equation| equation.target == slot)
+                {
+                    return Err(AttributeError::MissingEquation {
+                        production: idx,
+                        attribute: result.slot_name(idx, slot),
+                    });
+                }
+            }
+        }
+
+        result.check_circularity()?;
+        Ok(result)
+    }
+}
+
+/// The pairs of an inherited and a synthesized attribute of a nonterminal, by
+/// index, where the synthesized attribute depends on the inherited one in some
+/// subtree.
+type IoGraph = BTreeSet<(usize, usize)>;
+
+/// Returns the nodes of a cycle of the graph given by the successors of each
+/// node, in the order of the edges, if there is one.
+fn find_cycle(edges: &[Vec<usize>]) -> Option<Vec<usize>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        New,
+        OnPath,
+        Done,
+    }
+    let mut state = vec![State::New; edges.len()];
+    for start in 0..edges.len() {
+        if state[start] != State::New {
+            continue;
+        }
+        // The path from `start`, each node with the index of its next edge.
+        let mut path = vec![(start, 0)];
+        state[start] = State::OnPath;
+        while let Some((node, next)) = path.last_mut() {
+            let Some(&successor) = edges[*node].get(*next) else {
+                state[*node] = State::Done;
+                path.pop();
+                continue;
+            };
+            *next += 1;
+            match state[successor] {
+                State::New => {
+                    state[successor] = State::OnPath;
+                    path.push((successor, 0));
+                }
+                State::OnPath => {
+                    let from = path.iter().position(|&(n, _)| n == successor).unwrap();
+                    return Some(path[from..].iter().map(|&(n, _)| n).collect());
+                }
+                State::Done => {}
+            }
+        }
+    }
+    None
+}
+
+/// An attribute grammar over a [`Grammar`], built with
+/// [`AttributeGrammar::builder`].
+pub struct AttributeGrammar<'a, V> {
+    grammar: &'a Grammar,
+    /// The attributes of each nonterminal, indexed by [`NonTerminalId::index`].
+    attributes: Vec<Vec<(String, AttributeKind)>>,
+    /// The equations of each production.
+    equations: Vec<Vec<Equation<'a, V>>>,
+}
+
+impl<'a, V> fmt::Debug for AttributeGrammar<'a, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AttributeGrammar")
+            .field("attributes", &self.attributes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, V> AttributeGrammar<'a, V> {
+    pub fn builder(grammar: &'a Grammar) -> AttributeGrammarBuilder<'a, V> {
+        AttributeGrammarBuilder::new(grammar)
+    }
+
+    pub fn grammar(&self) -> &'a Grammar {
+        self.grammar
+    }
+
+    /// The attributes of `nt`, in the order they were declared.
+    pub fn attributes(&self, nt: NonTerminalId) -> impl Iterator<Item = (&str, AttributeKind)> {
+        self.attributes[nt.index()]
+            .iter()
+            .map(|(name, kind)| (name.as_str(), *kind))
+    }
+
+    /// The nonterminal at `position` of the production at `idx`.
+    fn nonterminal_at(&self, idx: usize, position: usize) -> Option<NonTerminalId> {
+        let production = &self.grammar.productions()[idx];
+        if position == 0 {
+            return Some(production.lhs());
+        }
+        match production.rhs().get(position - 1)? {
+            Symbol::NonTerminal(nt) => Some(*nt),
+            Symbol::Terminal(_) => None,
+        }
+    }
+
+    /// Resolves `$$.name` or `$k.name` in the production at `idx`.
+    fn slot(&self, idx: usize, attribute: &str) -> Option<Slot> {
+        let (symbol, name) = attribute.strip_prefix('$')?.split_once('.')?;
+        let position = match symbol {
+            "$" => 0,
+            k => k.parse::<usize>().ok().filter(|&k| k > 0)?,
+        };
+        let nt = self.nonterminal_at(idx, position)?;
+        let attribute = self.attributes[nt.index()]
+            .iter()
+            .position(|(declared, _)| declared == name)?;
+        Some(Slot {
+            position,
+            attribute,
+        })
+    }
+
+    fn kind(&self, idx: usize, slot: Slot) -> AttributeKind {
+        let nt = self.nonterminal_at(idx, slot.position).unwrap();
+        self.attributes[nt.index()][slot.attribute].1
+    }
+
+    fn slot_name(&self, idx: usize, slot: Slot) -> String {
+        let nt = self.nonterminal_at(idx, slot.position).unwrap();
+        let name = &self.attributes[nt.index()][slot.attribute].0;
+        match slot.position {
+            0 => format!("$$.{}", name),
+            k => format!("${}.{}", k, name),
+        }
+    }
+
+    /// Every attribute of the symbols of the production at `idx`, lhs first.
+    fn slots(&self, idx: usize) -> Vec<Slot> {
+        let len = self.grammar.productions()[idx].rhs().len();
+        (0..=len)
+            .filter_map(|position| {
+                let nt = self.nonterminal_at(idx, position)?;
+                Some(
+                    (0..self.attributes[nt.index()].len()).map(move |attribute| Slot {
+                        position,
+                        attribute,
+                    }),
+                )
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Knuth's test: computes every way in which the synthesized attributes of
+    /// each nonterminal can depend on its inherited attributes, until the
+    /// dependency graph of a production with such graphs for its rhs has a
+    /// cycle, or there are no new graphs.
+    fn check_circularity(&self) -> Result<(), AttributeError> {
+        let productions = self.grammar.productions();
+        let mut graphs = vec![HashSet::<IoGraph>::new(); self.grammar.nonterminal_count()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (idx, production) in productions.iter().enumerate() {
+                let children = (1..=production.rhs().len())
+                    .filter_map(|position| Some((position, self.nonterminal_at(idx, position)?)))
+                    .map(|(position, nt)| (position, graphs[nt.index()].iter().cloned().collect()))
+                    .collect::<Vec<(usize, Vec<IoGraph>)>>();
+                if children.iter().any(|(_, choices)| choices.is_empty()) {
+                    continue;
+                }
+                // Go through every choice of a graph for each child.
+                let mut choice = vec![0; children.len()];
+                let mut new = vec![];
+                loop {
+                    let subtrees = children
+                        .iter()
+                        .zip(&choice)
+                        .map(|((position, choices), &k)| (*position, &choices[k]));
+                    new.push(self.production_graph(idx, subtrees)?);
+                    let Some(k) = (0..choice.len()).find(|&k| choice[k] + 1 < children[k].1.len())
+                    else {
+                        break;
+                    };
+                    choice[k] += 1;
+                    choice[..k].fill(0);
+                }
+                let lhs = &mut graphs[production.lhs().index()];
+                for graph in new {
+                    changed |= lhs.insert(graph);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the graph of the lhs of the production at `idx` given a graph for
+    /// each nonterminal of its rhs, or the cycle if there is one.
+    fn production_graph<'g>(
+        &self,
+        idx: usize,
+        subtrees: impl Iterator<Item = (usize, &'g IoGraph)>,
+    ) -> Result<IoGraph, AttributeError> {
+        let slots = self.slots(idx);
+        let node = |slot: Slot| slots.iter().position(|&s| s == slot).unwrap();
+        let mut edges = vec![vec![]; slots.len()];
+        for equation in self.equations[idx].iter() {
+            for &dependency in equation.dependencies.iter() {
+                edges[node(dependency)].push(node(equation.target));
+            }
+        }
+        for (position, graph) in subtrees {
+            for &(inherited, synthesized) in graph {
+                let slot = |attribute| Slot {
+                    position,
+                    attribute,
+                };
+                edges[node(slot(inherited))].push(node(slot(synthesized)));
+            }
+        }
+
+        if let Some(cycle) = find_cycle(&edges) {
+            return Err(AttributeError::Circular {
+                production: idx,
+                cycle: cycle
+                    .into_iter()
+                    .map(|node| self.slot_name(idx, slots[node]))
+                    .collect(),
+            });
+        }
+
+        let lhs = self.grammar.productions()[idx].lhs();
+        let kinds = &self.attributes[lhs.index()];
+        let mut graph = IoGraph::new();
+        for (inherited, _) in kinds
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, kind))| *kind == AttributeKind::Inherited)
+        {
+            let start = node(Slot {
+                position: 0,
+                attribute: inherited,
+            });
+            let mut seen = vec![false; slots.len()];
+            let mut stack = vec![start];
+            while let Some(node) = stack.pop() {
+                for &next in edges[node].iter() {
+                    if !seen[next] {
+                        seen[next] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+            for (synthesized, _) in kinds
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, kind))| *kind == AttributeKind::Synthesized)
+            {
+                let end = node(Slot {
+                    position: 0,
+                    attribute: synthesized,
+                });
+                if seen[end] {
+                    graph.insert((inherited, synthesized));
+                }
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Computes every attribute of every node of `tree`, given the inherited
+    /// attributes of its root.
+    pub fn evaluate(
+        &self,
+        tree: &ParseTree,
+        root: Vec<(&str, V)>,
+    ) -> Result<Evaluation<V>, EvaluationError> {
+        let mut nodes = vec![];
+        self.flatten(tree, &mut nodes)?;
+
+        // Each attribute of each node gets an index, starting at `offsets[node]`.
+        let mut offsets = vec![];
+        let mut count = 0;
+        for &(production, _) in nodes.iter() {
+            offsets.push(count);
+            let lhs = self.grammar.productions()[production].lhs();
+            count += self.attributes[lhs.index()].len();
+        }
+        let mut values = (0..count).map(|_| None).collect::<Vec<Option<V>>>();
+
+        let root_attributes =
+            &self.attributes[self.grammar.productions()[nodes[0].0].lhs().index()];
+        for (name, value) in root {
+            let Some(attribute) = root_attributes
+                .iter()
+                .position(|(n, kind)| n == name && *kind == AttributeKind::Inherited)
+            else {
+                return Err(EvaluationError::UnknownInherited(name.to_string()));
+            };
+            values[attribute] = Some(value);
+        }
+        if let Some((name, _)) = root_attributes
+            .iter()
+            .zip(&values)
+            .find(|((_, kind), value)| *kind == AttributeKind::Inherited && value.is_none())
+            .map(|(attribute, _)| attribute)
+        {
+            return Err(EvaluationError::MissingInherited(name.clone()));
+        }
+
+        // The equation defining each attribute, and the attributes that depend
+        // on it.
+        let mut definitions = (0..count).map(|_| None).collect::<Vec<_>>();
+        let mut dependents = vec![vec![]; count];
+        let mut waiting = vec![0; count];
+        for (node, (production, children)) in nodes.iter().enumerate() {
+            let instance = |slot: Slot| {
+                let owner = match slot.position {
+                    0 => node,
+                    k => children[k - 1].unwrap(),
+                };
+                offsets[owner] + slot.attribute
+            };
+            for equation in self.equations[*production].iter() {
+                let target = instance(equation.target);
+                let dependencies = equation
+                    .dependencies
+                    .iter()
+                    .map(|&slot| instance(slot))
+                    .collect::<Vec<_>>();
+                for &dependency in dependencies.iter() {
+                    dependents[dependency].push(target);
+                }
+                waiting[target] = dependencies.len();
+                definitions[target] = Some((equation, dependencies));
+            }
+        }
+
+        let mut ready = (0..count)
+            .filter(|&instance| waiting[instance] == 0)
+            .collect::<VecDeque<_>>();
+        while let Some(instance) = ready.pop_front() {
+            if let Some((equation, dependencies)) = &definitions[instance] {
+                let arguments = dependencies
+                    .iter()
+                    .map(|&dependency| values[dependency].as_ref().unwrap())
+                    .collect::<Vec<_>>();
+                let value = (equation.function)(&arguments);
+                values[instance] = Some(value);
+            }
+            for &dependent in dependents[instance].iter() {
+                waiting[dependent] -= 1;
+                if waiting[dependent] == 0 {
+                    ready.push_back(dependent);
+                }
+            }
+        }
+
+        let mut values = values.into_iter();
+        let nodes = nodes
+            .into_iter()
+            .map(|(production, children)| {
+                let lhs = self.grammar.productions()[production].lhs();
+                let attributes = self.attributes[lhs.index()]
+                    .iter()
+                    .map(|(name, _)| {
+                        let value = values.next().unwrap();
+                        (name.clone(), value.expect("circular attribute dependency"))
+                    })
+                    .collect();
+                EvaluatedNode {
+                    children,
+                    attributes,
+                }
+            })
+            .collect();
+        Ok(Evaluation { nodes })
+    }
+
+    /// Appends the nodes of `tree` in preorder, each with its production and the
+    /// index of each nonterminal child.
+    fn flatten(
+        &self,
+        tree: &ParseTree,
+        nodes: &mut Vec<(usize, Vec<Option<usize>>)>,
+    ) -> Result<usize, EvaluationError> {
+        let production = self.grammar.tree_production(tree).ok_or_else(|| {
+            EvaluationError::NotAProduction(match tree {
+                ParseTree::Terminal(t) => t.0.clone(),
+                ParseTree::NonTerminal(nt, _) => nt.0.clone(),
+            })
+        })?;
+        let node = nodes.len();
+        nodes.push((production, vec![]));
+        let ParseTree::NonTerminal(_, children) = tree else {
+            unreachable!("terminals have no production")
+        };
+        for child in children {
+            let child = match child {
+                ParseTree::Terminal(_) => None,
+                ParseTree::NonTerminal(..) => Some(self.flatten(child, nodes)?),
+            };
+            nodes[node].1.push(child);
+        }
+        Ok(node)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct EvaluatedNode<V> {
+    children: Vec<Option<usize>>,
+    attributes: Vec<(String, V)>,
+}
+
+/// The attributes of every node of a tree, computed by
+/// [`AttributeGrammar::evaluate`].
+#[derive(Debug, Clone)]
+pub struct Evaluation<V> {
+    nodes: Vec<EvaluatedNode<V>>,
+}
+
+impl<V> Evaluation<V> {
+    /// The attribute `name` of the node at `path`, which lists the index of
+    /// the child to go down to at each level, starting from the root.
+    pub fn get(&self, path: &[usize], name: &str) -> Option<&V> {
+        let mut node = 0;
+        for &child in path {
+            node = (*self.nodes[node].children.get(child)?)?;
+        }
+        self.nodes[node]
+            .attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value)
+    }
+
+    /// The attribute `name` of the root.
+    pub fn root(&self, name: &str) -> Option<&V> {
+        self.get(&[], name)
+    }
+}
@@ -1,13 +1,16 @@
-use parsing::{
-    grammar::{build_grammar, Grammar},
-    ll1,
-};
+use parsing::{grammar::Grammar, ll1};
 
 fn run(grammar: &Grammar, string: &str) {
     let tokens = string.split_whitespace().collect::<Vec<_>>();
     let res = ll1::parse(grammar, &tokens);
     match res {
-        ll1::ParseResult::Conflict => println!("conflict"),
+        ll1::ParseResult::Conflict(conflicts) => {
+            println!(
+                "conflict\n{}",
+                ll1::conflicts_to_string(grammar, &conflicts)
+            );
+        }
+        ll1::ParseResult::TableMismatch => println!("table mismatch"),
         ll1::ParseResult::NoParse => println!("no parser"),
         ll1::ParseResult::Parse(res) => println!("{}\n", res),
     }
@@ -15,52 +18,13 @@ fn run(grammar: &Grammar, string: &str) {
 
 fn main() {
     let grammars = [
-        build_grammar(
-            "E E' T T' F ID",
-            "+ * ( ) x y z w",
-            vec![
-                ("E", "T E'"),
-                ("E'", "+ T E' | "),
-                ("T", "F T'"),
-                ("T'", "* F T' | "),
-                ("F", "( E ) | ID"),
-                ("ID", "w | x | y | z"),
-            ],
-            "E",
-        ),
-        build_grammar(
-            "E T F ID",
-            "+ * ( ) w x y z",
-            vec![
-                ("E", "E + T | T"),
-                ("T", "T * F | F"),
-                ("F", "( E ) | ID"),
-                ("ID", "w | x | y | z"),
-            ],
-            "E",
-        ),
-        build_grammar(
-            "E ID",
-            "+ * ( ) w x y z",
-            vec![("E", "E + E | E * E | ( E ) | ID"), ("ID", "w | x | y | z")],
-            "E",
-        ),
-        build_grammar(
-            "S NP VP PP N V P",
-            "can fish in rivers they",
-            vec![
-                ("S", "NP VP"),
-                ("NP", "N PP | N"),
-                ("PP", "P NP"),
-                ("VP", "VP PP | V VP | V NP | V"),
-                ("N", "can | they | fish | rivers"),
-                ("P", "in"),
-                ("V", "can | fish"),
-            ],
-            "S",
-        ),
-        build_grammar("S", "b", vec![("S", "S S | b")], "S"),
-    ];
+        include_str!("../../grammars/expr_ll1.grammar"),
+        include_str!("../../grammars/expr.grammar"),
+        include_str!("../../grammars/expr_ambiguous.grammar"),
+        include_str!("../../grammars/english.grammar"),
+        include_str!("../../grammars/binary.grammar"),
+    ]
+    .map(|source| source.parse::<Grammar>().unwrap());
     for grammar in grammars[0..3].iter() {
         let string = "w + x * ( y + z ) * w + y * x";
         run(grammar, string);
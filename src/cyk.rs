@@ -0,0 +1,126 @@
+//! CYK parsing. The grammar is first converted to Chomsky normal form, and the
+//! resulting tree is mapped back onto the original grammar.
+
+use std::collections::HashMap;
+
+use crate::{
+    grammar::{
+        transform::{to_cnf, Cnf, Derivation},
+        Grammar, Symbol,
+    },
+    parse_tree::ParseTree,
+};
+
+#[derive(Debug, Clone, Copy)]
+enum Back {
+    /// `A -> a` with the production index.
+    Terminal(usize),
+    /// `A -> B C` with the index into the binary rules and the length of the
+    /// span covered by `B`.
+    Binary { rule: usize, split: usize },
+}
+
+/// A production `lhs -> left right` with the nonterminals as indices.
+#[derive(Debug, Clone, Copy)]
+struct BinaryRule {
+    production: usize,
+    lhs: usize,
+    left: usize,
+    right: usize,
+}
+
+struct Table<'t> {
+    /// `cells[len - 1][start][nt]` is how `nt` derives the `len` tokens from `start`.
+    cells: Vec<Vec<Vec<Option<Back>>>>,
+    rules: &'t [BinaryRule],
+}
+
+impl<'t> Table<'t> {
+    fn derivation(&self, len: usize, start: usize, nt: usize) -> Derivation {
+        match self.cells[len - 1][start][nt].unwrap() {
+            Back::Terminal(production) => Derivation::Node(production, vec![Derivation::Leaf]),
+            Back::Binary { rule, split } => {
+                let rule = self.rules[rule];
+                Derivation::Node(
+                    rule.production,
+                    vec![
+                        self.derivation(split, start, rule.left),
+                        self.derivation(len - split, start + split, rule.right),
+                    ],
+                )
+            }
+        }
+    }
+}
+
+/// Parses `tokens` with a grammar already converted by [`to_cnf`].
+///
+/// If the input is ambiguous, one of the parse trees is returned.
+pub fn parse_with_cnf<'g>(cnf: &Cnf<'g>, tokens: &[&str]) -> Option<ParseTree<'g>> {
+    let grammar = cnf.grammar();
+    if tokens.is_empty() {
+        let idx = grammar
+            .productions()
+            .iter()
+            .position(|p| p.lhs() == grammar.start() && p.rhs().is_empty())?;
+        return Some(cnf.to_original(Derivation::Node(idx, vec![])));
+    }
+
+    let mut terminal_rules = HashMap::<_, Vec<_>>::new();
+    let mut rules = vec![];
+    for (production_idx, production) in grammar.productions().iter().enumerate() {
+        let lhs = production.lhs().index();
+        match *production.rhs() {
+            [Symbol::Terminal(t)] => terminal_rules
+                .entry(t)
+                .or_default()
+                .push((production_idx, lhs)),
+            [Symbol::NonTerminal(left), Symbol::NonTerminal(right)] => rules.push(BinaryRule {
+                production: production_idx,
+                lhs,
+                left: left.index(),
+                right: right.index(),
+            }),
+            [] => {}
+            _ => unreachable!("Production not in Chomsky normal form"),
+        }
+    }
+
+    let n = tokens.len();
+    let mut cells = vec![vec![vec![None; grammar.nonterminal_count()]; n]; n];
+    for (start, token) in tokens.iter().enumerate() {
+        let t = grammar.terminal_id(token)?;
+        for &(production, lhs) in terminal_rules.get(&t).into_iter().flatten() {
+            cells[0][start][lhs].get_or_insert(Back::Terminal(production));
+        }
+    }
+    for len in 2..=n {
+        for start in 0..=n - len {
+            for split in 1..len {
+                for (rule_idx, rule) in rules.iter().enumerate() {
+                    if cells[len - 1][start][rule.lhs].is_none()
+                        && cells[split - 1][start][rule.left].is_some()
+                        && cells[len - split - 1][start + split][rule.right].is_some()
+                    {
+                        cells[len - 1][start][rule.lhs] = Some(Back::Binary {
+                            rule: rule_idx,
+                            split,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    let start = grammar.start().index();
+    cells[n - 1][0][start]?;
+    let table = Table {
+        cells,
+        rules: &rules,
+    };
+    Some(cnf.to_original(table.derivation(n, 0, start)))
+}
+
+pub fn parse<'a>(grammar: &'a Grammar, tokens: &[&str]) -> Option<ParseTree<'a>> {
+    parse_with_cnf(&to_cnf(grammar), tokens)
+}
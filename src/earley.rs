@@ -3,12 +3,13 @@
 use std::collections::HashMap;
 
 use crate::{
+    actions::Actions,
     grammar::{Grammar, Symbol},
     item::Item,
     parse_tree::ParseTree,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 enum HistoryValue {
     Scan {
         prev: (usize, usize),
@@ -25,17 +26,18 @@ enum HistoryValue {
 /// If the inputs are valid, then the tree is built up to the location of the dot
 /// with the root being the nonterminal of the lhs of the production a `current`.
 fn build_trees<'a>(
+    grammar: &'a Grammar,
     states: &[Vec<(Item<'a>, usize)>],
     hist: &HashMap<(usize, usize), Vec<HistoryValue>>,
     current: (usize, usize),
 ) -> Vec<ParseTree<'a>> {
-    let lhs = &states[current.0][current.1].0.production().lhs();
+    let lhs = states[current.0][current.1].0.production().lhs();
 
     // The row for parse tree children in built in reverse
     let mut bag = vec![(current, vec![])];
     let mut parse_trees = vec![];
     while let Some((current, mut row)) = bag.pop() {
-        assert_eq!(lhs, &states[current.0][current.1].0.production().lhs());
+        assert_eq!(lhs, states[current.0][current.1].0.production().lhs());
         if let Some(hists) = hist.get(&current) {
             for history in hists.iter() {
                 match *history {
@@ -48,11 +50,11 @@ fn build_trees<'a>(
                         };
 
                         let mut row = row.clone();
-                        row.push(ParseTree::Terminal(t));
+                        row.push(ParseTree::Terminal(grammar.terminal(t)));
                         bag.push((prev, row));
                     }
                     HistoryValue::Complete { prev, parent } => {
-                        let trees = build_trees(states, hist, parent);
+                        let trees = build_trees(grammar, states, hist, parent);
                         for tree in trees.into_iter() {
                             let mut row = row.clone();
                             row.push(tree);
@@ -70,11 +72,14 @@ fn build_trees<'a>(
     }
     parse_trees
         .into_iter()
-        .map(|row| ParseTree::NonTerminal(lhs, row))
+        .map(|row| ParseTree::NonTerminal(grammar.nonterminal(lhs), row))
         .collect()
 }
 
+/// Returns every parse tree of `tokens`, except those that break the precedence
+/// declarations of the grammar (see [`Grammar::respects_precedence`]).
 pub fn parse<'a>(grammar: &'a Grammar, tokens: &[&str]) -> Vec<ParseTree<'a>> {
+    let tokens = grammar.token_ids(tokens);
     let mut states = vec![vec![]; tokens.len() + 1];
     for production in grammar.productions_from(grammar.start()) {
         states[0].push((Item::new(production), 0));
@@ -97,7 +102,7 @@ pub fn parse<'a>(grammar: &'a Grammar, tokens: &[&str]) -> Vec<ParseTree<'a>> {
                     Symbol::Terminal(symbol) => {
                         // Scan
                         if let Some(&token) = tokens.get(end) {
-                            if token == symbol.0 {
+                            if token == Some(symbol) {
                                 let next = &mut tail[0];
                                 let entry = (item.to_next(), start);
                                 // Only insert into state set if it's not already there.
@@ -124,6 +129,42 @@ pub fn parse<'a>(grammar: &'a Grammar, tokens: &[&str]) -> Vec<ParseTree<'a>> {
                                 current.push(value);
                             }
                         }
+
+                        // If `symbol` is nullable, it may have already been completed at this
+                        // position before the current item was added, in which case the
+                        // complete step never saw the current item as a parent. So complete
+                        // it here with those items. (Items completed later will find the
+                        // current item themselves.)
+                        let mut to_add = vec![];
+                        let earlier = if grammar.is_nullable(symbol) {
+                            &current[..item_idx]
+                        } else {
+                            &[]
+                        };
+                        for (child_idx, &(child, child_start)) in earlier.iter().enumerate() {
+                            if let Item::Complete(child) = child {
+                                if child_start == end && child.production().lhs() == symbol {
+                                    to_add.push((
+                                        (item.to_next(), start),
+                                        HistoryValue::Complete {
+                                            prev: (end, item_idx),
+                                            parent: (end, child_idx),
+                                        },
+                                    ));
+                                }
+                            }
+                        }
+                        for (entry, history_value) in to_add {
+                            let idx =
+                                current.iter().position(|x| x == &entry).unwrap_or_else(|| {
+                                    current.push(entry);
+                                    current.len() - 1
+                                });
+                            let hists = hist.entry((end, idx)).or_default();
+                            if !hists.contains(&history_value) {
+                                hists.push(history_value);
+                            }
+                        }
                     }
                 },
                 Item::Complete(item) => {
@@ -182,28 +223,6 @@ pub fn parse<'a>(grammar: &'a Grammar, tokens: &[&str]) -> Vec<ParseTree<'a>> {
         }
     }
 
-    let mut collect = hist.iter().collect::<Vec<_>>();
-    collect.sort_by_key(|(&k, _)| k);
-    for (&current, hists) in collect {
-        for history in hists.iter() {
-            let (prev, parent) = match *history {
-                HistoryValue::Scan { prev } => (prev, None),
-                HistoryValue::Complete { prev, parent } => (prev, Some(parent)),
-            };
-            println!(
-                "{:?}\t{}\t{}\t{}",
-                (current, prev, parent.unwrap_or((69, 69))),
-                states[current.0][current.1].0,
-                states[prev.0][prev.1].0,
-                if let Some(parent) = parent {
-                    states[parent.0][parent.1].0.to_string()
-                } else {
-                    "".to_string()
-                },
-            );
-        }
-    }
-
     states
         .last()
         .unwrap()
@@ -216,6 +235,20 @@ pub fn parse<'a>(grammar: &'a Grammar, tokens: &[&str]) -> Vec<ParseTree<'a>> {
                 false
             }
         })
-        .flat_map(|(idx, _)| build_trees(&states, &hist, (states.len() - 1, idx)))
+        .flat_map(|(idx, _)| build_trees(grammar, &states, &hist, (states.len() - 1, idx)))
+        .filter(|tree| grammar.respects_precedence(tree))
+        .collect()
+}
+
+/// Like [`parse`], but returns the value of each parse tree, computed with
+/// `actions`.
+pub fn evaluate<T>(actions: &Actions<'_, T>, tokens: &[&str]) -> Vec<T> {
+    parse(actions.grammar(), tokens)
+        .iter()
+        .map(|tree| {
+            actions
+                .evaluate(tree)
+                .expect("Earley parse tree does not match the grammar")
+        })
         .collect()
 }
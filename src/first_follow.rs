@@ -1,18 +1,23 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 
-use crate::grammar::{Grammar, NonTerminal, Symbol, Terminal};
+use crate::grammar::{Grammar, NonTerminalId, Symbol, SymbolId, TerminalId};
 
-pub type FirstSet<'a> = HashMap<&'a NonTerminal, HashSet<Option<&'a Terminal>>>;
-pub type FollowSet<'a> = HashMap<&'a NonTerminal, HashSet<&'a Terminal>>;
+/// Indexed by [`NonTerminalId::index`].
+pub type FirstSet = Vec<HashSet<Option<TerminalId>>>;
+/// Indexed by [`NonTerminalId::index`].
+pub type FollowSet = Vec<HashSet<TerminalId>>;
+/// Sequences of at most k terminals, indexed by [`NonTerminalId::index`]. The
+/// empty sequence represents ε.
+pub type FirstKSet = Vec<HashSet<Vec<TerminalId>>>;
+/// Sequences of at most k terminals, indexed by [`NonTerminalId::index`]. A
+/// sequence shorter than k ends with the end-of-input marker.
+pub type FollowKSet = Vec<HashSet<Vec<TerminalId>>>;
 
 /// Returns first(`rhs`) - {ε} and if ε ∈ first(`rhs`)
-pub fn first_rhs<'a>(
-    rhs: &'a [Symbol<Terminal, NonTerminal>],
-    first: &FirstSet<'a>,
-) -> (HashSet<&'a Terminal>, bool) {
+pub fn first_rhs(rhs: &[SymbolId], first: &FirstSet) -> (HashSet<TerminalId>, bool) {
     let mut set = HashSet::new();
     let mut nullable = true;
-    for symbol in rhs.iter() {
+    for &symbol in rhs.iter() {
         if !nullable {
             break;
         }
@@ -22,8 +27,7 @@ pub fn first_rhs<'a>(
                 set.insert(t);
             }
             Symbol::NonTerminal(nt) => {
-                let elements = first[nt].iter().copied().collect::<Vec<_>>();
-                for element in elements {
+                for &element in first[nt.index()].iter() {
                     if let Some(element) = element {
                         set.insert(element);
                     } else {
@@ -42,18 +46,14 @@ pub fn create_first(grammar: &Grammar) -> FirstSet {
     // first[nt] contains None only if nt is nullable.
     // Relationship should be iff by the end of the fixed point iterations.
     // (But I should probably prove this)
-    let mut first = grammar
-        .nonterminals()
-        .iter()
-        .zip(std::iter::repeat_with(HashSet::new))
-        .collect::<HashMap<_, _>>();
+    let mut first = vec![HashSet::new(); grammar.nonterminal_count()];
 
     let mut changed = true;
     while changed {
         changed = false;
         for production in grammar.productions() {
             let (terminals, nullable) = first_rhs(production.rhs(), &first);
-            let set = first.get_mut(production.lhs()).unwrap();
+            let set = &mut first[production.lhs().index()];
             for terminal in terminals {
                 changed |= set.insert(Some(terminal));
             }
@@ -66,16 +66,19 @@ pub fn create_first(grammar: &Grammar) -> FirstSet {
     first
 }
 
-pub fn create_follow<'a>(grammar: &'a Grammar, first: &FirstSet<'a>) -> FollowSet<'a> {
-    let mut follow = grammar
-        .nonterminals()
+/// Returns the nullable nonterminals, ie. those that derive ε.
+pub fn nullable(first: &FirstSet) -> HashSet<NonTerminalId> {
+    first
         .iter()
-        .zip(std::iter::repeat_with(HashSet::new))
-        .collect::<HashMap<_, _>>();
-    follow
-        .get_mut(grammar.start())
-        .unwrap()
-        .insert(Terminal::eoim());
+        .enumerate()
+        .filter(|(_, set)| set.contains(&None))
+        .map(|(idx, _)| NonTerminalId::new(idx))
+        .collect()
+}
+
+pub fn create_follow(grammar: &Grammar, first: &FirstSet) -> FollowSet {
+    let mut follow = vec![HashSet::new(); grammar.nonterminal_count()];
+    follow[grammar.start().index()].insert(TerminalId::eoim());
     let mut changed = true;
     while changed {
         changed = false;
@@ -83,7 +86,7 @@ pub fn create_follow<'a>(grammar: &'a Grammar, first: &FirstSet<'a>) -> FollowSe
             let lhs = production.lhs();
             let rhs = production.rhs();
 
-            for (i, current) in rhs.iter().enumerate() {
+            for (i, &current) in rhs.iter().enumerate() {
                 let Symbol::NonTerminal(current) = current else {
                     continue;
                 };
@@ -92,10 +95,10 @@ pub fn create_follow<'a>(grammar: &'a Grammar, first: &FirstSet<'a>) -> FollowSe
                 if nullable {
                     // Either the remaining rhs is nullable, or current is the
                     // last symbol in the rhs
-                    terminals.extend(follow[lhs].iter());
+                    terminals.extend(follow[lhs.index()].iter());
                 }
 
-                let set = follow.get_mut(current).unwrap();
+                let set = &mut follow[current.index()];
                 for terminal in terminals {
                     changed |= set.insert(terminal);
                 }
@@ -105,40 +108,156 @@ pub fn create_follow<'a>(grammar: &'a Grammar, first: &FirstSet<'a>) -> FollowSe
     follow
 }
 
-pub fn first_to_string(first: &FirstSet) -> String {
+/// The k-concatenation of `left` and `right`: every sequence of `left`
+/// followed by every sequence of `right`, cut off after `k` terminals.
+pub fn concat_k(
+    left: &HashSet<Vec<TerminalId>>,
+    right: &HashSet<Vec<TerminalId>>,
+    k: usize,
+) -> HashSet<Vec<TerminalId>> {
+    let mut set = HashSet::new();
+    for l in left.iter() {
+        if l.len() >= k {
+            set.insert(l[..k].to_vec());
+            continue;
+        }
+        for r in right.iter() {
+            let mut sequence = l.clone();
+            sequence.extend(r.iter().take(k - l.len()));
+            set.insert(sequence);
+        }
+    }
+    set
+}
+
+/// Returns first_k(`rhs`), which contains the empty sequence if `rhs` is
+/// nullable.
+pub fn first_rhs_k(rhs: &[SymbolId], first: &FirstKSet, k: usize) -> HashSet<Vec<TerminalId>> {
+    let mut set = HashSet::from([vec![]]);
+    for &symbol in rhs.iter() {
+        if set.iter().all(|sequence| sequence.len() >= k) {
+            break;
+        }
+        set = match symbol {
+            Symbol::Terminal(t) => concat_k(&set, &HashSet::from([vec![t]]), k),
+            Symbol::NonTerminal(nt) => concat_k(&set, &first[nt.index()], k),
+        };
+    }
+    set
+}
+
+/// Like [`create_first`], but with the sequences of at most `k` terminals that
+/// the derivations of each nonterminal start with. For `k = 1`, the sets are
+/// those of [`create_first`] with ε as the empty sequence.
+///
+/// # Panics
+///
+/// If `k` is 0.
+pub fn create_first_k(grammar: &Grammar, k: usize) -> FirstKSet {
+    assert!(k > 0, "k must be at least 1");
+    let mut first = vec![HashSet::new(); grammar.nonterminal_count()];
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for production in grammar.productions() {
+            let sequences = first_rhs_k(production.rhs(), &first, k);
+            let set = &mut first[production.lhs().index()];
+            for sequence in sequences {
+                changed |= set.insert(sequence);
+            }
+        }
+    }
+
     first
+}
+
+/// Like [`create_follow`], but with the sequences of at most `k` terminals that
+/// can follow each nonterminal, given the sets of [`create_first_k`] for the
+/// same `k`. The start symbol is followed by the end-of-input marker, so for
+/// `k = 1` the sets are those of [`create_follow`].
+///
+/// # Panics
+///
+/// If `k` is 0, as the marker would not fit in a sequence.
+pub fn create_follow_k(grammar: &Grammar, first: &FirstKSet, k: usize) -> FollowKSet {
+    assert!(k > 0, "k must be at least 1");
+    let mut follow = vec![HashSet::new(); grammar.nonterminal_count()];
+    follow[grammar.start().index()].insert(vec![TerminalId::eoim()]);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for production in grammar.productions() {
+            let lhs = production.lhs();
+            let rhs = production.rhs();
+
+            for (i, &current) in rhs.iter().enumerate() {
+                let Symbol::NonTerminal(current) = current else {
+                    continue;
+                };
+                let rest = first_rhs_k(&rhs[i + 1..], first, k);
+                let sequences = concat_k(&rest, &follow[lhs.index()], k);
+
+                let set = &mut follow[current.index()];
+                for sequence in sequences {
+                    changed |= set.insert(sequence);
+                }
+            }
+        }
+    }
+    follow
+}
+
+/// Renders one line per declared nonterminal, with the terminals in id order.
+fn sets_to_string<T: Clone + Ord>(
+    grammar: &Grammar,
+    sets: &[HashSet<T>],
+    name: impl Fn(T) -> String,
+) -> String {
+    grammar
+        .nonterminals()
         .iter()
-        .map(|(k, v)| {
-            let v = v
-                .iter()
-                .map(|s| {
-                    if let Some(s) = s {
-                        s.to_string()
-                    } else {
-                        "ε".to_string()
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join(", ");
-            format!("{}: {}", k, v)
+        .map(|&nt| {
+            let mut v = sets[nt.index()].iter().cloned().collect::<Vec<_>>();
+            v.sort();
+            let v = v.into_iter().map(&name).collect::<Vec<_>>().join(", ");
+            format!("{}: {}", grammar.nonterminal(nt), v)
         })
         .chain(std::iter::once("".to_string()))
         .collect::<Vec<_>>()
         .join("\n")
 }
 
-pub fn follow_to_string(follow: &FollowSet) -> String {
-    follow
+pub fn first_to_string(grammar: &Grammar, first: &FirstSet) -> String {
+    sets_to_string(grammar, first, |s| {
+        if let Some(s) = s {
+            grammar.terminal(s).to_string()
+        } else {
+            "ε".to_string()
+        }
+    })
+}
+
+pub fn follow_to_string(grammar: &Grammar, follow: &FollowSet) -> String {
+    sets_to_string(grammar, follow, |t| grammar.terminal(t).to_string())
+}
+
+/// Writes a sequence of terminals separated by spaces, or ε if it is empty.
+fn sequence_to_string(grammar: &Grammar, sequence: &[TerminalId]) -> String {
+    if sequence.is_empty() {
+        return "ε".to_string();
+    }
+    sequence
         .iter()
-        .map(|(k, v)| {
-            let v = v
-                .iter()
-                .map(ToString::to_string)
-                .collect::<Vec<_>>()
-                .join(", ");
-            format!("{}: {}", k, v)
-        })
-        .chain(std::iter::once("".to_string()))
+        .map(|&t| grammar.terminal(t).to_string())
         .collect::<Vec<_>>()
-        .join("\n")
+        .join(" ")
+}
+
+pub fn first_k_to_string(grammar: &Grammar, first: &FirstKSet) -> String {
+    sets_to_string(grammar, first, |s| sequence_to_string(grammar, &s))
+}
+
+pub fn follow_k_to_string(grammar: &Grammar, follow: &FollowKSet) -> String {
+    sets_to_string(grammar, follow, |s| sequence_to_string(grammar, &s))
 }
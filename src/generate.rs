@@ -0,0 +1,614 @@
+//! Generates sentences of a [`Grammar`], to use as parser inputs in tests.
+//!
+//! [`RandomGenerator`] derives random sentences, and [`enumerate`] lists every
+//! sentence up to a given length. Both can also return the derivation of each
+//! sentence as a [`ParseTree`]. [`production_coverage`] and [`pair_coverage`]
+//! return a few sentences that together exercise the whole grammar.
+
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+
+use crate::{
+    grammar::{transform::Derivation, Grammar, NonTerminalId, Symbol, SymbolId, TerminalId},
+    parse_tree::ParseTree,
+};
+
+/// The height and the number of terminals of the smallest derivation trees, for
+/// every nonterminal and every production. `None` if it derives no terminal
+/// string.
+struct Distances {
+    height: Vec<Option<usize>>,
+    length: Vec<Option<usize>>,
+    /// The first production of each nonterminal in a derivation of
+    /// `length` terminals. Following these from any nonterminal terminates.
+    shortest: Vec<Option<usize>>,
+    production_height: Vec<Option<usize>>,
+    production_length: Vec<Option<usize>>,
+}
+
+impl Distances {
+    fn new(grammar: &Grammar) -> Self {
+        let mut distances = Self {
+            height: vec![None; grammar.nonterminal_count()],
+            length: vec![None; grammar.nonterminal_count()],
+            shortest: vec![None; grammar.nonterminal_count()],
+            production_height: vec![None; grammar.productions().len()],
+            production_length: vec![None; grammar.productions().len()],
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (idx, production) in grammar.productions().iter().enumerate() {
+                let Some((height, length)) = distances.rhs(production.rhs()) else {
+                    continue;
+                };
+                distances.production_height[idx] = Some(height);
+                distances.production_length[idx] = Some(length);
+                let lhs = production.lhs().index();
+                if distances.height[lhs].is_none_or(|best| height < best) {
+                    distances.height[lhs] = Some(height);
+                    changed = true;
+                }
+                // Only a strictly shorter production replaces the current one,
+                // so `shortest` never forms a cycle.
+                if distances.length[lhs].is_none_or(|best| length < best) {
+                    distances.length[lhs] = Some(length);
+                    distances.shortest[lhs] = Some(idx);
+                    changed = true;
+                }
+            }
+        }
+        distances
+    }
+
+    /// The height and length of the smallest derivation of `rhs` as the rhs of
+    /// a production.
+    fn rhs(&self, rhs: &[SymbolId]) -> Option<(usize, usize)> {
+        let mut height = 1;
+        let mut length = 0;
+        for &symbol in rhs {
+            match symbol {
+                Symbol::Terminal(_) => length += 1,
+                Symbol::NonTerminal(nt) => {
+                    height = height.max(1 + self.height[nt.index()]?);
+                    length += self.length[nt.index()]?;
+                }
+            }
+        }
+        Some((height, length))
+    }
+}
+
+/// A xorshift* generator, so that the sentences only depend on the seed.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // One round of splitmix64, so that similar seeds give unrelated
+        // sequences and the state is never 0.
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Self((z ^ (z >> 31)).max(1))
+    }
+
+    /// Returns a number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as usize % n
+    }
+}
+
+/// Derives random sentences from the start symbol.
+///
+/// Every production is picked so that the derivation can still be completed
+/// within `max_depth`, using the height of the smallest derivation tree of
+/// each nonterminal, so generation always terminates. Among those, a
+/// production is picked uniformly from the ones that can keep the sentence
+/// within `target_length` tokens, or from the shortest ones if none can.
+pub struct RandomGenerator<'a> {
+    grammar: &'a Grammar,
+    distances: Distances,
+    rng: Rng,
+    max_depth: usize,
+    target_length: usize,
+}
+
+impl<'a> RandomGenerator<'a> {
+    /// Creates a generator with a `max_depth` of 32 and a `target_length` of 8.
+    pub fn new(grammar: &'a Grammar, seed: u64) -> Self {
+        Self {
+            grammar,
+            distances: Distances::new(grammar),
+            rng: Rng::new(seed),
+            max_depth: 32,
+            target_length: 8,
+        }
+    }
+
+    /// Sets the maximum height of the derivation trees.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn target_length(mut self, target_length: usize) -> Self {
+        self.target_length = target_length;
+        self
+    }
+
+    /// Returns a random sentence, or `None` if the start symbol has no
+    /// derivation within `max_depth`.
+    pub fn generate(&mut self) -> Option<Vec<&'a str>> {
+        self.generate_with_tree().map(|(tokens, _)| tokens)
+    }
+
+    /// Like [`generate`](Self::generate), but also returns the derivation of
+    /// the sentence.
+    pub fn generate_with_tree(&mut self) -> Option<(Vec<&'a str>, ParseTree<'a>)> {
+        let start = self.grammar.start();
+        if self.distances.height[start.index()]? > self.max_depth {
+            return None;
+        }
+        let mut tokens = vec![];
+        let derivation = self.expand(start, self.max_depth, 0, &mut tokens);
+        let tokens = tokens
+            .into_iter()
+            .map(|t| self.grammar.terminal(t).0.as_str())
+            .collect();
+        Some((tokens, derivation.to_parse_tree(self.grammar)))
+    }
+
+    /// Derives `nt` with a tree of at most `depth` height, appending the
+    /// terminals to `tokens`. At least `pending` more terminals will follow.
+    fn expand(
+        &mut self,
+        nt: NonTerminalId,
+        depth: usize,
+        pending: usize,
+        tokens: &mut Vec<TerminalId>,
+    ) -> Derivation {
+        let grammar = self.grammar;
+        let candidates = grammar
+            .production_indices(nt)
+            .iter()
+            .copied()
+            .filter(|&idx| {
+                self.distances.production_height[idx].is_some_and(|height| height <= depth)
+            })
+            .collect::<Vec<_>>();
+        let length = |idx: usize| self.distances.production_length[idx].unwrap();
+        let mut choices = candidates
+            .iter()
+            .copied()
+            .filter(|&idx| tokens.len() + pending + length(idx) <= self.target_length)
+            .collect::<Vec<_>>();
+        if choices.is_empty() {
+            let shortest = candidates.iter().map(|&idx| length(idx)).min().unwrap();
+            choices = candidates
+                .into_iter()
+                .filter(|&idx| length(idx) == shortest)
+                .collect();
+        }
+        let idx = choices[self.rng.below(choices.len())];
+
+        let rhs = grammar.productions()[idx].rhs();
+        let mut children = vec![];
+        for (i, &symbol) in rhs.iter().enumerate() {
+            children.push(match symbol {
+                Symbol::Terminal(t) => {
+                    tokens.push(t);
+                    Derivation::Leaf
+                }
+                Symbol::NonTerminal(child) => {
+                    let rest = self.distances.rhs(&rhs[i + 1..]).unwrap().1;
+                    self.expand(child, depth - 1, pending + rest, tokens)
+                }
+            });
+        }
+        Derivation::Node(idx, children)
+    }
+}
+
+/// The first step of one derivation of a string.
+#[derive(Clone, PartialEq, Eq)]
+struct Witness {
+    production: usize,
+    /// The number of terminals derived by each rhs symbol of the production.
+    lengths: Vec<usize>,
+}
+
+/// How a nonterminal derives a string.
+#[derive(Clone)]
+pub(crate) struct Derivations {
+    /// One or two different first steps. Building the first one's children
+    /// from the first steps of their strings always terminates.
+    witnesses: Vec<Witness>,
+    /// Whether the string has more than one derivation.
+    pub(crate) ambiguous: bool,
+}
+
+/// Every string of at most `max_length` terminals that each nonterminal
+/// derives.
+pub(crate) struct Sentences {
+    pub(crate) strings: Vec<HashMap<Vec<TerminalId>, Derivations>>,
+}
+
+impl Sentences {
+    pub(crate) fn new(grammar: &Grammar, max_length: usize) -> Self {
+        let mut strings = vec![HashMap::new(); grammar.nonterminal_count()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (idx, production) in grammar.productions().iter().enumerate() {
+                // Every way to derive a prefix of the rhs, with the lengths of
+                // the parts and whether one of them is ambiguous.
+                let mut prefixes = vec![(vec![], vec![], false)];
+                for symbol in production.rhs() {
+                    let parts = match symbol {
+                        Symbol::Terminal(t) => vec![(std::slice::from_ref(t), false)],
+                        Symbol::NonTerminal(nt) => strings[nt.index()]
+                            .iter()
+                            .map(|(string, derivations): (&Vec<_>, &Derivations)| {
+                                (string.as_slice(), derivations.ambiguous)
+                            })
+                            .collect(),
+                    };
+                    let mut extended = vec![];
+                    for (prefix, lengths, ambiguous) in &prefixes {
+                        for (part, part_ambiguous) in parts
+                            .iter()
+                            .filter(|(part, _)| prefix.len() + part.len() <= max_length)
+                        {
+                            let mut prefix: Vec<TerminalId> = prefix.clone();
+                            prefix.extend_from_slice(part);
+                            let mut lengths: Vec<usize> = lengths.clone();
+                            lengths.push(part.len());
+                            extended.push((prefix, lengths, *ambiguous || *part_ambiguous));
+                        }
+                    }
+                    prefixes = extended;
+                }
+                let lhs = &mut strings[production.lhs().index()];
+                for (string, lengths, ambiguous) in prefixes {
+                    let witness = Witness {
+                        production: idx,
+                        lengths,
+                    };
+                    match lhs.entry(string) {
+                        Entry::Vacant(entry) => {
+                            entry.insert(Derivations {
+                                witnesses: vec![witness],
+                                ambiguous,
+                            });
+                            changed = true;
+                        }
+                        Entry::Occupied(mut entry) => {
+                            let derivations = entry.get_mut();
+                            let new = !derivations.witnesses.contains(&witness);
+                            if new && derivations.witnesses.len() < 2 {
+                                derivations.witnesses.push(witness);
+                            }
+                            if (new || ambiguous) && !derivations.ambiguous {
+                                derivations.ambiguous = true;
+                                changed = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Self { strings }
+    }
+
+    pub(crate) fn derivation(
+        &self,
+        grammar: &Grammar,
+        nt: NonTerminalId,
+        string: &[TerminalId],
+    ) -> Derivation {
+        let derivations = &self.strings[nt.index()][string];
+        self.apply(grammar, &derivations.witnesses[0], string, None)
+    }
+
+    /// Returns a derivation of an ambiguous string that differs from
+    /// [`derivation`](Self::derivation).
+    pub(crate) fn alternative_derivation(
+        &self,
+        grammar: &Grammar,
+        nt: NonTerminalId,
+        string: &[TerminalId],
+    ) -> Derivation {
+        let derivations = &self.strings[nt.index()][string];
+        assert!(
+            derivations.ambiguous,
+            "Unambiguous string has no alternative"
+        );
+        match &derivations.witnesses[..] {
+            [_, second] => self.apply(grammar, second, string, None),
+            // The string was ambiguous before it had a second first step, so a
+            // child of the first one is ambiguous. It was derived before this
+            // string, so the recursion terminates.
+            [first] => {
+                let ambiguous = self
+                    .parts(grammar, first, string)
+                    .position(|(symbol, part)| match symbol {
+                        Symbol::Terminal(_) => false,
+                        Symbol::NonTerminal(child) => self.strings[child.index()][part].ambiguous,
+                    })
+                    .unwrap();
+                self.apply(grammar, first, string, Some(ambiguous))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// The rhs symbols of the production of `witness` with the parts of
+    /// `string` they derive.
+    fn parts<'s>(
+        &self,
+        grammar: &'s Grammar,
+        witness: &'s Witness,
+        string: &'s [TerminalId],
+    ) -> impl Iterator<Item = (SymbolId, &'s [TerminalId])> {
+        let mut start = 0;
+        grammar.productions()[witness.production]
+            .rhs()
+            .iter()
+            .zip(&witness.lengths)
+            .map(move |(&symbol, &length)| {
+                let part = &string[start..start + length];
+                start += length;
+                (symbol, part)
+            })
+    }
+
+    /// Derives `string` starting with `witness`, using the alternative
+    /// derivation for the child at position `alternative`.
+    fn apply(
+        &self,
+        grammar: &Grammar,
+        witness: &Witness,
+        string: &[TerminalId],
+        alternative: Option<usize>,
+    ) -> Derivation {
+        let children = self
+            .parts(grammar, witness, string)
+            .enumerate()
+            .map(|(i, (symbol, part))| match symbol {
+                Symbol::Terminal(_) => Derivation::Leaf,
+                Symbol::NonTerminal(child) if alternative == Some(i) => {
+                    self.alternative_derivation(grammar, child, part)
+                }
+                Symbol::NonTerminal(child) => self.derivation(grammar, child, part),
+            })
+            .collect();
+        Derivation::Node(witness.production, children)
+    }
+}
+
+/// Returns every sentence of at most `max_length` tokens, shortest first and
+/// sentences of the same length in lexicographic order of the terminal names.
+pub fn enumerate(grammar: &Grammar, max_length: usize) -> Vec<Vec<&str>> {
+    enumerate_with_trees(grammar, max_length)
+        .into_iter()
+        .map(|(tokens, _)| tokens)
+        .collect()
+}
+
+/// Like [`enumerate`], but also returns a derivation of each sentence. For an
+/// ambiguous sentence only one of its parse trees is returned.
+pub fn enumerate_with_trees(
+    grammar: &Grammar,
+    max_length: usize,
+) -> Vec<(Vec<&str>, ParseTree<'_>)> {
+    let sentences = Sentences::new(grammar, max_length);
+    let start = grammar.start();
+    let mut strings = sentences.strings[start.index()]
+        .keys()
+        .map(|string| {
+            let tokens = string
+                .iter()
+                .map(|&t| grammar.terminal(t).0.as_str())
+                .collect::<Vec<_>>();
+            (tokens, string)
+        })
+        .collect::<Vec<_>>();
+    strings.sort_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+    strings
+        .into_iter()
+        .map(|(tokens, string)| {
+            let tree = sentences
+                .derivation(grammar, start, string)
+                .to_parse_tree(grammar);
+            (tokens, tree)
+        })
+        .collect()
+}
+
+/// Builds sentences that use productions which are not covered yet.
+struct Coverage<'a> {
+    grammar: &'a Grammar,
+    distances: Distances,
+    /// The production and rhs position through which each nonterminal is
+    /// reached with the fewest terminals around it. `None` for the start
+    /// symbol and for unreachable nonterminals.
+    parent: Vec<Option<(usize, usize)>>,
+    reachable: Vec<bool>,
+    productions: Vec<bool>,
+    /// The covered pairs of a parent production and a production of one of its
+    /// rhs nonterminals, if pairs are to be covered.
+    pairs: Option<HashSet<(usize, usize)>>,
+}
+
+impl<'a> Coverage<'a> {
+    fn new(grammar: &'a Grammar, pairs: bool) -> Self {
+        let distances = Distances::new(grammar);
+        let mut cost = vec![None; grammar.nonterminal_count()];
+        let mut parent = vec![None; grammar.nonterminal_count()];
+        cost[grammar.start().index()] = Some(0);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (idx, production) in grammar.productions().iter().enumerate() {
+                let (Some(lhs_cost), Some(length)) = (
+                    cost[production.lhs().index()],
+                    distances.production_length[idx],
+                ) else {
+                    continue;
+                };
+                for (pos, &symbol) in production.rhs().iter().enumerate() {
+                    let Symbol::NonTerminal(nt) = symbol else {
+                        continue;
+                    };
+                    let new = lhs_cost + length - distances.length[nt.index()].unwrap();
+                    if cost[nt.index()].is_none_or(|cost| new < cost) {
+                        cost[nt.index()] = Some(new);
+                        parent[nt.index()] = Some((idx, pos));
+                        changed = true;
+                    }
+                }
+            }
+        }
+        Self {
+            grammar,
+            distances,
+            parent,
+            reachable: cost.iter().map(Option::is_some).collect(),
+            productions: vec![false; grammar.productions().len()],
+            pairs: pairs.then(HashSet::new),
+        }
+    }
+
+    /// Returns if `idx` is used in some derivation of a sentence.
+    fn is_usable(&self, idx: usize) -> bool {
+        let production = &self.grammar.productions()[idx];
+        self.reachable[production.lhs().index()] && self.distances.production_length[idx].is_some()
+    }
+
+    /// Returns if applying `idx` below `parent` would cover something new.
+    fn is_new(&self, parent: Option<usize>, idx: usize) -> bool {
+        !self.productions[idx]
+            || self
+                .pairs
+                .as_ref()
+                .zip(parent)
+                .is_some_and(|(pairs, parent)| !pairs.contains(&(parent, idx)))
+    }
+
+    /// The steps from the start symbol to `nt`, as productions and the rhs
+    /// position of the next step.
+    fn path(&self, nt: NonTerminalId) -> Vec<(usize, usize)> {
+        let mut path = vec![];
+        let mut nt = nt;
+        while let Some((idx, pos)) = self.parent[nt.index()] {
+            path.push((idx, pos));
+            nt = self.grammar.productions()[idx].lhs();
+        }
+        path.reverse();
+        path
+    }
+
+    /// Adds a sentence to `sentences` for every usable production that is not
+    /// covered yet.
+    fn cover_productions(&mut self, sentences: &mut Vec<Vec<&'a str>>) {
+        for idx in 0..self.grammar.productions().len() {
+            if self.is_usable(idx) && !self.productions[idx] {
+                let path = self.path(self.grammar.productions()[idx].lhs());
+                sentences.push(self.sentence(&path, idx));
+            }
+        }
+    }
+
+    /// Derives a sentence through `path` that applies `target` at its end.
+    fn sentence(&mut self, path: &[(usize, usize)], target: usize) -> Vec<&'a str> {
+        let mut tokens = vec![];
+        self.derive(self.grammar.start(), None, path, Some(target), &mut tokens);
+        tokens
+    }
+
+    /// Derives `nt` below the production `parent`, following `path` and then
+    /// applying `target`. Elsewhere it picks the first production that covers
+    /// something new, or else the shortest one.
+    fn derive(
+        &mut self,
+        nt: NonTerminalId,
+        parent: Option<usize>,
+        path: &[(usize, usize)],
+        target: Option<usize>,
+        tokens: &mut Vec<&'a str>,
+    ) {
+        let grammar = self.grammar;
+        let (idx, next) = match (path.split_first(), target) {
+            (Some((&(idx, pos), rest)), _) => (idx, Some((pos, rest))),
+            (None, Some(idx)) => (idx, None),
+            (None, None) => {
+                let idx = grammar
+                    .production_indices(nt)
+                    .iter()
+                    .copied()
+                    .find(|&idx| {
+                        self.distances.production_length[idx].is_some() && self.is_new(parent, idx)
+                    })
+                    .or(self.distances.shortest[nt.index()])
+                    .unwrap();
+                (idx, None)
+            }
+        };
+        self.productions[idx] = true;
+        if let (Some(pairs), Some(parent)) = (&mut self.pairs, parent) {
+            pairs.insert((parent, idx));
+        }
+        for (i, &symbol) in grammar.productions()[idx].rhs().iter().enumerate() {
+            match symbol {
+                Symbol::Terminal(t) => tokens.push(&grammar.terminal(t).0),
+                Symbol::NonTerminal(child) => match next {
+                    Some((pos, rest)) if pos == i => {
+                        self.derive(child, Some(idx), rest, target, tokens)
+                    }
+                    _ => self.derive(child, Some(idx), &[], None, tokens),
+                },
+            }
+        }
+    }
+}
+
+/// Returns a small set of sentences that together use every production that
+/// is used by any sentence.
+///
+/// Each sentence is built for a production that is not covered yet, and
+/// expands the other nonterminals with uncovered productions where it can, in
+/// the spirit of Purdom's algorithm.
+pub fn production_coverage(grammar: &Grammar) -> Vec<Vec<&str>> {
+    let mut sentences = vec![];
+    Coverage::new(grammar, false).cover_productions(&mut sentences);
+    sentences
+}
+
+/// Like [`production_coverage`], but the sentences also use every pair of a
+/// production and a production of one of its rhs nonterminals that can appear
+/// as parent and child in a parse tree.
+pub fn pair_coverage(grammar: &Grammar) -> Vec<Vec<&str>> {
+    let mut coverage = Coverage::new(grammar, true);
+    let mut sentences = vec![];
+    for (parent, production) in grammar.productions().iter().enumerate() {
+        if !coverage.is_usable(parent) {
+            continue;
+        }
+        for (pos, &symbol) in production.rhs().iter().enumerate() {
+            let Symbol::NonTerminal(nt) = symbol else {
+                continue;
+            };
+            for &child in grammar.production_indices(nt) {
+                if coverage.is_usable(child) && coverage.is_new(Some(parent), child) {
+                    let mut path = coverage.path(production.lhs());
+                    path.push((parent, pos));
+                    sentences.push(coverage.sentence(&path, child));
+                }
+            }
+        }
+    }
+    coverage.cover_productions(&mut sentences);
+    sentences
+}
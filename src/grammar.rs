@@ -1,6 +1,35 @@
-use std::{collections::HashSet, fmt, sync::OnceLock};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::OnceLock,
+};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+mod builder;
+pub mod export;
+mod import;
+mod index;
+mod precedence;
+#[cfg(feature = "serde")]
+mod serialize;
+mod symbol_table;
+pub mod text;
+pub mod transform;
+pub mod validate;
+pub mod yacc;
+
+pub use builder::{GrammarBuilder, GrammarError};
+pub use precedence::{Associativity, Precedence};
+
+use index::Index;
+use precedence::Precedences;
+use symbol_table::SymbolTable;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct Terminal(pub String);
 
 impl fmt::Display for Terminal {
@@ -9,7 +38,12 @@ impl fmt::Display for Terminal {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct NonTerminal(pub String);
 
 impl fmt::Display for NonTerminal {
@@ -18,7 +52,13 @@ impl fmt::Display for NonTerminal {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// With the `serde` feature, this is `{"terminal": t}` or `{"nonterminal": n}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum Symbol<T, N> {
     Terminal(T),
     NonTerminal(N),
@@ -69,70 +109,402 @@ impl Symbol<&Terminal, &NonTerminal> {
     }
 }
 
+/// Index of a terminal in the symbol table of a [`Grammar`]. Serialized as the
+/// bare number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct TerminalId(u32);
+
+/// Index of a nonterminal in the symbol table of a [`Grammar`]. Serialized as the
+/// bare number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct NonTerminalId(u32);
+
+impl TerminalId {
+    pub(crate) fn new(index: usize) -> Self {
+        Self(index.try_into().expect("Too many terminals"))
+    }
+
+    /// End-of-input marker
+    pub fn eoim() -> Self {
+        Self(0)
+    }
+
+    /// Ids of a grammar are dense, so this can index a `Vec` of length
+    /// [`Grammar::terminal_count`].
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl NonTerminalId {
+    pub(crate) fn new(index: usize) -> Self {
+        Self(index.try_into().expect("Too many nonterminals"))
+    }
+
+    /// Ids of a grammar are dense, so this can index a `Vec` of length
+    /// [`Grammar::nonterminal_count`].
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+pub type SymbolId = Symbol<TerminalId, NonTerminalId>;
+
+/// With the `serde` feature, this is `{"lhs": 3, "rhs": [{"terminal": 1}, ...]}`.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Production {
-    lhs: NonTerminal,
-    rhs: Vec<Symbol<Terminal, NonTerminal>>,
+    lhs: NonTerminalId,
+    rhs: Vec<SymbolId>,
 }
 
 impl Production {
-    pub fn lhs(&self) -> &NonTerminal {
-        &self.lhs
+    pub(crate) fn new(lhs: NonTerminalId, rhs: Vec<SymbolId>) -> Self {
+        Self { lhs, rhs }
     }
 
-    pub fn rhs(&self) -> &[Symbol<Terminal, NonTerminal>] {
+    pub fn lhs(&self) -> NonTerminalId {
+        self.lhs
+    }
+
+    pub fn rhs(&self) -> &[SymbolId] {
         &self.rhs
     }
+
+    /// Returns something that displays the production with the symbol names of
+    /// `grammar`.
+    pub fn display<'a>(&'a self, grammar: &'a Grammar) -> DisplayProduction<'a> {
+        DisplayProduction {
+            production: self,
+            grammar,
+        }
+    }
 }
 
-impl fmt::Display for Production {
+pub struct DisplayProduction<'a> {
+    production: &'a Production,
+    grammar: &'a Grammar,
+}
+
+impl<'a> fmt::Display for DisplayProduction<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
             "{} -> {}",
-            self.lhs(),
-            self.rhs
+            self.grammar.nonterminal(self.production.lhs()),
+            self.production
+                .rhs()
                 .iter()
-                .map(ToString::to_string)
+                .map(|&symbol| self.grammar.symbol(symbol).to_string())
                 .collect::<Vec<_>>()
                 .join(" ")
         )
     }
 }
 
+/// A context free grammar.
+///
+/// Symbols are interned into a symbol table, and everything else refers to them
+/// by [`TerminalId`] and [`NonTerminalId`]. The table may contain symbols that
+/// are not declared, such as a terminal that is only used on a rhs; see
+/// [`validate`](Self::validate).
+///
+/// # Serialization
+///
+/// With the `serde` feature, a grammar is serialized with its whole symbol table
+/// so that every id survives a round trip, and tables such as
+/// [`LL1Table`](crate::ll1::LL1Table) built for it stay valid:
+///
+/// ```json
+/// {
+///   "terminals": ["$", "+", "x"],
+///   "nonterminals": ["E", "T"],
+///   "declared_terminals": [1, 2],
+///   "declared_nonterminals": [0, 1],
+///   "productions": [
+///     {"lhs": 0, "rhs": [{"nonterminal": 1}, {"terminal": 1}, {"nonterminal": 0}]},
+///     {"lhs": 0, "rhs": [{"nonterminal": 1}]},
+///     {"lhs": 1, "rhs": [{"terminal": 2}]}
+///   ],
+///   "start": 0,
+///   "synthetic": []
+/// }
+/// ```
+///
+/// `terminals` and `nonterminals` list the names of the symbol table in id
+/// order, so the id of a symbol is its index; terminal 0 is always the
+/// end-of-input marker `$`. `declared_terminals`, `declared_nonterminals` and
+/// `synthetic` are sorted. Deserializing checks that every id is in range.
+///
+/// A grammar with precedence declarations also has `"precedence"`, a list of
+/// levels such as `["left", [1, 2]]`, and `"prec"`, a sorted list of pairs of a
+/// production index and its `%prec` terminal.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "serialize::GrammarData", try_from = "serialize::GrammarData")
+)]
 pub struct Grammar {
-    nonterminals: HashSet<NonTerminal>,
-    terminals: HashSet<Terminal>,
+    symbols: SymbolTable,
+    /// Declared nonterminals, sorted.
+    nonterminals: Vec<NonTerminalId>,
+    /// Declared terminals, sorted.
+    terminals: Vec<TerminalId>,
     productions: Vec<Production>,
-    start: NonTerminal,
+    start: NonTerminalId,
+    synthetic: HashSet<NonTerminalId>,
+    index: Index,
+    precedence: Precedences,
+    /// The parameterised form of nonterminals that instantiate a template, such
+    /// as `list(Expr)` for `list_Expr`.
+    display_names: HashMap<NonTerminalId, String>,
 }
 
 impl Grammar {
-    pub fn nonterminals(&self) -> &HashSet<NonTerminal> {
+    pub(crate) fn from_parts(
+        symbols: SymbolTable,
+        nonterminals: impl IntoIterator<Item = NonTerminalId>,
+        terminals: impl IntoIterator<Item = TerminalId>,
+        productions: Vec<Production>,
+        start: NonTerminalId,
+        synthetic: HashSet<NonTerminalId>,
+    ) -> Self {
+        let mut nonterminals = nonterminals.into_iter().collect::<Vec<_>>();
+        nonterminals.sort();
+        nonterminals.dedup();
+        let mut terminals = terminals.into_iter().collect::<Vec<_>>();
+        terminals.sort();
+        terminals.dedup();
+        let index = Index::new(symbols.nonterminal_count(), &productions);
+        Self {
+            symbols,
+            nonterminals,
+            terminals,
+            productions,
+            start,
+            synthetic,
+            index,
+            precedence: Precedences::default(),
+            display_names: HashMap::new(),
+        }
+    }
+
+    /// Interns the given names in order and builds a grammar out of them.
+    pub(crate) fn from_named(
+        nonterminals: impl IntoIterator<Item = NonTerminal>,
+        terminals: impl IntoIterator<Item = Terminal>,
+        productions: impl IntoIterator<Item = (NonTerminal, Vec<Symbol<Terminal, NonTerminal>>)>,
+        start: NonTerminal,
+        synthetic: impl IntoIterator<Item = NonTerminal>,
+    ) -> Self {
+        let mut symbols = SymbolTable::new();
+        let terminals = terminals
+            .into_iter()
+            .map(|t| symbols.intern_terminal(&t.0))
+            .collect::<Vec<_>>();
+        let nonterminals = nonterminals
+            .into_iter()
+            .map(|nt| symbols.intern_nonterminal(&nt.0))
+            .collect::<Vec<_>>();
+        let productions = productions
+            .into_iter()
+            .map(|(lhs, rhs)| Production {
+                lhs: symbols.intern_nonterminal(&lhs.0),
+                rhs: rhs
+                    .iter()
+                    .map(|symbol| match symbol {
+                        Symbol::Terminal(t) => Symbol::Terminal(symbols.intern_terminal(&t.0)),
+                        Symbol::NonTerminal(nt) => {
+                            Symbol::NonTerminal(symbols.intern_nonterminal(&nt.0))
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        let start = symbols.intern_nonterminal(&start.0);
+        let synthetic = synthetic
+            .into_iter()
+            .map(|nt| symbols.intern_nonterminal(&nt.0))
+            .collect();
+        Self::from_parts(
+            symbols,
+            nonterminals,
+            terminals,
+            productions,
+            start,
+            synthetic,
+        )
+    }
+
+    pub(crate) fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// The declared nonterminals, sorted by id.
+    pub fn nonterminals(&self) -> &[NonTerminalId] {
         &self.nonterminals
     }
 
-    pub fn terminals(&self) -> &HashSet<Terminal> {
+    /// The declared terminals, sorted by id.
+    pub fn terminals(&self) -> &[TerminalId] {
         &self.terminals
     }
 
+    /// Returns if `nt` is a declared nonterminal.
+    pub fn is_nonterminal(&self, nt: NonTerminalId) -> bool {
+        self.nonterminals.binary_search(&nt).is_ok()
+    }
+
+    /// Returns if `t` is a declared terminal.
+    pub fn is_terminal(&self, t: TerminalId) -> bool {
+        self.terminals.binary_search(&t).is_ok()
+    }
+
     pub fn productions(&self) -> &[Production] {
         &self.productions
     }
 
-    pub fn productions_from(&self, state: &NonTerminal) -> Vec<&Production> {
+    /// The productions with `state` on the lhs, in order.
+    pub fn productions_from(
+        &self,
+        state: NonTerminalId,
+    ) -> impl ExactSizeIterator<Item = &Production> + '_ {
+        self.production_indices(state)
+            .iter()
+            .map(|&idx| &self.productions[idx])
+    }
+
+    /// Indices into [`productions`](Self::productions) of the productions with
+    /// `state` on the lhs, in order.
+    pub fn production_indices(&self, state: NonTerminalId) -> &[usize] {
+        &self.index.productions[state.index()]
+    }
+
+    /// The index of the production written as `production`, in the form of
+    /// [`Production::display`], as in `"E' -> + T E'"`. Whitespace is not
+    /// significant, and an ε-production can be written as `"E' ->"` or
+    /// `"E' -> ε"`.
+    pub fn find_production(&self, production: &str) -> Option<usize> {
+        let words = |s: &str| {
+            s.split_whitespace()
+                .filter(|&word| word != "ε")
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let wanted = words(production);
         self.productions
             .iter()
-            .filter(|&p| &p.lhs == state)
-            .collect()
+            .position(|p| words(&p.display(self).to_string()) == wanted)
+    }
+
+    /// Returns if `nt` derives ε.
+    pub fn is_nullable(&self, nt: NonTerminalId) -> bool {
+        self.index.nullable[nt.index()]
+    }
+
+    /// Returns if `nt` derives at least one string of terminals.
+    pub fn is_productive(&self, nt: NonTerminalId) -> bool {
+        self.index.productive[nt.index()]
+    }
+
+    /// Returns if `nt =>+ nt α` for some `α`, including through nullable
+    /// prefixes.
+    pub fn is_left_recursive(&self, nt: NonTerminalId) -> bool {
+        self.index.left_recursive[nt.index()]
+    }
+
+    pub fn start(&self) -> NonTerminalId {
+        self.start
+    }
+
+    /// Returns if `nt` was generated while desugaring the grammar rather than
+    /// written by the user.
+    pub fn is_synthetic(&self, nt: NonTerminalId) -> bool {
+        self.synthetic.contains(&nt)
+    }
+
+    /// The name of `nt` as the user wrote it: the application of a
+    /// parameterised nonterminal, such as `sep_by(Expr, ",")`, for one of its
+    /// instances, and the plain name otherwise.
+    pub fn display_name(&self, nt: NonTerminalId) -> &str {
+        self.display_names
+            .get(&nt)
+            .unwrap_or(&self.nonterminal(nt).0)
     }
 
-    pub fn start(&self) -> &NonTerminal {
-        &self.start
+    /// Interns the nonterminals of `names` and records their display names.
+    pub(crate) fn set_display_names(
+        &mut self,
+        names: impl IntoIterator<Item = (NonTerminal, String)>,
+    ) {
+        for (nt, name) in names {
+            let nt = self.symbols.intern_nonterminal(&nt.0);
+            self.display_names.insert(nt, name);
+        }
+    }
+
+    pub fn terminal(&self, id: TerminalId) -> &Terminal {
+        self.symbols.terminal(id)
+    }
+
+    pub fn nonterminal(&self, id: NonTerminalId) -> &NonTerminal {
+        self.symbols.nonterminal(id)
+    }
+
+    /// Resolves the names of `symbol`.
+    pub fn symbol(&self, symbol: SymbolId) -> Symbol<&Terminal, &NonTerminal> {
+        match symbol {
+            Symbol::Terminal(t) => Symbol::Terminal(self.terminal(t)),
+            Symbol::NonTerminal(nt) => Symbol::NonTerminal(self.nonterminal(nt)),
+        }
+    }
+
+    /// Looks up a terminal by name. This does not allocate, so it can be used to
+    /// map input tokens to ids.
+    pub fn terminal_id(&self, name: &str) -> Option<TerminalId> {
+        self.symbols.terminal_id(name)
+    }
+
+    pub fn nonterminal_id(&self, name: &str) -> Option<NonTerminalId> {
+        self.symbols.nonterminal_id(name)
+    }
+
+    /// One more than the largest [`TerminalId`] of the grammar, including the
+    /// end-of-input marker and undeclared terminals.
+    pub fn terminal_count(&self) -> usize {
+        self.symbols.terminal_count()
+    }
+
+    /// One more than the largest [`NonTerminalId`] of the grammar, including
+    /// undeclared nonterminals.
+    pub fn nonterminal_count(&self) -> usize {
+        self.symbols.nonterminal_count()
+    }
+
+    /// Maps each token to its terminal id, or `None` if it is not a terminal of
+    /// the grammar.
+    pub fn token_ids(&self, tokens: &[&str]) -> Vec<Option<TerminalId>> {
+        tokens.iter().map(|token| self.terminal_id(token)).collect()
     }
 }
 
+/// Builds a grammar from whitespace separated symbols and `|` separated
+/// alternatives.
+///
+/// Any rhs symbol that is not one of `given_nonterminals` becomes a terminal, even
+/// if it is missing from `terminals`, and no other checks are made. Use
+/// [`GrammarBuilder`] to get an error for malformed grammars instead.
 pub fn build_grammar(
     given_nonterminals: &str,
     terminals: &str,
@@ -143,36 +515,35 @@ pub fn build_grammar(
     let nonterminals = given_nonterminals
         .split_whitespace()
         .map(|nt| NonTerminal(nt.into()))
-        .collect::<HashSet<_>>();
+        .collect::<Vec<_>>();
     let terminals = terminals
         .split_whitespace()
         .map(|t| Terminal(t.into()))
-        .collect();
+        .collect::<Vec<_>>();
     let mut productions = vec![];
     for production in given_productions {
         let lhs = production.0;
         for rhs in production.1.split('|').map(|s| s.trim()) {
-            productions.push(Production {
-                lhs: NonTerminal(lhs.to_string()),
-                rhs: {
-                    rhs.split_whitespace()
-                        .map(|s| {
-                            let s = s.to_string();
-                            if nonterminals.contains(&NonTerminal(s.clone())) {
-                                Symbol::NonTerminal(NonTerminal(s))
-                            } else {
-                                Symbol::Terminal(Terminal(s))
-                            }
-                        })
-                        .collect()
-                },
-            })
+            productions.push((
+                NonTerminal(lhs.to_string()),
+                rhs.split_whitespace()
+                    .map(|s| {
+                        let s = s.to_string();
+                        if nonterminals.contains(&NonTerminal(s.clone())) {
+                            Symbol::NonTerminal(NonTerminal(s))
+                        } else {
+                            Symbol::Terminal(Terminal(s))
+                        }
+                    })
+                    .collect(),
+            ))
         }
     }
-    Grammar {
+    Grammar::from_named(
         nonterminals,
         terminals,
         productions,
-        start: NonTerminal(start.into()),
-    }
+        NonTerminal(start.into()),
+        [],
+    )
 }
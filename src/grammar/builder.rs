@@ -0,0 +1,272 @@
+use std::{collections::HashSet, error, fmt};
+
+use super::{
+    import::{Clash, Imports},
+    Associativity, Grammar, NonTerminal, Symbol, Terminal,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrammarError {
+    /// The lhs of the production at the given index is not a declared nonterminal.
+    UndeclaredLhs {
+        production: usize,
+        symbol: String,
+    },
+    /// A rhs symbol of the production at the given index is declared neither as a
+    /// terminal nor as a nonterminal.
+    UndeclaredSymbol {
+        production: usize,
+        symbol: String,
+    },
+    /// A symbol is declared both as a terminal and as a nonterminal.
+    TerminalAndNonTerminal(String),
+    NoStart,
+    UndeclaredStart(String),
+    /// A precedence level names a symbol that is not a declared terminal.
+    UndeclaredPrecedence(String),
+    /// A symbol of the grammar imported under `namespace` clashes with another
+    /// symbol; see [`GrammarBuilder::import`].
+    ImportClash {
+        namespace: String,
+        symbol: String,
+    },
+}
+
+impl fmt::Display for GrammarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrammarError::UndeclaredLhs { production, symbol } => write!(
+                f,
+                "lhs `{}` of production {} is not a declared nonterminal",
+                symbol, production
+            ),
+            GrammarError::UndeclaredSymbol { production, symbol } => write!(
+                f,
+                "symbol `{}` in production {} is not declared",
+                symbol, production
+            ),
+            GrammarError::TerminalAndNonTerminal(s) => {
+                write!(
+                    f,
+                    "`{}` is declared as both a terminal and a nonterminal",
+                    s
+                )
+            }
+            GrammarError::NoStart => write!(f, "no start symbol was set"),
+            GrammarError::UndeclaredStart(s) => {
+                write!(f, "start symbol `{}` is not a declared nonterminal", s)
+            }
+            GrammarError::UndeclaredPrecedence(s) => {
+                write!(f, "`{}` has a precedence but is not a declared terminal", s)
+            }
+            GrammarError::ImportClash { namespace, symbol } => write!(
+                f,
+                "`{}` from import `{}` clashes with another symbol",
+                symbol, namespace
+            ),
+        }
+    }
+}
+
+impl error::Error for GrammarError {}
+
+impl From<Clash> for GrammarError {
+    fn from(value: Clash) -> Self {
+        GrammarError::ImportClash {
+            namespace: value.namespace,
+            symbol: value.symbol,
+        }
+    }
+}
+
+/// Builds a [`Grammar`] one symbol and production at a time.
+///
+/// Unlike [`build_grammar`](super::build_grammar), every symbol must be declared
+/// before [`build`](Self::build) is called, and mistakes are reported as a
+/// [`GrammarError`] instead of producing a malformed grammar.
+#[derive(Debug, Clone, Default)]
+pub struct GrammarBuilder {
+    terminals: Vec<String>,
+    nonterminals: Vec<String>,
+    productions: Vec<(String, Vec<String>)>,
+    precedence: Vec<(Associativity, Vec<String>)>,
+    imports: Vec<(String, Grammar)>,
+    start: Option<String>,
+}
+
+impl GrammarBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_terminal(&mut self, terminal: impl Into<String>) -> &mut Self {
+        self.terminals.push(terminal.into());
+        self
+    }
+
+    pub fn add_nonterminal(&mut self, nonterminal: impl Into<String>) -> &mut Self {
+        self.nonterminals.push(nonterminal.into());
+        self
+    }
+
+    /// Adds the production `lhs -> rhs`. An empty `rhs` is an ε-production.
+    pub fn add_production<S: Into<String>>(
+        &mut self,
+        lhs: impl Into<String>,
+        rhs: impl IntoIterator<Item = S>,
+    ) -> &mut Self {
+        self.productions
+            .push((lhs.into(), rhs.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Adds a precedence level that binds tighter than the ones added before.
+    pub fn add_precedence<S: Into<String>>(
+        &mut self,
+        associativity: Associativity,
+        terminals: impl IntoIterator<Item = S>,
+    ) -> &mut Self {
+        self.precedence.push((
+            associativity,
+            terminals.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Imports the nonterminals of `grammar` as `namespace.Name`, with their
+    /// productions and precedence declarations. Terminals are shared.
+    ///
+    /// Imported nonterminals count as declared. Adding a production for one
+    /// replaces all of its imported productions, as in
+    /// `builder.add_production("expr.Atom", ["id"])`. A namespace imported twice,
+    /// or a symbol that is a terminal in one grammar and a nonterminal in
+    /// another, is a [`GrammarError::ImportClash`].
+    pub fn import(&mut self, namespace: impl Into<String>, grammar: &Grammar) -> &mut Self {
+        self.imports.push((namespace.into(), grammar.clone()));
+        self
+    }
+
+    pub fn set_start(&mut self, start: impl Into<String>) -> &mut Self {
+        self.start = Some(start.into());
+        self
+    }
+
+    pub fn build(&self) -> Result<Grammar, GrammarError> {
+        let mut imports = Imports::default();
+        for (namespace, grammar) in self.imports.iter() {
+            imports.add(namespace, grammar)?;
+        }
+        imports.check(
+            self.nonterminals.iter().map(String::as_str),
+            self.terminals.iter().map(String::as_str),
+            self.precedence
+                .iter()
+                .flat_map(|(_, level)| level)
+                .map(String::as_str),
+        )?;
+        let imported_terminals = imports
+            .terminals()
+            .into_iter()
+            .map(|t| t.0)
+            .collect::<Vec<_>>();
+        let imported_nonterminals = imports
+            .nonterminals()
+            .into_iter()
+            .map(|nt| nt.0)
+            .collect::<Vec<_>>();
+        let terminals = self
+            .terminals
+            .iter()
+            .chain(imported_terminals.iter())
+            .collect::<HashSet<_>>();
+        let nonterminals = self
+            .nonterminals
+            .iter()
+            .chain(imported_nonterminals.iter())
+            .collect::<HashSet<_>>();
+        if let Some(s) = self.nonterminals.iter().find(|s| terminals.contains(s)) {
+            return Err(GrammarError::TerminalAndNonTerminal(s.clone()));
+        }
+
+        let mut productions = vec![];
+        for (idx, (lhs, rhs)) in self.productions.iter().enumerate() {
+            if !nonterminals.contains(lhs) {
+                return Err(GrammarError::UndeclaredLhs {
+                    production: idx,
+                    symbol: lhs.clone(),
+                });
+            }
+            let rhs = rhs
+                .iter()
+                .map(|s| {
+                    if terminals.contains(s) {
+                        Ok(Symbol::Terminal(Terminal(s.clone())))
+                    } else if nonterminals.contains(s) {
+                        Ok(Symbol::NonTerminal(NonTerminal(s.clone())))
+                    } else {
+                        Err(GrammarError::UndeclaredSymbol {
+                            production: idx,
+                            symbol: s.clone(),
+                        })
+                    }
+                })
+                .collect::<Result<_, _>>()?;
+            productions.push((NonTerminal(lhs.clone()), rhs));
+        }
+
+        let start = self.start.clone().ok_or(GrammarError::NoStart)?;
+        if !nonterminals.contains(&start) {
+            return Err(GrammarError::UndeclaredStart(start));
+        }
+
+        if let Some(s) = self
+            .precedence
+            .iter()
+            .flat_map(|(_, level)| level)
+            .find(|s| !terminals.contains(s))
+        {
+            return Err(GrammarError::UndeclaredPrecedence(s.clone()));
+        }
+
+        let overridden = productions
+            .iter()
+            .map(|(lhs, _)| lhs.clone())
+            .filter(|lhs| imports.is_nonterminal(&lhs.0))
+            .collect();
+        let synthetic = imports.synthetic().collect::<Vec<_>>();
+        let display_names = imports.display_names();
+        let mut overrides = vec![];
+        let mut levels = imports.append(&mut productions, &mut overrides, &overridden);
+        levels.extend(self.precedence.iter().map(|(associativity, level)| {
+            (
+                *associativity,
+                level.iter().cloned().map(Terminal).collect(),
+            )
+        }));
+
+        let mut grammar = Grammar::from_named(
+            self.nonterminals
+                .iter()
+                .chain(imported_nonterminals.iter())
+                .map(|nt| NonTerminal(nt.clone())),
+            self.terminals
+                .iter()
+                .chain(imported_terminals.iter())
+                .map(|t| Terminal(t.clone())),
+            productions,
+            NonTerminal(start),
+            synthetic,
+        );
+        if !levels.is_empty() || !overrides.is_empty() {
+            grammar.set_precedence(levels, overrides);
+        }
+        grammar.set_display_names(display_names);
+        Ok(grammar)
+    }
+}
+
+impl Grammar {
+    pub fn builder() -> GrammarBuilder {
+        GrammarBuilder::new()
+    }
+}
@@ -0,0 +1,318 @@
+//! Renders a [`Grammar`] in the syntax of other parser generators.
+//!
+//! Each tool has its own rules for names, so nonterminals are renamed where
+//! needed (`E'` becomes `E_p`), keeping the names distinct. Nonterminals without
+//! productions are left out.
+
+use std::{collections::HashSet, fmt::Write};
+
+use super::{
+    transform::ordered_nonterminals, Grammar, NonTerminalId, Production, Symbol, TerminalId,
+};
+
+/// Rewrites `name` to only use characters accepted by `keep`, and to start with
+/// a character accepted by `keep_first`.
+fn mangle(name: &str, keep: impl Fn(char) -> bool, keep_first: impl Fn(char) -> bool) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        match c {
+            c if keep(c) => out.push(c),
+            '\'' => out.push_str("_p"),
+            _ => out.push('_'),
+        }
+    }
+    if !out.chars().next().is_some_and(keep_first) {
+        out.insert(0, '_');
+    }
+    out
+}
+
+/// Returns `name`, or `name` followed by the smallest number that makes it a
+/// name not in `taken`, and adds it to `taken`.
+fn unique(name: String, taken: &mut HashSet<String>) -> String {
+    let name = std::iter::once(name.clone())
+        .chain((1..).map(|n| format!("{}{}", name, n)))
+        .find(|name| !taken.contains(name))
+        .unwrap();
+    taken.insert(name.clone());
+    name
+}
+
+/// The nonterminals with productions in output order, each with its
+/// productions.
+fn rules(grammar: &Grammar) -> Vec<(NonTerminalId, Vec<&Production>)> {
+    ordered_nonterminals(grammar)
+        .into_iter()
+        .map(|nt| (nt, grammar.productions_from(nt).collect::<Vec<_>>()))
+        .filter(|(_, productions)| !productions.is_empty())
+        .collect()
+}
+
+/// Names for every nonterminal, indexed by [`NonTerminalId::index`].
+fn nonterminal_names(
+    grammar: &Grammar,
+    taken: &mut HashSet<String>,
+    rename: impl Fn(&str) -> String,
+) -> Vec<String> {
+    (0..grammar.nonterminal_count())
+        .map(|idx| {
+            let nt = grammar.nonterminal(NonTerminalId::new(idx));
+            unique(rename(&nt.0), taken)
+        })
+        .collect()
+}
+
+fn is_c_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Writes `s` between `quote`s, escaping `quote` and `\` with a backslash and
+/// control characters as `\n`, `\r` and `\t`.
+fn escaped(s: &str, quote: char) -> String {
+    let mut out = String::from(quote);
+    for c in s.chars() {
+        match c {
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c == quote || c == '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push(quote);
+    out
+}
+
+/// Renders `grammar` as a bison `.y` file with empty actions.
+///
+/// Terminals that are C identifiers are declared with `%token` and used by
+/// name. Other single character terminals are written as character literals,
+/// and the rest get a `%token TOK_n "..."` declaration and are written as the
+/// string alias. ε-alternatives are written as `%empty`, and the precedence
+/// declarations as `%left`, `%right`, `%nonassoc` and `%prec`.
+pub fn to_bison(grammar: &Grammar) -> String {
+    let is_token_name = |t: &str| is_c_ident(t) && grammar.nonterminal_id(t).is_none();
+    let mut taken = (1..grammar.terminal_count())
+        .map(|idx| grammar.terminal(TerminalId::new(idx)).0.clone())
+        .filter(|t| is_token_name(t))
+        .collect::<HashSet<_>>();
+    let mut token_declarations = vec![];
+    // The end-of-input marker is predeclared by bison.
+    let terminals = std::iter::once("YYEOF".to_string())
+        .chain((1..grammar.terminal_count()).map(|idx| {
+            let t = &grammar.terminal(TerminalId::new(idx)).0;
+            let mut chars = t.chars();
+            if is_token_name(t) {
+                token_declarations.push(format!("%token {}", t));
+                t.clone()
+            } else if let (Some(c), None) = (chars.next(), chars.next()) {
+                escaped(&c.to_string(), '\'')
+            } else {
+                let literal = escaped(t, '"');
+                let name = unique(format!("TOK_{}", idx), &mut taken);
+                token_declarations.push(format!("%token {} {}", name, literal));
+                literal
+            }
+        }))
+        .collect::<Vec<_>>();
+    let nonterminals = nonterminal_names(grammar, &mut taken, |name| {
+        mangle(
+            name,
+            |c| c.is_ascii_alphanumeric() || c == '_',
+            |c| c.is_ascii_alphabetic() || c == '_',
+        )
+    });
+
+    let mut out = String::new();
+    for declaration in token_declarations {
+        writeln!(out, "{}", declaration).unwrap();
+    }
+    for (associativity, level) in grammar.precedence_levels() {
+        let level = level.iter().map(|t| terminals[t.index()].as_str());
+        writeln!(
+            out,
+            "%{} {}",
+            associativity.directive(),
+            level.collect::<Vec<_>>().join(" ")
+        )
+        .unwrap();
+    }
+    writeln!(out, "%start {}", nonterminals[grammar.start().index()]).unwrap();
+    writeln!(out, "\n%%").unwrap();
+    for (nt, productions) in rules(grammar) {
+        writeln!(out, "\n{}", nonterminals[nt.index()]).unwrap();
+        let indices = grammar.production_indices(nt);
+        for (i, (production, &idx)) in productions.iter().zip(indices).enumerate() {
+            let rhs = if production.rhs().is_empty() {
+                "%empty".to_string()
+            } else {
+                production
+                    .rhs()
+                    .iter()
+                    .map(|symbol| match symbol {
+                        Symbol::Terminal(t) => terminals[t.index()].as_str(),
+                        Symbol::NonTerminal(nt) => nonterminals[nt.index()].as_str(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+            let prec = match grammar.precedence_override(idx) {
+                Some(t) => format!(" %prec {}", terminals[t.index()]),
+                None => String::new(),
+            };
+            let separator = if i == 0 { ':' } else { '|' };
+            writeln!(out, "    {} {}{} {{}}", separator, rhs, prec).unwrap();
+        }
+        writeln!(out, "    ;").unwrap();
+    }
+    writeln!(out, "\n%%").unwrap();
+    out
+}
+
+/// ANTLR keywords that cannot be used as rule names.
+const ANTLR_KEYWORDS: &[&str] = &[
+    "catch",
+    "finally",
+    "fragment",
+    "grammar",
+    "import",
+    "lexer",
+    "locals",
+    "mode",
+    "options",
+    "parser",
+    "private",
+    "protected",
+    "public",
+    "returns",
+    "throws",
+    "tokens",
+];
+
+/// Renders `grammar` as a combined ANTLR4 grammar called `name`.
+///
+/// Nonterminals become parser rules, which must start with a lowercase letter,
+/// so `Expr` becomes `expr` and `ID` becomes `id`.
+/// Every terminal is written as a string literal, which ANTLR turns into an
+/// implicit token, and whitespace between tokens is skipped. ε-alternatives are
+/// left empty and marked with a comment.
+pub fn to_antlr(grammar: &Grammar, name: &str) -> String {
+    let mut taken = ANTLR_KEYWORDS
+        .iter()
+        .map(ToString::to_string)
+        .collect::<HashSet<_>>();
+    let nonterminals = nonterminal_names(grammar, &mut taken, |name| {
+        let mut name = mangle(
+            name,
+            |c| c.is_ascii_alphanumeric() || c == '_',
+            |c| c.is_ascii_alphabetic(),
+        );
+        if name.chars().any(|c| c.is_ascii_lowercase()) {
+            name[..1].make_ascii_lowercase();
+        } else {
+            name.make_ascii_lowercase();
+        }
+        if name.starts_with('_') {
+            name.insert(0, 'r');
+        }
+        name
+    });
+
+    let mut out = String::new();
+    writeln!(out, "grammar {};", name).unwrap();
+    for (nt, productions) in rules(grammar) {
+        writeln!(out, "\n{}", nonterminals[nt.index()]).unwrap();
+        for (i, production) in productions.iter().enumerate() {
+            let rhs = if production.rhs().is_empty() {
+                "/* ε */".to_string()
+            } else {
+                production
+                    .rhs()
+                    .iter()
+                    .map(|&symbol| match symbol {
+                        Symbol::Terminal(t) => escaped(&grammar.terminal(t).0, '\''),
+                        Symbol::NonTerminal(nt) => nonterminals[nt.index()].clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+            writeln!(out, "    {} {}", if i == 0 { ':' } else { '|' }, rhs).unwrap();
+        }
+        writeln!(out, "    ;").unwrap();
+    }
+    writeln!(out, "\nWS : [ \\t\\r\\n]+ -> skip ;").unwrap();
+    out
+}
+
+/// Writes a terminal as W3C string literals, which have no escapes: `"..."` or
+/// `'...'`, or a parenthesised sequence of both if it contains both quotes.
+fn w3c_literal(s: &str) -> String {
+    if !s.contains('"') {
+        return format!("\"{}\"", s);
+    }
+    if !s.contains('\'') {
+        return format!("'{}'", s);
+    }
+    let mut pieces = vec![];
+    let mut rest = s;
+    while !rest.is_empty() {
+        let end = rest.find('"').unwrap_or(rest.len());
+        if end == 0 {
+            pieces.push("'\"'".to_string());
+            rest = &rest[1..];
+        } else {
+            pieces.push(format!("\"{}\"", &rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+    format!("( {} )", pieces.join(" "))
+}
+
+/// Renders `grammar` in the EBNF notation of the W3C XML specification.
+///
+/// Each nonterminal gets one `::=` rule. The notation has no empty sequence, so
+/// a nonterminal with ε-alternatives has its other alternatives wrapped in
+/// `( ... )?`, and one whose only production is ε gets an empty rule marked with
+/// a comment.
+pub fn to_w3c_ebnf(grammar: &Grammar) -> String {
+    let nonterminals = nonterminal_names(grammar, &mut HashSet::new(), |name| {
+        mangle(
+            name,
+            |c| c.is_ascii_alphanumeric() || c == '_',
+            |c| c.is_ascii_alphabetic() || c == '_',
+        )
+    });
+
+    let mut out = String::new();
+    for (nt, productions) in rules(grammar) {
+        let alternatives = productions
+            .iter()
+            .filter(|p| !p.rhs().is_empty())
+            .map(|p| {
+                p.rhs()
+                    .iter()
+                    .map(|&symbol| match symbol {
+                        Symbol::Terminal(t) => w3c_literal(&grammar.terminal(t).0),
+                        Symbol::NonTerminal(nt) => nonterminals[nt.index()].clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>();
+        let nullable = alternatives.len() < productions.len();
+        let rhs = match (alternatives.len(), nullable) {
+            (0, _) => "/* ε */".to_string(),
+            (_, false) => alternatives.join(" | "),
+            (_, true) => format!("( {} )?", alternatives.join(" | ")),
+        };
+        writeln!(out, "{} ::= {}", nonterminals[nt.index()], rhs).unwrap();
+    }
+    out
+}
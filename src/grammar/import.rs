@@ -0,0 +1,233 @@
+//! Composition of grammars by import.
+//!
+//! Importing a grammar under a namespace `ns` brings in its nonterminals as
+//! `ns.Name`, with all their productions and precedence declarations.
+//! Terminals are not renamed, so the grammars share one set of tokens. A rule
+//! of the importing grammar for a qualified name replaces the imported
+//! productions of that nonterminal, and the other imported nonterminals can be
+//! used in its rhs like any other.
+//!
+//! Imports are checked for clashes: a namespace imported twice, a name that is
+//! a terminal on one side and a nonterminal on the other, or a terminal given a
+//! precedence by more than one grammar.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{Associativity, Grammar, NonTerminal, Symbol, Terminal};
+
+type Rhs = Vec<Symbol<Terminal, NonTerminal>>;
+
+/// A symbol that two of the composed grammars disagree on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Clash {
+    pub(crate) namespace: String,
+    pub(crate) symbol: String,
+}
+
+/// The productions of the imported grammars, renamed into their namespaces.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Imports {
+    /// The namespace each imported nonterminal came from.
+    nonterminals: HashMap<String, String>,
+    /// The namespace of the first import that used each terminal.
+    terminals: HashMap<String, String>,
+    productions: Vec<(NonTerminal, Rhs, Option<Terminal>)>,
+    levels: Vec<(Associativity, Vec<Terminal>)>,
+    /// The namespace that gave each terminal a precedence.
+    precedence: HashMap<String, String>,
+    synthetic: Vec<NonTerminal>,
+    display_names: Vec<(NonTerminal, String)>,
+}
+
+impl Imports {
+    /// Adds the symbols and productions of `grammar` under `namespace`.
+    pub(crate) fn add(&mut self, namespace: &str, grammar: &Grammar) -> Result<(), Clash> {
+        let clash = |symbol: &str| Clash {
+            namespace: namespace.to_string(),
+            symbol: symbol.to_string(),
+        };
+        let qualify = |nt: &NonTerminal| NonTerminal(format!("{}.{}", namespace, nt.0));
+
+        let mut nonterminals = grammar
+            .nonterminals()
+            .iter()
+            .map(|&nt| grammar.nonterminal(nt).clone())
+            .collect::<Vec<_>>();
+        let mut terminals = grammar
+            .terminals()
+            .iter()
+            .map(|&t| grammar.terminal(t).clone())
+            .collect::<Vec<_>>();
+        for production in grammar.productions() {
+            for &symbol in production.rhs() {
+                match grammar.symbol(symbol) {
+                    Symbol::Terminal(t) => terminals.push(t.clone()),
+                    Symbol::NonTerminal(nt) => nonterminals.push(nt.clone()),
+                }
+            }
+        }
+
+        for nt in nonterminals.iter().map(qualify) {
+            if self.nonterminals.contains_key(&nt.0) || self.terminals.contains_key(&nt.0) {
+                return Err(clash(&nt.0));
+            }
+        }
+        for t in terminals.iter() {
+            if self.nonterminals.contains_key(&t.0) {
+                return Err(clash(&t.0));
+            }
+        }
+        let levels = grammar
+            .precedence_levels()
+            .map(|(associativity, level)| {
+                let level = level
+                    .iter()
+                    .map(|&t| grammar.terminal(t).clone())
+                    .collect::<Vec<_>>();
+                (associativity, level)
+            })
+            .collect::<Vec<_>>();
+        if let Some(t) = levels
+            .iter()
+            .flat_map(|(_, level)| level)
+            .find(|t| self.precedence.contains_key(&t.0))
+        {
+            return Err(clash(&t.0));
+        }
+
+        for nt in nonterminals.iter().map(qualify) {
+            self.nonterminals.insert(nt.0, namespace.to_string());
+        }
+        for t in terminals {
+            self.terminals.entry(t.0).or_insert(namespace.to_string());
+        }
+        for t in levels.iter().flat_map(|(_, level)| level) {
+            self.precedence.insert(t.0.clone(), namespace.to_string());
+        }
+        self.levels.extend(levels);
+        for (idx, production) in grammar.productions().iter().enumerate() {
+            let rhs = production
+                .rhs()
+                .iter()
+                .map(|&symbol| match grammar.symbol(symbol) {
+                    Symbol::Terminal(t) => Symbol::Terminal(t.clone()),
+                    Symbol::NonTerminal(nt) => Symbol::NonTerminal(qualify(nt)),
+                })
+                .collect();
+            let prec = grammar
+                .precedence_override(idx)
+                .map(|t| grammar.terminal(t).clone());
+            let lhs = qualify(grammar.nonterminal(production.lhs()));
+            self.productions.push((lhs, rhs, prec));
+        }
+        self.synthetic.extend(
+            nonterminals
+                .iter()
+                .filter(|nt| {
+                    grammar
+                        .nonterminal_id(&nt.0)
+                        .is_some_and(|id| grammar.is_synthetic(id))
+                })
+                .map(qualify),
+        );
+        for &nt in grammar.nonterminals() {
+            let name = grammar.display_name(nt);
+            if name != grammar.nonterminal(nt).0 {
+                let display = format!("{}.{}", namespace, name);
+                self.display_names
+                    .push((qualify(grammar.nonterminal(nt)), display));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns if `nt` is the qualified name of an imported nonterminal.
+    pub(crate) fn is_nonterminal(&self, nt: &str) -> bool {
+        self.nonterminals.contains_key(nt)
+    }
+
+    /// The imported nonterminals, sorted.
+    pub(crate) fn nonterminals(&self) -> Vec<NonTerminal> {
+        let mut nonterminals = self
+            .nonterminals
+            .keys()
+            .map(|nt| NonTerminal(nt.clone()))
+            .collect::<Vec<_>>();
+        nonterminals.sort();
+        nonterminals
+    }
+
+    /// The terminals of the imported grammars, sorted.
+    pub(crate) fn terminals(&self) -> Vec<Terminal> {
+        let mut terminals = self
+            .terminals
+            .keys()
+            .map(|t| Terminal(t.clone()))
+            .collect::<Vec<_>>();
+        terminals.sort();
+        terminals
+    }
+
+    pub(crate) fn synthetic(&self) -> impl Iterator<Item = NonTerminal> + '_ {
+        self.synthetic.iter().cloned()
+    }
+
+    /// The display names of imported template instances, qualified.
+    pub(crate) fn display_names(&self) -> Vec<(NonTerminal, String)> {
+        self.display_names.clone()
+    }
+
+    /// Checks the symbols of the importing grammar against the imported ones:
+    /// its nonterminals must not be imported terminals, its terminals must not
+    /// be imported nonterminals, and it must not give a precedence to a
+    /// terminal that already has one.
+    pub(crate) fn check<'s>(
+        &self,
+        nonterminals: impl IntoIterator<Item = &'s str>,
+        terminals: impl IntoIterator<Item = &'s str>,
+        precedence: impl IntoIterator<Item = &'s str>,
+    ) -> Result<(), Clash> {
+        let clash = |namespace: &String, symbol: &str| Clash {
+            namespace: namespace.clone(),
+            symbol: symbol.to_string(),
+        };
+        for nt in nonterminals {
+            if let Some(namespace) = self.terminals.get(nt) {
+                return Err(clash(namespace, nt));
+            }
+        }
+        for t in terminals {
+            if let Some(namespace) = self.nonterminals.get(t) {
+                return Err(clash(namespace, t));
+            }
+        }
+        for t in precedence {
+            if let Some(namespace) = self.precedence.get(t) {
+                return Err(clash(namespace, t));
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends the imported productions whose lhs is not in `overridden` to
+    /// `productions`, and their `%prec` terminals by index to `overrides`.
+    /// Returns the imported precedence levels, which bind looser than the
+    /// levels of the importing grammar.
+    pub(crate) fn append(
+        self,
+        productions: &mut Vec<(NonTerminal, Rhs)>,
+        overrides: &mut Vec<(usize, Terminal)>,
+        overridden: &HashSet<NonTerminal>,
+    ) -> Vec<(Associativity, Vec<Terminal>)> {
+        for (lhs, rhs, prec) in self.productions {
+            if overridden.contains(&lhs) {
+                continue;
+            }
+            if let Some(prec) = prec {
+                overrides.push((productions.len(), prec));
+            }
+            productions.push((lhs, rhs));
+        }
+        self.levels
+    }
+}
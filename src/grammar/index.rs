@@ -0,0 +1,102 @@
+use super::{Production, Symbol, SymbolId};
+
+/// Facts about the nonterminals of a grammar, computed once when it is built.
+/// Everything is indexed by [`NonTerminalId::index`](super::NonTerminalId::index).
+#[derive(Debug, Clone)]
+pub(crate) struct Index {
+    /// Indices of the productions of each nonterminal, in order.
+    pub(crate) productions: Vec<Vec<usize>>,
+    pub(crate) nullable: Vec<bool>,
+    pub(crate) productive: Vec<bool>,
+    pub(crate) left_recursive: Vec<bool>,
+}
+
+impl Index {
+    pub(crate) fn new(nonterminal_count: usize, productions: &[Production]) -> Self {
+        let mut by_lhs = vec![vec![]; nonterminal_count];
+        for (idx, production) in productions.iter().enumerate() {
+            by_lhs[production.lhs().index()].push(idx);
+        }
+
+        let nullable = fixed_point(
+            nonterminal_count,
+            productions,
+            |symbol, nullable| match symbol {
+                Symbol::Terminal(_) => false,
+                Symbol::NonTerminal(nt) => nullable[nt.index()],
+            },
+        );
+        let productive =
+            fixed_point(
+                nonterminal_count,
+                productions,
+                |symbol, productive| match symbol {
+                    Symbol::Terminal(_) => true,
+                    Symbol::NonTerminal(nt) => productive[nt.index()],
+                },
+            );
+
+        // `A` is a left corner of `B` if `B -> α A β` with `α` nullable.
+        let mut corners = vec![vec![]; nonterminal_count];
+        for production in productions {
+            for &symbol in production.rhs() {
+                let Symbol::NonTerminal(nt) = symbol else {
+                    break;
+                };
+                corners[production.lhs().index()].push(nt.index());
+                if !nullable[nt.index()] {
+                    break;
+                }
+            }
+        }
+        let left_recursive = (0..nonterminal_count)
+            .map(|nt| {
+                let mut seen = vec![false; nonterminal_count];
+                let mut stack = corners[nt].clone();
+                while let Some(corner) = stack.pop() {
+                    if corner == nt {
+                        return true;
+                    }
+                    if !std::mem::replace(&mut seen[corner], true) {
+                        stack.extend_from_slice(&corners[corner]);
+                    }
+                }
+                false
+            })
+            .collect();
+
+        Self {
+            productions: by_lhs,
+            nullable,
+            productive,
+            left_recursive,
+        }
+    }
+}
+
+/// Marks every nonterminal with a production whose rhs symbols all satisfy
+/// `holds`, until nothing changes.
+fn fixed_point(
+    nonterminal_count: usize,
+    productions: &[Production],
+    holds: impl Fn(SymbolId, &[bool]) -> bool,
+) -> Vec<bool> {
+    let mut marked = vec![false; nonterminal_count];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for production in productions {
+            let lhs = production.lhs().index();
+            if !marked[lhs]
+                && production
+                    .rhs()
+                    .iter()
+                    .all(|&symbol| holds(symbol, &marked))
+            {
+                marked[lhs] = true;
+                changed = true;
+            }
+        }
+    }
+    marked
+}
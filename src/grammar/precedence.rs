@@ -0,0 +1,245 @@
+//! Operator precedence and associativity declarations, as in yacc.
+//!
+//! Each declaration such as `%left "+" "-"` is a level, and later levels bind
+//! tighter. A production takes the precedence of its `%prec` terminal if it has
+//! one, and otherwise that of the last terminal of its rhs with a precedence.
+//!
+//! The declarations resolve ambiguities between parse trees where one operator
+//! production is an operand of another: [`earley::parse`](crate::earley::parse)
+//! drops the trees that break them (see [`Grammar::respects_precedence`]), and
+//! [`stratify_precedence`](super::transform::stratify_precedence) compiles them
+//! into an unambiguous grammar.
+
+use std::collections::HashMap;
+
+use crate::parse_tree::ParseTree;
+
+use super::{Grammar, Symbol, Terminal, TerminalId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Associativity {
+    /// `a + b + c` is `(a + b) + c`.
+    Left,
+    /// `a ^ b ^ c` is `a ^ (b ^ c)`.
+    Right,
+    /// `a == b == c` is an error.
+    NonAssoc,
+}
+
+impl Associativity {
+    /// The name of the directive that declares a level, without the `%`.
+    pub fn directive(self) -> &'static str {
+        match self {
+            Associativity::Left => "left",
+            Associativity::Right => "right",
+            Associativity::NonAssoc => "nonassoc",
+        }
+    }
+}
+
+/// The precedence of a terminal or a production.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Precedence {
+    /// Index of the declaration, so a larger level binds tighter.
+    pub level: usize,
+    pub associativity: Associativity,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Precedences {
+    pub(crate) levels: Vec<(Associativity, Vec<TerminalId>)>,
+    terminals: HashMap<TerminalId, Precedence>,
+    /// The `%prec` terminal of a production, by production index.
+    pub(crate) overrides: HashMap<usize, TerminalId>,
+}
+
+impl Precedences {
+    pub(crate) fn new(
+        levels: Vec<(Associativity, Vec<TerminalId>)>,
+        overrides: HashMap<usize, TerminalId>,
+    ) -> Self {
+        let mut terminals = HashMap::new();
+        for (level, (associativity, level_terminals)) in levels.iter().enumerate() {
+            for &t in level_terminals {
+                terminals.insert(
+                    t,
+                    Precedence {
+                        level,
+                        associativity: *associativity,
+                    },
+                );
+            }
+        }
+        Self {
+            levels,
+            terminals,
+            overrides,
+        }
+    }
+}
+
+/// Which operand of an operator production a child is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+impl Grammar {
+    /// Interns the terminals of `levels` and the `%prec` terminals of
+    /// `overrides`, given by production index, and replaces the precedence
+    /// declarations of the grammar with them.
+    pub(crate) fn set_precedence(
+        &mut self,
+        levels: Vec<(Associativity, Vec<Terminal>)>,
+        overrides: impl IntoIterator<Item = (usize, Terminal)>,
+    ) {
+        let levels = levels
+            .into_iter()
+            .map(|(associativity, terminals)| {
+                let terminals = terminals
+                    .iter()
+                    .map(|t| self.symbols.intern_terminal(&t.0))
+                    .collect();
+                (associativity, terminals)
+            })
+            .collect();
+        let overrides = overrides
+            .into_iter()
+            .map(|(idx, t)| (idx, self.symbols.intern_terminal(&t.0)))
+            .collect();
+        self.precedence = Precedences::new(levels, overrides);
+    }
+
+    /// The precedence levels from loosest to tightest, each with the terminals
+    /// declared on it.
+    pub fn precedence_levels(
+        &self,
+    ) -> impl ExactSizeIterator<Item = (Associativity, &[TerminalId])> {
+        self.precedence
+            .levels
+            .iter()
+            .map(|(associativity, terminals)| (*associativity, terminals.as_slice()))
+    }
+
+    pub fn terminal_precedence(&self, t: TerminalId) -> Option<Precedence> {
+        self.precedence.terminals.get(&t).copied()
+    }
+
+    /// The terminal given with `%prec` for the production at index
+    /// `production`.
+    pub fn precedence_override(&self, production: usize) -> Option<TerminalId> {
+        self.precedence.overrides.get(&production).copied()
+    }
+
+    /// The precedence of the production at index `production`: that of its
+    /// `%prec` terminal, or else that of the last terminal of its rhs that has
+    /// one.
+    pub fn production_precedence(&self, production: usize) -> Option<Precedence> {
+        match self.precedence_override(production) {
+            Some(t) => self.terminal_precedence(t),
+            None => {
+                self.productions[production]
+                    .rhs()
+                    .iter()
+                    .rev()
+                    .find_map(|&symbol| match symbol {
+                        Symbol::Terminal(t) => self.terminal_precedence(t),
+                        Symbol::NonTerminal(_) => None,
+                    })
+            }
+        }
+    }
+
+    /// Returns if no operator production in `tree` has an operand whose
+    /// production binds looser than it, or as tight but on the side its
+    /// associativity forbids. Chains of unit productions between an operator
+    /// and its operand are looked through.
+    pub fn respects_precedence(&self, tree: &ParseTree) -> bool {
+        if self.precedence.levels.is_empty() {
+            return true;
+        }
+        let ParseTree::NonTerminal(_, children) = tree else {
+            return true;
+        };
+        let precedence = self
+            .tree_production(tree)
+            .and_then(|idx| self.production_precedence(idx));
+        for (i, child) in children.iter().enumerate() {
+            if !self.respects_precedence(child) {
+                return false;
+            }
+            let (Some(precedence), true) = (precedence, children.len() > 1) else {
+                continue;
+            };
+            let side = if i == 0 {
+                Side::Left
+            } else if i == children.len() - 1 {
+                Side::Right
+            } else {
+                continue;
+            };
+            if !self.is_valid_operand(precedence, side, child) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn is_valid_operand(&self, parent: Precedence, side: Side, child: &ParseTree) -> bool {
+        let mut child = child;
+        while let ParseTree::NonTerminal(_, children) = child {
+            match &children[..] {
+                [only @ ParseTree::NonTerminal(..)] => child = only,
+                _ => break,
+            }
+        }
+        let Some(idx) = self.tree_production(child) else {
+            return true;
+        };
+        let Some(precedence) = self.production_precedence(idx) else {
+            return true;
+        };
+        // Only an operand that is open towards the parent's operator competes
+        // with it, like `a + b` as the left operand of `*` in `a + b * c`.
+        let rhs = self.productions[idx].rhs();
+        let open = match side {
+            Side::Left => rhs.last(),
+            Side::Right => rhs.first(),
+        };
+        if !matches!(open, Some(Symbol::NonTerminal(_))) {
+            return true;
+        }
+        let allowed = match side {
+            Side::Left => Associativity::Left,
+            Side::Right => Associativity::Right,
+        };
+        precedence.level > parent.level
+            || (precedence.level == parent.level && parent.associativity == allowed)
+    }
+
+    /// The index of the production applied at the root of `tree`, found by the
+    /// names of the symbols.
+    pub(crate) fn tree_production(&self, tree: &ParseTree) -> Option<usize> {
+        let ParseTree::NonTerminal(nt, children) = tree else {
+            return None;
+        };
+        let nt = self.nonterminal_id(&nt.0)?;
+        self.production_indices(nt).iter().copied().find(|&idx| {
+            let rhs = self.productions[idx].rhs();
+            rhs.len() == children.len()
+                && rhs.iter().zip(children).all(|(&symbol, child)| {
+                    match (self.symbol(symbol), child) {
+                        (Symbol::Terminal(a), ParseTree::Terminal(b)) => a == *b,
+                        (Symbol::NonTerminal(a), ParseTree::NonTerminal(b, _)) => a == *b,
+                        _ => false,
+                    }
+                })
+        })
+    }
+}
@@ -0,0 +1,125 @@
+//! The serialized form of a [`Grammar`], documented on the type.
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    precedence::Precedences, Associativity, Grammar, NonTerminal, NonTerminalId, Production,
+    Symbol, SymbolTable, Terminal, TerminalId,
+};
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct GrammarData {
+    terminals: Vec<Terminal>,
+    nonterminals: Vec<NonTerminal>,
+    declared_terminals: Vec<TerminalId>,
+    declared_nonterminals: Vec<NonTerminalId>,
+    productions: Vec<Production>,
+    start: NonTerminalId,
+    synthetic: Vec<NonTerminalId>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    precedence: Vec<(Associativity, Vec<TerminalId>)>,
+    /// `%prec` terminals as pairs of a production index and a terminal, sorted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    prec: Vec<(usize, TerminalId)>,
+    /// Display names of template instances, sorted by nonterminal.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    display_names: Vec<(NonTerminalId, String)>,
+}
+
+impl From<Grammar> for GrammarData {
+    fn from(grammar: Grammar) -> Self {
+        let mut synthetic = grammar.synthetic.iter().copied().collect::<Vec<_>>();
+        synthetic.sort();
+        let mut prec = grammar
+            .precedence
+            .overrides
+            .iter()
+            .map(|(&idx, &t)| (idx, t))
+            .collect::<Vec<_>>();
+        prec.sort();
+        let mut display_names = grammar
+            .display_names
+            .iter()
+            .map(|(&nt, name)| (nt, name.clone()))
+            .collect::<Vec<_>>();
+        display_names.sort();
+        Self {
+            terminals: (0..grammar.terminal_count())
+                .map(|idx| grammar.terminal(TerminalId::new(idx)).clone())
+                .collect(),
+            nonterminals: (0..grammar.nonterminal_count())
+                .map(|idx| grammar.nonterminal(NonTerminalId::new(idx)).clone())
+                .collect(),
+            declared_terminals: grammar.terminals,
+            declared_nonterminals: grammar.nonterminals,
+            productions: grammar.productions,
+            start: grammar.start,
+            synthetic,
+            precedence: grammar.precedence.levels,
+            prec,
+            display_names,
+        }
+    }
+}
+
+impl TryFrom<GrammarData> for Grammar {
+    type Error = String;
+
+    fn try_from(data: GrammarData) -> Result<Self, Self::Error> {
+        if data.terminals.first() != Some(Terminal::eoim()) {
+            return Err(format!("terminal 0 must be `{}`", Terminal::eoim()));
+        }
+        let mut symbols = SymbolTable::new();
+        for (idx, t) in data.terminals.iter().enumerate() {
+            if symbols.intern_terminal(&t.0).index() != idx {
+                return Err(format!("duplicate terminal `{}`", t));
+            }
+        }
+        for (idx, nt) in data.nonterminals.iter().enumerate() {
+            if symbols.intern_nonterminal(&nt.0).index() != idx {
+                return Err(format!("duplicate nonterminal `{}`", nt));
+            }
+        }
+
+        let terminal_in_range = |t: &TerminalId| t.index() < data.terminals.len();
+        let nonterminal_in_range = |nt: &NonTerminalId| nt.index() < data.nonterminals.len();
+        let in_range = data.declared_terminals.iter().all(terminal_in_range)
+            && data.declared_nonterminals.iter().all(nonterminal_in_range)
+            && data.synthetic.iter().all(nonterminal_in_range)
+            && nonterminal_in_range(&data.start)
+            && data.productions.iter().all(|p| {
+                nonterminal_in_range(&p.lhs)
+                    && p.rhs.iter().all(|symbol| match symbol {
+                        Symbol::Terminal(t) => terminal_in_range(t),
+                        Symbol::NonTerminal(nt) => nonterminal_in_range(nt),
+                    })
+            })
+            && data
+                .precedence
+                .iter()
+                .all(|(_, level)| level.iter().all(terminal_in_range))
+            && data
+                .prec
+                .iter()
+                .all(|(idx, t)| *idx < data.productions.len() && terminal_in_range(t))
+            && data
+                .display_names
+                .iter()
+                .all(|(nt, _)| nonterminal_in_range(nt));
+        if !in_range {
+            return Err("symbol id out of range".to_string());
+        }
+
+        let mut grammar = Grammar::from_parts(
+            symbols,
+            data.declared_nonterminals,
+            data.declared_terminals,
+            data.productions,
+            data.start,
+            data.synthetic.into_iter().collect(),
+        );
+        grammar.precedence = Precedences::new(data.precedence, data.prec.into_iter().collect());
+        grammar.display_names = data.display_names.into_iter().collect();
+        Ok(grammar)
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use super::{NonTerminal, NonTerminalId, Terminal, TerminalId};
+
+/// Interns the names of the symbols of a grammar as dense ids.
+///
+/// Terminal 0 is always the end-of-input marker.
+#[derive(Debug, Clone)]
+pub(crate) struct SymbolTable {
+    terminals: Vec<Terminal>,
+    nonterminals: Vec<NonTerminal>,
+    terminal_ids: HashMap<String, TerminalId>,
+    nonterminal_ids: HashMap<String, NonTerminalId>,
+}
+
+impl SymbolTable {
+    pub(crate) fn new() -> Self {
+        let mut table = Self {
+            terminals: vec![],
+            nonterminals: vec![],
+            terminal_ids: HashMap::new(),
+            nonterminal_ids: HashMap::new(),
+        };
+        let eoim = table.intern_terminal(&Terminal::eoim().0);
+        debug_assert_eq!(eoim, TerminalId::eoim());
+        table
+    }
+
+    pub(crate) fn intern_terminal(&mut self, name: &str) -> TerminalId {
+        if let Some(&id) = self.terminal_ids.get(name) {
+            return id;
+        }
+        let id = TerminalId::new(self.terminals.len());
+        self.terminals.push(Terminal(name.to_string()));
+        self.terminal_ids.insert(name.to_string(), id);
+        id
+    }
+
+    pub(crate) fn intern_nonterminal(&mut self, name: &str) -> NonTerminalId {
+        if let Some(&id) = self.nonterminal_ids.get(name) {
+            return id;
+        }
+        let id = NonTerminalId::new(self.nonterminals.len());
+        self.nonterminals.push(NonTerminal(name.to_string()));
+        self.nonterminal_ids.insert(name.to_string(), id);
+        id
+    }
+
+    /// Interns the first of `candidates` that is not already the name of a
+    /// terminal or a nonterminal.
+    pub(crate) fn fresh_nonterminal(
+        &mut self,
+        candidates: impl IntoIterator<Item = String>,
+    ) -> NonTerminalId {
+        let name = candidates
+            .into_iter()
+            .find(|name| !self.contains(name))
+            .expect("Ran out of fresh names");
+        self.intern_nonterminal(&name)
+    }
+
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.terminal_ids.contains_key(name) || self.nonterminal_ids.contains_key(name)
+    }
+
+    pub(crate) fn terminal(&self, id: TerminalId) -> &Terminal {
+        &self.terminals[id.index()]
+    }
+
+    pub(crate) fn nonterminal(&self, id: NonTerminalId) -> &NonTerminal {
+        &self.nonterminals[id.index()]
+    }
+
+    pub(crate) fn terminal_id(&self, name: &str) -> Option<TerminalId> {
+        self.terminal_ids.get(name).copied()
+    }
+
+    pub(crate) fn nonterminal_id(&self, name: &str) -> Option<NonTerminalId> {
+        self.nonterminal_ids.get(name).copied()
+    }
+
+    pub(crate) fn terminal_count(&self) -> usize {
+        self.terminals.len()
+    }
+
+    pub(crate) fn nonterminal_count(&self) -> usize {
+        self.nonterminals.len()
+    }
+}
@@ -0,0 +1,1528 @@
+//! Textual grammar format.
+//!
+//! A grammar file is a sequence of statements, each terminated by `;`:
+//!
+//! ```text
+//! // Line comments start with `//`.
+//! %terminal x y z w;          // declare terminals
+//! %nonterminal E E' T T' F;   // declare nonterminals (optional)
+//! %start E;                   // defaults to the lhs of the first rule
+//!
+//! E  -> T E';
+//! E' -> "+" T E' | ε;         // an empty alternative may also be left blank
+//! T  -> F T';
+//! T' -> "*" F T' | ;
+//! F  -> "(" E ")" | x | y | z | w;
+//! ```
+//!
+//! Identifiers start with an ASCII letter or `_` and may contain ASCII letters,
+//! digits, `_`, `'` and `.`. The `.` is there for the `namespace.Name` of
+//! imported nonterminals (see below), but it is allowed in any identifier, so
+//! `a.b` is always a single symbol and a terminal named `a.b` is displayed
+//! without quotes. Anything else has to be written as a double quoted
+//! literal, which may use the escapes `\"` and `\\`. A quoted literal is always
+//! a terminal and is declared implicitly. A bare identifier is a nonterminal if
+//! it is declared with `%nonterminal` or appears on the lhs of a rule, and a
+//! terminal otherwise. Rules may use `->` or `::=`.
+//!
+//! A rhs may also use the EBNF operators `a*`, `a+` and `a?`, and parenthesised
+//! groups such as `("," a | ";")*`. These are lowered into ordinary productions
+//! of fresh nonterminals named after the lhs (`E_rep1`, `E_opt1`, `E_group1`),
+//! which are marked as synthetic (see [`Grammar::is_synthetic`]).
+//!
+//! Operator precedence is declared as in yacc, one level per statement from
+//! loosest to tightest, and an alternative may end with `%prec` to take the
+//! precedence of another terminal (see [`Precedence`](super::Precedence)):
+//!
+//! ```text
+//! %left "+" "-";
+//! %left "*";
+//! %right NEG;
+//!
+//! E -> E "+" E | E "-" E | E "*" E | "-" E %prec NEG | x;
+//! ```
+//!
+//! Names in precedence declarations are terminals, but unlike `%terminal` they
+//! do not declare them.
+//!
+//! A nonterminal can take symbols as parameters. Applying it, with no space
+//! before the `(`, instantiates its rules with the arguments substituted:
+//!
+//! ```text
+//! sep_by(X, S) -> X (S X)* | ε;
+//! Call -> id "(" sep_by(Expr, ",") ")";
+//! ```
+//!
+//! Each distinct application becomes an ordinary nonterminal named after the
+//! rule and its arguments, here `sep_by_Expr_comma`, which keeps the
+//! application as its [display name](Grammar::display_name). An argument may
+//! be a symbol, an application or a group. Any other name directly followed by
+//! `(` is followed by a group, as if there were a space between them.
+//!
+//! A grammar can import another grammar file under a namespace, which makes its
+//! nonterminals available as `namespace.Name` and shares its terminals:
+//!
+//! ```text
+//! %import expr "expr.grammar";
+//!
+//! Stmt -> id "=" expr.E ";";
+//! expr.F -> "(" expr.E ")" | id | num;   // replaces the imported rule
+//! ```
+//!
+//! Paths are relative to the importing file, or to the current directory for a
+//! grammar parsed from a string. A rule for an imported nonterminal replaces
+//! all of its imported productions. Importing a namespace twice, or using a
+//! name as a terminal in one grammar and a nonterminal in another, is an error.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    error, fmt, fs, io,
+    iter::Peekable,
+    path::{Path, PathBuf},
+    str::{Chars, FromStr},
+};
+
+use super::{import::Imports, Associativity, Grammar, NonTerminal, Symbol, Terminal};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    UnexpectedChar(char),
+    UnterminatedLiteral,
+    /// A comment, action or code block is not closed.
+    UnterminatedBlock,
+    InvalidEscape(char),
+    UnknownDirective(String),
+    /// Found the token described by `found` where `expected` was required.
+    Expected {
+        expected: &'static str,
+        found: String,
+    },
+    DuplicateStart,
+    /// A symbol was used both as a terminal and as a nonterminal.
+    TerminalAndNonTerminal(String),
+    NoStart,
+    /// The start symbol given by a yacc `%start` has no rules.
+    StartWithoutRules(String),
+    /// The grammar file at `path` could not be imported.
+    Import {
+        path: String,
+        message: String,
+    },
+    /// The grammar file at the given path imports itself, directly or not.
+    ImportCycle(String),
+    /// A symbol of the grammar imported under `namespace` clashes with another
+    /// symbol.
+    ImportClash {
+        namespace: String,
+        symbol: String,
+    },
+    DuplicateTemplate(String),
+    WrongArgumentCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    /// Instantiating the given application keeps producing larger ones.
+    InstantiationTooDeep(String),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnexpectedChar(c) => write!(f, "unexpected character `{}`", c),
+            ParseErrorKind::UnterminatedLiteral => write!(f, "unterminated literal"),
+            ParseErrorKind::UnterminatedBlock => write!(f, "unterminated block"),
+            ParseErrorKind::InvalidEscape(c) => write!(f, "invalid escape `\\{}`", c),
+            ParseErrorKind::UnknownDirective(d) => write!(f, "unknown directive `%{}`", d),
+            ParseErrorKind::Expected { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            ParseErrorKind::DuplicateStart => write!(f, "start symbol declared more than once"),
+            ParseErrorKind::TerminalAndNonTerminal(s) => {
+                write!(f, "`{}` is used as both a terminal and a nonterminal", s)
+            }
+            ParseErrorKind::NoStart => write!(f, "grammar has no rules and no `%start`"),
+            ParseErrorKind::StartWithoutRules(s) => {
+                write!(f, "start symbol `{}` has no rules", s)
+            }
+            ParseErrorKind::Import { path, message } => {
+                write!(f, "cannot import `{}`: {}", path, message)
+            }
+            ParseErrorKind::ImportCycle(path) => write!(f, "`{}` imports itself", path),
+            ParseErrorKind::ImportClash { namespace, symbol } => write!(
+                f,
+                "`{}` from import `{}` clashes with another symbol",
+                symbol, namespace
+            ),
+            ParseErrorKind::DuplicateTemplate(s) => {
+                write!(
+                    f,
+                    "parameterised nonterminal `{}` is defined more than once",
+                    s
+                )
+            }
+            ParseErrorKind::WrongArgumentCount {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` takes {} arguments, found {}",
+                name, expected, found
+            ),
+            ParseErrorKind::InstantiationTooDeep(s) => {
+                write!(f, "instantiating `{}` does not terminate", s)
+            }
+        }
+    }
+}
+
+/// Error produced when a grammar file is malformed.
+///
+/// `line` and `column` are 1-based and count characters, not bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.kind)
+    }
+}
+
+impl error::Error for ParseError {}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => e.fmt(f),
+            LoadError::Parse(e) => e.fmt(f),
+        }
+    }
+}
+
+impl error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            LoadError::Io(e) => Some(e),
+            LoadError::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<ParseError> for LoadError {
+    fn from(value: ParseError) -> Self {
+        Self::Parse(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Position {
+    pub(super) line: usize,
+    pub(super) column: usize,
+}
+
+impl Position {
+    pub(super) fn error(self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.column,
+            kind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    /// An identifier directly followed by `(`.
+    Apply(String),
+    Literal(String),
+    Directive(String),
+    Arrow,
+    Pipe,
+    Semi,
+    Epsilon,
+    LParen,
+    RParen,
+    Star,
+    Plus,
+    Question,
+    Comma,
+    Eof,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(s) => write!(f, "`{}`", s),
+            Token::Apply(s) => write!(f, "`{}(`", s),
+            Token::Literal(s) => write!(f, "{:?}", s),
+            Token::Directive(s) => write!(f, "`%{}`", s),
+            Token::Arrow => write!(f, "`->`"),
+            Token::Pipe => write!(f, "`|`"),
+            Token::Semi => write!(f, "`;`"),
+            Token::Epsilon => write!(f, "`ε`"),
+            Token::LParen => write!(f, "`(`"),
+            Token::RParen => write!(f, "`)`"),
+            Token::Star => write!(f, "`*`"),
+            Token::Plus => write!(f, "`+`"),
+            Token::Question => write!(f, "`?`"),
+            Token::Comma => write!(f, "`,`"),
+            Token::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_continue(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '\'' | '.')
+}
+
+/// Returns if `s` can be written as a bare identifier.
+pub(crate) fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(is_ident_start) && chars.all(is_ident_continue)
+}
+
+struct Lexer<'s> {
+    chars: Peekable<Chars<'s>>,
+    position: Position,
+}
+
+impl<'s> Lexer<'s> {
+    fn new(source: &'s str) -> Self {
+        Self {
+            chars: source.chars().peekable(),
+            position: Position { line: 1, column: 1 },
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('/') => {
+                    let position = self.position;
+                    self.bump();
+                    if self.peek() != Some('/') {
+                        return Err(position.error(ParseErrorKind::UnexpectedChar('/')));
+                    }
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn ident(&mut self) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.peek().filter(|&c| is_ident_continue(c)) {
+            s.push(c);
+            self.bump();
+        }
+        s
+    }
+
+    fn literal(&mut self, start: Position) -> Result<String, ParseError> {
+        let mut s = String::new();
+        loop {
+            let position = self.position;
+            match self.bump() {
+                None | Some('\n') => return Err(start.error(ParseErrorKind::UnterminatedLiteral)),
+                Some('"') => return Ok(s),
+                Some('\\') => match self.bump() {
+                    Some(c @ ('"' | '\\')) => s.push(c),
+                    Some(c) => return Err(position.error(ParseErrorKind::InvalidEscape(c))),
+                    None => return Err(start.error(ParseErrorKind::UnterminatedLiteral)),
+                },
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<(Token, Position), ParseError> {
+        self.skip_trivia()?;
+        let position = self.position;
+        let Some(c) = self.peek() else {
+            return Ok((Token::Eof, position));
+        };
+        let token = match c {
+            c if is_ident_start(c) => {
+                let s = self.ident();
+                if self.peek() == Some('(') {
+                    self.bump();
+                    Token::Apply(s)
+                } else {
+                    Token::Ident(s)
+                }
+            }
+            '"' => {
+                self.bump();
+                Token::Literal(self.literal(position)?)
+            }
+            '%' => {
+                self.bump();
+                Token::Directive(self.ident())
+            }
+            '|' => {
+                self.bump();
+                Token::Pipe
+            }
+            ';' => {
+                self.bump();
+                Token::Semi
+            }
+            ',' => {
+                self.bump();
+                Token::Comma
+            }
+            'ε' => {
+                self.bump();
+                Token::Epsilon
+            }
+            '(' | ')' | '*' | '+' | '?' => {
+                self.bump();
+                match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '*' => Token::Star,
+                    '+' => Token::Plus,
+                    _ => Token::Question,
+                }
+            }
+            '-' => {
+                self.bump();
+                if self.peek() != Some('>') {
+                    return Err(position.error(ParseErrorKind::UnexpectedChar('-')));
+                }
+                self.bump();
+                Token::Arrow
+            }
+            ':' => {
+                self.bump();
+                for expected in [':', '='] {
+                    if self.peek() != Some(expected) {
+                        return Err(position.error(ParseErrorKind::UnexpectedChar(':')));
+                    }
+                    self.bump();
+                }
+                Token::Arrow
+            }
+            c => return Err(position.error(ParseErrorKind::UnexpectedChar(c))),
+        };
+        Ok((token, position))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Repeat {
+    Star,
+    Plus,
+    Optional,
+}
+
+#[derive(Debug, Clone)]
+enum Atom {
+    Ident(String, Position),
+    Literal(String),
+    Group(Vec<Vec<Item>>),
+    /// A parameterised nonterminal applied to arguments.
+    Apply(String, Vec<Atom>, Position),
+}
+
+#[derive(Debug, Clone)]
+struct Item {
+    atom: Atom,
+    repeat: Option<Repeat>,
+}
+
+impl Atom {
+    /// Calls `f` on every identifier and literal in the atom.
+    fn visit_names<'a>(&'a self, f: &mut impl FnMut(&'a Atom)) {
+        match self {
+            Atom::Group(alternatives) => alternatives
+                .iter()
+                .flatten()
+                .for_each(|item| item.atom.visit_names(f)),
+            Atom::Apply(_, args, _) => args.iter().for_each(|arg| arg.visit_names(f)),
+            atom => f(atom),
+        }
+    }
+
+    /// Replaces the identifiers that are keys of `arguments`.
+    fn substitute(&self, arguments: &HashMap<&str, &Atom>) -> Atom {
+        match self {
+            Atom::Ident(s, _) if arguments.contains_key(s.as_str()) => {
+                arguments[s.as_str()].clone()
+            }
+            Atom::Group(alternatives) => Atom::Group(
+                alternatives
+                    .iter()
+                    .map(|items| {
+                        items
+                            .iter()
+                            .map(|item| item.substitute(arguments))
+                            .collect()
+                    })
+                    .collect(),
+            ),
+            Atom::Apply(name, args, position) => Atom::Apply(
+                name.clone(),
+                args.iter().map(|arg| arg.substitute(arguments)).collect(),
+                *position,
+            ),
+            atom => atom.clone(),
+        }
+    }
+
+    /// How deeply applications and groups are nested in the atom.
+    fn depth(&self) -> usize {
+        match self {
+            Atom::Ident(..) | Atom::Literal(_) => 0,
+            Atom::Group(alternatives) => {
+                1 + alternatives
+                    .iter()
+                    .flatten()
+                    .map(|item| item.atom.depth())
+                    .max()
+                    .unwrap_or(0)
+            }
+            Atom::Apply(_, args, _) => 1 + args.iter().map(Atom::depth).max().unwrap_or(0),
+        }
+    }
+
+    /// Part of the name of a nonterminal instantiated with the atom as an
+    /// argument.
+    fn mangle(&self) -> String {
+        match self {
+            Atom::Ident(s, _) => s.clone(),
+            Atom::Literal(s) if is_ident(s) => s.clone(),
+            Atom::Literal(s) => {
+                let words = s.chars().filter_map(punctuation_name).collect::<Vec<_>>();
+                if words.is_empty() {
+                    "tok".to_string()
+                } else {
+                    words.join("_")
+                }
+            }
+            Atom::Group(_) => "group".to_string(),
+            Atom::Apply(name, args, _) => std::iter::once(name.clone())
+                .chain(args.iter().map(Atom::mangle))
+                .collect::<Vec<_>>()
+                .join("_"),
+        }
+    }
+}
+
+impl Item {
+    fn substitute(&self, arguments: &HashMap<&str, &Atom>) -> Item {
+        Item {
+            atom: self.atom.substitute(arguments),
+            repeat: self.repeat,
+        }
+    }
+}
+
+/// Writes the atom as it would appear in a rhs.
+impl fmt::Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Atom::Ident(s, _) => f.write_str(s),
+            Atom::Literal(s) => write_literal(f, s),
+            Atom::Group(alternatives) => {
+                f.write_str("(")?;
+                for (i, items) in alternatives.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" | ")?;
+                    }
+                    for (j, item) in items.iter().enumerate() {
+                        if j > 0 {
+                            f.write_str(" ")?;
+                        }
+                        write!(f, "{}", item)?;
+                    }
+                }
+                f.write_str(")")
+            }
+            Atom::Apply(name, args, _) => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.atom)?;
+        match self.repeat {
+            Some(Repeat::Star) => f.write_str("*"),
+            Some(Repeat::Plus) => f.write_str("+"),
+            Some(Repeat::Optional) => f.write_str("?"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A word for an ASCII punctuation character, to name instances with literal
+/// arguments.
+fn punctuation_name(c: char) -> Option<&'static str> {
+    Some(match c {
+        ',' => "comma",
+        ';' => "semi",
+        ':' => "colon",
+        '.' => "dot",
+        '+' => "plus",
+        '-' => "minus",
+        '*' => "star",
+        '/' => "slash",
+        '%' => "percent",
+        '|' => "bar",
+        '&' => "amp",
+        '=' => "eq",
+        '<' => "lt",
+        '>' => "gt",
+        '!' => "bang",
+        '?' => "question",
+        '(' => "lparen",
+        ')' => "rparen",
+        '[' => "lbracket",
+        ']' => "rbracket",
+        '{' => "lbrace",
+        '}' => "rbrace",
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    lhs: String,
+    alternatives: Vec<Vec<Item>>,
+    /// The `%prec` terminal of each alternative.
+    precs: Vec<Option<String>>,
+}
+
+/// A rule with parameters, such as `list(X) -> X list(X) | ε;`.
+#[derive(Debug, Clone)]
+struct Template {
+    params: Vec<String>,
+    rule: Rule,
+    position: Position,
+}
+
+/// Returns the names of the templates defined in `source`, which are those
+/// applied at the start of a statement. Lexical errors are left to the parser.
+fn template_names(source: &str) -> HashSet<String> {
+    let mut lexer = Lexer::new(source);
+    let mut names = HashSet::new();
+    let mut statement_start = true;
+    while let Ok((token, _)) = lexer.next_token() {
+        match token {
+            Token::Eof => break,
+            Token::Apply(ref name) if statement_start => {
+                names.insert(name.clone());
+            }
+            _ => {}
+        }
+        statement_start = token == Token::Semi;
+    }
+    names
+}
+
+struct Parser<'s> {
+    lexer: Lexer<'s>,
+    token: Token,
+    position: Position,
+    /// The directory that import paths are relative to.
+    dir: PathBuf,
+    /// The files being loaded, outermost first, to detect import cycles.
+    loading: Vec<PathBuf>,
+    /// The names of the templates defined in the source.
+    templates: HashSet<String>,
+}
+
+impl<'s> Parser<'s> {
+    fn new(source: &'s str) -> Result<Self, ParseError> {
+        let mut lexer = Lexer::new(source);
+        let (token, position) = lexer.next_token()?;
+        Ok(Self {
+            lexer,
+            token,
+            position,
+            dir: PathBuf::new(),
+            loading: vec![],
+            templates: template_names(source),
+        })
+    }
+
+    /// Makes imports relative to the directory of `path`, the file `source`
+    /// was read from.
+    fn in_file(mut self, path: &Path, loading: Vec<PathBuf>) -> Self {
+        self.dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        self.loading = loading;
+        self
+    }
+
+    /// Loads the grammar file at `path`, given in an `%import` at `position`.
+    fn import(&self, path: &str, position: Position) -> Result<Grammar, ParseError> {
+        let error = |message: String| {
+            position.error(ParseErrorKind::Import {
+                path: path.to_string(),
+                message,
+            })
+        };
+        let full = self.dir.join(path);
+        let canonical = full.canonicalize().map_err(|e| error(e.to_string()))?;
+        if self.loading.contains(&canonical) {
+            return Err(position.error(ParseErrorKind::ImportCycle(path.to_string())));
+        }
+        let source = fs::read_to_string(&full).map_err(|e| error(e.to_string()))?;
+        let mut loading = self.loading.clone();
+        loading.push(canonical);
+        Parser::new(&source)
+            .and_then(|parser| parser.in_file(&full, loading).parse())
+            .map_err(|e| match e.kind {
+                ParseErrorKind::ImportCycle(_) => e,
+                _ => error(e.to_string()),
+            })
+    }
+
+    fn advance(&mut self) -> Result<Token, ParseError> {
+        let (token, position) = self.lexer.next_token()?;
+        self.position = position;
+        Ok(std::mem::replace(&mut self.token, token))
+    }
+
+    fn unexpected(&self, expected: &'static str) -> ParseError {
+        self.position.error(ParseErrorKind::Expected {
+            expected,
+            found: self.token.to_string(),
+        })
+    }
+
+    fn expect(&mut self, token: Token, expected: &'static str) -> Result<(), ParseError> {
+        if self.token != token {
+            return Err(self.unexpected(expected));
+        }
+        self.advance()?;
+        Ok(())
+    }
+
+    fn atom(&mut self) -> Result<Option<Atom>, ParseError> {
+        let position = self.position;
+        let atom = match self.token {
+            Token::Ident(_) => {
+                let Token::Ident(s) = self.advance()? else {
+                    unreachable!()
+                };
+                Atom::Ident(s, position)
+            }
+            Token::Literal(_) => {
+                let Token::Literal(s) = self.advance()? else {
+                    unreachable!()
+                };
+                Atom::Literal(s)
+            }
+            Token::LParen => {
+                self.advance()?;
+                let alternatives = self.alternatives()?;
+                self.expect(Token::RParen, "`)` or `|`")?;
+                Atom::Group(alternatives)
+            }
+            Token::Apply(ref name) if !self.templates.contains(name) => {
+                // Not an application, so the `(` starts a group, as it would
+                // after a space.
+                let Token::Apply(name) = std::mem::replace(&mut self.token, Token::LParen) else {
+                    unreachable!()
+                };
+                self.position.column += name.len();
+                Atom::Ident(name, position)
+            }
+            Token::Apply(_) => {
+                let Token::Apply(name) = self.advance()? else {
+                    unreachable!()
+                };
+                let mut args = vec![];
+                loop {
+                    let Some(arg) = self.atom()? else {
+                        return Err(self.unexpected("argument"));
+                    };
+                    args.push(arg);
+                    if self.token != Token::Comma {
+                        break;
+                    }
+                    self.advance()?;
+                }
+                self.expect(Token::RParen, "`,` or `)`")?;
+                Atom::Apply(name, args, position)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(atom))
+    }
+
+    fn item(&mut self) -> Result<Option<Item>, ParseError> {
+        let Some(atom) = self.atom()? else {
+            return Ok(None);
+        };
+        let mut item = Item { atom, repeat: None };
+        loop {
+            let repeat = match self.token {
+                Token::Star => Repeat::Star,
+                Token::Plus => Repeat::Plus,
+                Token::Question => Repeat::Optional,
+                _ => break,
+            };
+            self.advance()?;
+            if item.repeat.is_some() {
+                // `a*?` is `(a*)?`
+                item = Item {
+                    atom: Atom::Group(vec![vec![item]]),
+                    repeat: None,
+                };
+            }
+            item.repeat = Some(repeat);
+        }
+        Ok(Some(item))
+    }
+
+    fn alternative(&mut self) -> Result<Vec<Item>, ParseError> {
+        if self.token == Token::Epsilon {
+            self.advance()?;
+            return Ok(vec![]);
+        }
+        let mut items = vec![];
+        while let Some(item) = self.item()? {
+            items.push(item);
+        }
+        Ok(items)
+    }
+
+    fn alternatives(&mut self) -> Result<Vec<Vec<Item>>, ParseError> {
+        let mut alternatives = vec![self.alternative()?];
+        while self.token == Token::Pipe {
+            self.advance()?;
+            alternatives.push(self.alternative()?);
+        }
+        Ok(alternatives)
+    }
+
+    /// Parses an optional `%prec` annotation at the end of an alternative.
+    fn prec(&mut self) -> Result<Option<String>, ParseError> {
+        if self.token != Token::Directive("prec".to_string()) {
+            return Ok(None);
+        }
+        self.advance()?;
+        match self.token {
+            Token::Ident(_) | Token::Literal(_) => match self.advance()? {
+                Token::Ident(s) | Token::Literal(s) => Ok(Some(s)),
+                _ => unreachable!(),
+            },
+            _ => Err(self.unexpected("terminal")),
+        }
+    }
+
+    fn rule(&mut self, lhs: String) -> Result<Rule, ParseError> {
+        self.expect(Token::Arrow, "`->`")?;
+        let mut alternatives = vec![];
+        let mut precs = vec![];
+        loop {
+            alternatives.push(self.alternative()?);
+            precs.push(self.prec()?);
+            if self.token != Token::Pipe {
+                break;
+            }
+            self.advance()?;
+        }
+        self.expect(Token::Semi, "`;` or `|`")?;
+        Ok(Rule {
+            lhs,
+            alternatives,
+            precs,
+        })
+    }
+
+    /// Parses the parameters and rule of a template called `name`.
+    fn template(&mut self, name: String, position: Position) -> Result<Template, ParseError> {
+        let mut params = vec![];
+        loop {
+            let Token::Ident(param) = self.token.clone() else {
+                return Err(self.unexpected("parameter"));
+            };
+            self.advance()?;
+            params.push(param);
+            if self.token != Token::Comma {
+                break;
+            }
+            self.advance()?;
+        }
+        self.expect(Token::RParen, "`,` or `)`")?;
+        Ok(Template {
+            params,
+            rule: self.rule(name)?,
+            position,
+        })
+    }
+
+    fn parse(mut self) -> Result<Grammar, ParseError> {
+        let mut declared_terminals = vec![];
+        let mut declared_nonterminals = vec![];
+        let mut levels = vec![];
+        let mut start = None;
+        let mut rules = vec![];
+        let mut templates = HashMap::new();
+        let mut imports = Imports::default();
+        let mut namespaces = vec![];
+
+        loop {
+            let position = self.position;
+            match self.advance()? {
+                Token::Eof => break,
+                Token::Ident(lhs) => rules.push(self.rule(lhs)?),
+                Token::Apply(name) => {
+                    let template = self.template(name.clone(), position)?;
+                    if templates.insert(name.clone(), template).is_some() {
+                        return Err(position.error(ParseErrorKind::DuplicateTemplate(name)));
+                    }
+                }
+                Token::Directive(directive) => match directive.as_str() {
+                    "terminal" => {
+                        while let Token::Ident(_) | Token::Literal(_) = self.token {
+                            declared_terminals.extend(self.atom()?);
+                        }
+                        self.expect(Token::Semi, "terminal or `;`")?;
+                    }
+                    "left" | "right" | "nonassoc" => {
+                        let associativity = match directive.as_str() {
+                            "left" => Associativity::Left,
+                            "right" => Associativity::Right,
+                            _ => Associativity::NonAssoc,
+                        };
+                        let mut atoms = vec![];
+                        while let Token::Ident(_) | Token::Literal(_) = self.token {
+                            atoms.extend(self.atom()?);
+                        }
+                        self.expect(Token::Semi, "terminal or `;`")?;
+                        levels.push((associativity, atoms));
+                    }
+                    "nonterminal" => {
+                        while let Token::Ident(_) = self.token {
+                            let position = self.position;
+                            let Token::Ident(s) = self.advance()? else {
+                                unreachable!()
+                            };
+                            declared_nonterminals.push((s, position));
+                        }
+                        self.expect(Token::Semi, "identifier or `;`")?;
+                    }
+                    "import" => {
+                        let Token::Ident(namespace) = self.token.clone() else {
+                            return Err(self.unexpected("namespace"));
+                        };
+                        self.advance()?;
+                        let path_position = self.position;
+                        let Token::Literal(path) = &self.token else {
+                            return Err(self.unexpected("path"));
+                        };
+                        let grammar = self.import(&path.clone(), path_position)?;
+                        self.advance()?;
+                        self.expect(Token::Semi, "`;`")?;
+                        imports.add(&namespace, &grammar).map_err(|clash| {
+                            position.error(ParseErrorKind::ImportClash {
+                                namespace: clash.namespace,
+                                symbol: clash.symbol,
+                            })
+                        })?;
+                        namespaces.push((namespace, position));
+                    }
+                    "start" => {
+                        if start.is_some() {
+                            return Err(position.error(ParseErrorKind::DuplicateStart));
+                        }
+                        let Token::Ident(s) = &self.token else {
+                            return Err(self.unexpected("identifier"));
+                        };
+                        start = Some(s.clone());
+                        self.advance()?;
+                        self.expect(Token::Semi, "`;`")?;
+                    }
+                    _ => return Err(position.error(ParseErrorKind::UnknownDirective(directive))),
+                },
+                token => {
+                    return Err(position.error(ParseErrorKind::Expected {
+                        expected: "rule or directive",
+                        found: token.to_string(),
+                    }))
+                }
+            }
+        }
+
+        let mut nonterminals = declared_nonterminals
+            .iter()
+            .map(|(s, _)| s)
+            .chain(rules.iter().map(|rule| &rule.lhs))
+            .map(|s| NonTerminal(s.clone()))
+            .collect::<HashSet<_>>();
+        let names = |atoms: &mut dyn Iterator<Item = &Atom>| -> Vec<String> {
+            atoms
+                .filter_map(|atom| match atom {
+                    Atom::Ident(s, _) | Atom::Literal(s) => Some(s.clone()),
+                    Atom::Group(_) | Atom::Apply(..) => None,
+                })
+                .collect()
+        };
+        let own_terminals = names(&mut declared_terminals.iter());
+        let own_precedence = names(&mut levels.iter().flat_map(|(_, atoms)| atoms));
+        imports
+            .check(
+                nonterminals.iter().map(|nt| nt.0.as_str()),
+                own_terminals.iter().map(String::as_str),
+                own_precedence.iter().map(String::as_str),
+            )
+            .map_err(|clash| {
+                let position = namespaces
+                    .iter()
+                    .find(|(namespace, _)| *namespace == clash.namespace)
+                    .map_or(self.position, |&(_, position)| position);
+                position.error(ParseErrorKind::ImportClash {
+                    namespace: clash.namespace,
+                    symbol: clash.symbol,
+                })
+            })?;
+        let overridden = rules
+            .iter()
+            .map(|rule| NonTerminal(rule.lhs.clone()))
+            .filter(|lhs| imports.is_nonterminal(&lhs.0))
+            .collect::<HashSet<_>>();
+        nonterminals.extend(imports.nonterminals());
+
+        let mut terminals = HashSet::new();
+        for atom in declared_terminals {
+            let s = match atom {
+                Atom::Ident(s, position) => {
+                    if nonterminals.contains(&NonTerminal(s.clone())) {
+                        return Err(position.error(ParseErrorKind::TerminalAndNonTerminal(s)));
+                    }
+                    s
+                }
+                Atom::Literal(s) => s,
+                Atom::Group(_) | Atom::Apply(..) => {
+                    unreachable!("Declared terminals are only names")
+                }
+            };
+            terminals.insert(Terminal(s));
+        }
+
+        terminals.extend(imports.terminals());
+        let mut taken = HashSet::new();
+        taken.extend(nonterminals.iter().map(|nt| nt.0.clone()));
+        taken.extend(declared_nonterminals.iter().map(|(s, _)| s.clone()));
+        taken.extend(terminals.iter().map(|t| t.0.clone()));
+        let no_params = vec![];
+        let bodies = rules
+            .iter()
+            .map(|rule| (rule, &no_params))
+            .chain(templates.values().map(|t: &Template| (&t.rule, &t.params)));
+        for (rule, params) in bodies {
+            taken.insert(rule.lhs.clone());
+            for item in rule.alternatives.iter().flatten() {
+                item.atom.visit_names(&mut |atom| match atom {
+                    Atom::Ident(s, _) if params.contains(s) => {}
+                    Atom::Ident(s, _) => {
+                        taken.insert(s.clone());
+                    }
+                    Atom::Literal(s) => {
+                        taken.insert(s.clone());
+                        terminals.insert(Terminal(s.clone()));
+                    }
+                    Atom::Group(_) | Atom::Apply(..) => unreachable!(),
+                });
+            }
+        }
+        for (s, position) in declared_nonterminals.iter() {
+            if terminals.contains(&Terminal(s.clone())) {
+                return Err(position.error(ParseErrorKind::TerminalAndNonTerminal(s.clone())));
+            }
+        }
+        let mut precedence_levels = vec![];
+        for (associativity, atoms) in levels {
+            let mut level = vec![];
+            for atom in atoms {
+                let s = match atom {
+                    Atom::Ident(s, position) => {
+                        if nonterminals.contains(&NonTerminal(s.clone())) {
+                            return Err(position.error(ParseErrorKind::TerminalAndNonTerminal(s)));
+                        }
+                        s
+                    }
+                    Atom::Literal(s) => s,
+                    Atom::Group(_) | Atom::Apply(..) => {
+                        unreachable!("Precedence levels are only names")
+                    }
+                };
+                taken.insert(s.clone());
+                level.push(Terminal(s));
+            }
+            precedence_levels.push((associativity, level));
+        }
+
+        let start = match (start, rules.first()) {
+            (Some(start), _) => start,
+            (None, Some(rule)) => rule.lhs.clone(),
+            (None, None) => return Err(self.position.error(ParseErrorKind::NoStart)),
+        };
+
+        let mut lowering = Lowering {
+            nonterminals,
+            terminals,
+            taken,
+            helpers: vec![],
+            synthetic: HashSet::new(),
+            templates,
+            instances: HashMap::new(),
+            pending: VecDeque::new(),
+            display_names: vec![],
+        };
+        let mut productions = vec![];
+        let mut overrides = vec![];
+        for rule in rules {
+            lowering.rule(rule, &mut productions, &mut overrides)?;
+        }
+        while let Some(rule) = lowering.pending.pop_front() {
+            lowering.rule(rule, &mut productions, &mut overrides)?;
+        }
+
+        lowering.synthetic.extend(imports.synthetic());
+        let imports_display = imports.display_names();
+        let mut levels = imports.append(&mut productions, &mut overrides, &overridden);
+        levels.extend(precedence_levels);
+
+        let mut grammar = Grammar::from_named(
+            sorted(lowering.nonterminals),
+            sorted(lowering.terminals),
+            productions,
+            NonTerminal(start),
+            lowering.synthetic,
+        );
+        if !levels.is_empty() || !overrides.is_empty() {
+            grammar.set_precedence(levels, overrides);
+        }
+        grammar.set_display_names(lowering.display_names.into_iter().chain(imports_display));
+        Ok(grammar)
+    }
+}
+
+/// How deeply applications and groups may be nested in the arguments of an
+/// instance.
+const MAX_INSTANCE_DEPTH: usize = 16;
+/// How long an application may be when written out, as arguments can also grow
+/// wider with each instance.
+const MAX_INSTANCE_LEN: usize = 1024;
+
+/// Desugars EBNF operators on a rhs into fresh synthetic nonterminals.
+///
+/// `a*` becomes `N` with `N -> a N | ε`, `a?` becomes `N` with `N -> a | ε`,
+/// `a+` becomes `a N` with `N` as for `a*`, and a group with more than one
+/// alternative becomes `N` with a production for each alternative. A group with
+/// a single alternative is spliced into the surrounding sequence. The
+/// alternatives of a repeated group are expanded directly into the helper.
+struct Lowering {
+    nonterminals: HashSet<NonTerminal>,
+    terminals: HashSet<Terminal>,
+    /// Every name used in the source, so fresh names never clash.
+    taken: HashSet<String>,
+    /// Productions of helpers created for the current rule.
+    helpers: Vec<(NonTerminal, Rhs)>,
+    synthetic: HashSet<NonTerminal>,
+    templates: HashMap<String, Template>,
+    /// The nonterminal of each application, keyed by how it is written.
+    instances: HashMap<String, NonTerminal>,
+    /// Rules of instances that still have to be lowered.
+    pending: VecDeque<Rule>,
+    display_names: Vec<(NonTerminal, String)>,
+}
+
+type Rhs = Vec<Symbol<Terminal, NonTerminal>>;
+
+/// Interning in name order keeps the ids of a parsed grammar deterministic.
+fn sorted<T: Ord>(set: HashSet<T>) -> Vec<T> {
+    let mut v = set.into_iter().collect::<Vec<_>>();
+    v.sort();
+    v
+}
+
+impl Lowering {
+    /// Lowers the alternatives of `rule` into `productions`, followed by the
+    /// helpers they need, and records their `%prec` terminals in `overrides`.
+    fn rule(
+        &mut self,
+        rule: Rule,
+        productions: &mut Vec<(NonTerminal, Rhs)>,
+        overrides: &mut Vec<(usize, Terminal)>,
+    ) -> Result<(), ParseError> {
+        let alternatives = self.alternatives(&rule.lhs, rule.alternatives)?;
+        for (rhs, prec) in alternatives.into_iter().zip(rule.precs) {
+            if let Some(prec) = prec {
+                overrides.push((productions.len(), Terminal(prec)));
+            }
+            productions.push((NonTerminal(rule.lhs.clone()), rhs));
+        }
+        productions.append(&mut self.helpers);
+        Ok(())
+    }
+
+    /// Returns the nonterminal for the application of template `name` to
+    /// `args`, queueing its rule the first time.
+    fn instance(
+        &mut self,
+        name: String,
+        args: Vec<Atom>,
+        position: Position,
+    ) -> Result<NonTerminal, ParseError> {
+        let template = &self.templates[&name];
+        if template.params.len() != args.len() {
+            return Err(position.error(ParseErrorKind::WrongArgumentCount {
+                name,
+                expected: template.params.len(),
+                found: args.len(),
+            }));
+        }
+        let application = Atom::Apply(name, args, position);
+        let key = application.to_string();
+        if let Some(nt) = self.instances.get(&key) {
+            return Ok(nt.clone());
+        }
+        if application.depth() > MAX_INSTANCE_DEPTH || key.len() > MAX_INSTANCE_LEN {
+            return Err(template
+                .position
+                .error(ParseErrorKind::InstantiationTooDeep(key)));
+        }
+        let Atom::Apply(_, args, _) = &application else {
+            unreachable!()
+        };
+
+        let base = application.mangle();
+        let name = std::iter::once(base.clone())
+            .chain((1..).map(|n| format!("{}{}", base, n)))
+            .find(|name| !self.taken.contains(name))
+            .unwrap();
+        self.taken.insert(name.clone());
+        let nt = NonTerminal(name.clone());
+        self.nonterminals.insert(nt.clone());
+        self.instances.insert(key.clone(), nt.clone());
+        self.display_names.push((nt.clone(), key));
+
+        let arguments = template
+            .params
+            .iter()
+            .map(String::as_str)
+            .zip(args)
+            .collect::<HashMap<_, _>>();
+        let alternatives = template
+            .rule
+            .alternatives
+            .iter()
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|item| item.substitute(&arguments))
+                    .collect()
+            })
+            .collect();
+        let precs = template
+            .rule
+            .precs
+            .iter()
+            .map(|prec| {
+                prec.as_ref()
+                    .map(|prec| match arguments.get(prec.as_str()) {
+                        Some(Atom::Ident(s, _) | Atom::Literal(s)) => s.clone(),
+                        _ => prec.clone(),
+                    })
+            })
+            .collect();
+        self.pending.push_back(Rule {
+            lhs: name,
+            alternatives,
+            precs,
+        });
+        Ok(nt)
+    }
+
+    fn fresh(&mut self, lhs: &str, kind: &str) -> NonTerminal {
+        let name = (1..)
+            .map(|n| format!("{}_{}{}", lhs, kind, n))
+            .find(|name| !self.taken.contains(name))
+            .unwrap();
+        self.taken.insert(name.clone());
+        let nt = NonTerminal(name);
+        self.nonterminals.insert(nt.clone());
+        self.synthetic.insert(nt.clone());
+        nt
+    }
+
+    fn helper(&mut self, lhs: &str, kind: &str, bodies: Vec<Rhs>) -> NonTerminal {
+        let nt = self.fresh(lhs, kind);
+        for rhs in bodies {
+            self.helpers.push((nt.clone(), rhs));
+        }
+        nt
+    }
+
+    fn alternatives(
+        &mut self,
+        lhs: &str,
+        alternatives: Vec<Vec<Item>>,
+    ) -> Result<Vec<Rhs>, ParseError> {
+        alternatives
+            .into_iter()
+            .map(|items| self.sequence(lhs, items))
+            .collect()
+    }
+
+    fn sequence(&mut self, lhs: &str, items: Vec<Item>) -> Result<Rhs, ParseError> {
+        let mut rhs = vec![];
+        for item in items {
+            rhs.extend(self.item(lhs, item)?);
+        }
+        Ok(rhs)
+    }
+
+    /// Alternatives matched by a single occurrence of `atom`.
+    fn bodies(&mut self, lhs: &str, atom: Atom) -> Result<Vec<Rhs>, ParseError> {
+        match atom {
+            Atom::Group(alternatives) => self.alternatives(lhs, alternatives),
+            atom => Ok(vec![self.atom(lhs, atom)?]),
+        }
+    }
+
+    fn atom(&mut self, lhs: &str, atom: Atom) -> Result<Rhs, ParseError> {
+        let symbol = match atom {
+            Atom::Ident(s, position) if self.templates.contains_key(&s) => {
+                return Err(position.error(ParseErrorKind::WrongArgumentCount {
+                    expected: self.templates[&s].params.len(),
+                    name: s,
+                    found: 0,
+                }));
+            }
+            Atom::Ident(s, position) => {
+                let nt = NonTerminal(s);
+                if !self.nonterminals.contains(&nt) {
+                    Symbol::Terminal(Terminal(nt.0))
+                } else if self.terminals.contains(&Terminal(nt.0.clone())) {
+                    return Err(position.error(ParseErrorKind::TerminalAndNonTerminal(nt.0)));
+                } else {
+                    Symbol::NonTerminal(nt)
+                }
+            }
+            Atom::Literal(s) => Symbol::Terminal(Terminal(s)),
+            Atom::Group(mut alternatives) => {
+                if alternatives.len() == 1 {
+                    return self.sequence(lhs, alternatives.pop().unwrap());
+                }
+                let bodies = self.alternatives(lhs, alternatives)?;
+                Symbol::NonTerminal(self.helper(lhs, "group", bodies))
+            }
+            Atom::Apply(name, args, position) => {
+                Symbol::NonTerminal(self.instance(name, args, position)?)
+            }
+        };
+        Ok(vec![symbol])
+    }
+
+    fn item(&mut self, lhs: &str, item: Item) -> Result<Rhs, ParseError> {
+        let Some(repeat) = item.repeat else {
+            return self.atom(lhs, item.atom);
+        };
+        let bodies = self.bodies(lhs, item.atom)?;
+        let rhs = match repeat {
+            Repeat::Star | Repeat::Plus => {
+                let nt = self.fresh(lhs, "rep");
+                for body in bodies.iter() {
+                    let mut rhs = body.clone();
+                    rhs.push(Symbol::NonTerminal(nt.clone()));
+                    self.helpers.push((nt.clone(), rhs));
+                }
+                self.helpers.push((nt.clone(), vec![]));
+                let rep = Symbol::NonTerminal(nt);
+                if repeat == Repeat::Star {
+                    vec![rep]
+                } else if bodies.len() == 1 {
+                    let mut rhs = bodies.into_iter().next().unwrap();
+                    rhs.push(rep);
+                    rhs
+                } else {
+                    vec![Symbol::NonTerminal(self.helper(lhs, "group", bodies)), rep]
+                }
+            }
+            Repeat::Optional => {
+                let mut bodies = bodies;
+                bodies.push(vec![]);
+                vec![Symbol::NonTerminal(self.helper(lhs, "opt", bodies))]
+            }
+        };
+        Ok(rhs)
+    }
+}
+
+impl FromStr for Grammar {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::new(s)?.parse()
+    }
+}
+
+impl Grammar {
+    /// Reads and parses the grammar file at `path`, with its imports.
+    pub fn load(path: impl AsRef<Path>) -> Result<Grammar, LoadError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        let loading = vec![path.canonicalize()?];
+        Ok(Parser::new(&source)?.in_file(path, loading).parse()?)
+    }
+}
+
+/// Writes `s` as a double quoted literal.
+pub(crate) fn write_literal(f: &mut impl fmt::Write, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        if matches!(c, '"' | '\\') {
+            f.write_char('\\')?;
+        }
+        f.write_char(c)?;
+    }
+    f.write_char('"')
+}
+
+impl Grammar {
+    fn write_terminal(&self, f: &mut fmt::Formatter<'_>, t: &Terminal) -> fmt::Result {
+        let shadowed = self
+            .nonterminal_id(&t.0)
+            .is_some_and(|nt| self.is_nonterminal(nt));
+        if is_ident(&t.0) && !shadowed {
+            f.write_str(&t.0)
+        } else {
+            write_literal(f, &t.0)
+        }
+    }
+}
+
+/// Writes the grammar in the textual format, so that parsing the output gives
+/// back an equivalent grammar.
+impl fmt::Display for Grammar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut terminals = self
+            .terminals()
+            .iter()
+            .map(|&t| self.terminal(t))
+            .collect::<Vec<_>>();
+        terminals.sort();
+        let mut nonterminals = self
+            .nonterminals()
+            .iter()
+            .map(|&nt| self.nonterminal(nt))
+            .collect::<Vec<_>>();
+        nonterminals.sort();
+
+        if !terminals.is_empty() {
+            f.write_str("%terminal")?;
+            for t in terminals {
+                f.write_str(" ")?;
+                self.write_terminal(f, t)?;
+            }
+            writeln!(f, ";")?;
+        }
+        if !nonterminals.is_empty() {
+            write!(f, "%nonterminal")?;
+            for nt in nonterminals {
+                write!(f, " {}", nt)?;
+            }
+            writeln!(f, ";")?;
+        }
+        writeln!(f, "%start {};", self.nonterminal(self.start()))?;
+        for (associativity, terminals) in self.precedence_levels() {
+            write!(f, "%{}", associativity.directive())?;
+            for &t in terminals {
+                f.write_str(" ")?;
+                self.write_terminal(f, self.terminal(t))?;
+            }
+            writeln!(f, ";")?;
+        }
+
+        let mut productions = self.productions.iter().enumerate().peekable();
+        while let Some((idx, first)) = productions.next() {
+            write!(f, "\n{} ->", self.nonterminal(first.lhs()))?;
+            let (mut idx, mut production) = (idx, first);
+            loop {
+                if production.rhs().is_empty() {
+                    write!(f, " ε")?;
+                }
+                for &symbol in production.rhs() {
+                    f.write_str(" ")?;
+                    match self.symbol(symbol) {
+                        Symbol::Terminal(t) => self.write_terminal(f, t)?,
+                        Symbol::NonTerminal(nt) => write!(f, "{}", nt)?,
+                    }
+                }
+                if let Some(t) = self.precedence_override(idx) {
+                    f.write_str(" %prec ")?;
+                    self.write_terminal(f, self.terminal(t))?;
+                }
+                match productions.next_if(|(_, p)| p.lhs() == first.lhs()) {
+                    Some(next) => {
+                        write!(f, " |")?;
+                        (idx, production) = next;
+                    }
+                    None => break,
+                }
+            }
+            write!(f, ";")?;
+        }
+        writeln!(f)
+    }
+}
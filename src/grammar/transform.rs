@@ -0,0 +1,282 @@
+//! Transformations that rewrite a [`Grammar`] into an equivalent one with some
+//! desired property.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use crate::parse_tree::ParseTree;
+
+use super::{Grammar, NonTerminalId, Production, Symbol, SymbolTable};
+
+mod cnf;
+mod epsilon;
+mod left_factor;
+mod left_recursion;
+mod precedence;
+mod reduce;
+mod unit;
+
+pub use cnf::{to_cnf, Cnf};
+pub use epsilon::remove_epsilon_productions;
+pub use left_factor::left_factor;
+pub use left_recursion::eliminate_left_recursion;
+pub use precedence::stratify_precedence;
+pub use reduce::Reduction;
+pub use unit::remove_unit_productions;
+
+/// Where a production of a transformed grammar came from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Origin {
+    /// Index of the original production whose rhs the production was made from.
+    pub production: usize,
+    /// Indices of the original unit productions that were bypassed to reach
+    /// `production`, outermost first.
+    pub units: Vec<usize>,
+    /// Positions in the rhs of `production` that were erased because they are
+    /// nullable.
+    pub erased: Vec<usize>,
+    /// Positions in the rhs of `production` whose symbols the production has
+    /// itself. This is the whole rhs, unless the rhs was split into a chain of
+    /// fresh nonterminals, where each link has a symbol of the rhs followed by
+    /// the next link, and the last link has the last two symbols.
+    pub span: Range<usize>,
+}
+
+/// The [`Origin`] of each production of a transformed grammar, by index. `None`
+/// marks a production with no counterpart in the original grammar.
+pub type ProductionMap = Vec<Option<Origin>>;
+
+/// Returns the nonterminals of `grammar` in a deterministic order: the start
+/// symbol, then the lhs of each production in order of first appearance, then
+/// any remaining nonterminals sorted by name.
+pub(crate) fn ordered_nonterminals(grammar: &Grammar) -> Vec<NonTerminalId> {
+    let mut seen = HashSet::new();
+    let mut order = vec![];
+    for nt in
+        std::iter::once(grammar.start()).chain(grammar.productions().iter().map(Production::lhs))
+    {
+        if seen.insert(nt) {
+            order.push(nt);
+        }
+    }
+    let mut rest = grammar
+        .nonterminals()
+        .iter()
+        .copied()
+        .filter(|nt| !seen.contains(nt))
+        .collect::<Vec<_>>();
+    rest.sort_by_key(|&nt| grammar.nonterminal(nt));
+    order.extend(rest);
+    order
+}
+
+/// Interns `base` followed by as many `'` as needed to make a name that is not
+/// already in `symbols`.
+pub(crate) fn primed(base: NonTerminalId, symbols: &mut SymbolTable) -> NonTerminalId {
+    let base = symbols.nonterminal(base).0.clone();
+    symbols.fresh_nonterminal((1..).map(|n| format!("{}{}", base, "'".repeat(n))))
+}
+
+/// Interns `base`, or `base` followed by the smallest number that makes it a name
+/// not already in `symbols`.
+pub(crate) fn fresh(base: &str, symbols: &mut SymbolTable) -> NonTerminalId {
+    symbols.fresh_nonterminal(
+        std::iter::once(base.to_string()).chain((1..).map(|n| format!("{}{}", base, n))),
+    )
+}
+
+/// Splits the rhs of every production with more than two symbols for which
+/// `split` holds into a chain `A -> X1 A_1`, `A_1 -> X2 A_2`, ...,
+/// `A_n-2 -> Xn-1 Xn` of fresh synthetic nonterminals. Every production maps to
+/// the one it was made from, with the [`span`](Origin::span) it covers.
+pub(crate) fn binarise(
+    grammar: &Grammar,
+    split: impl Fn(&Production) -> bool,
+) -> (Grammar, ProductionMap) {
+    let mut symbols = grammar.symbols().clone();
+    let mut nonterminals = grammar.nonterminals().to_vec();
+    let mut synthetic = grammar.synthetic.clone();
+    let mut productions = vec![];
+    let mut map = vec![];
+    let mut split_counts = HashMap::<NonTerminalId, usize>::new();
+    for (idx, production) in grammar.productions().iter().enumerate() {
+        let rhs = production.rhs();
+        let origin = |span| {
+            Some(Origin {
+                production: idx,
+                units: vec![],
+                erased: vec![],
+                span,
+            })
+        };
+        if rhs.len() <= 2 || !split(production) {
+            productions.push(production.clone());
+            map.push(origin(0..rhs.len()));
+            continue;
+        }
+        let mut lhs = production.lhs();
+        for (i, &symbol) in rhs[..rhs.len() - 2].iter().enumerate() {
+            let count = split_counts.entry(production.lhs()).or_default();
+            *count += 1;
+            let base = format!("{}_{}", grammar.nonterminal(production.lhs()), count);
+            let link = fresh(&base, &mut symbols);
+            nonterminals.push(link);
+            synthetic.insert(link);
+            productions.push(Production::new(
+                lhs,
+                vec![symbol, Symbol::NonTerminal(link)],
+            ));
+            map.push(origin(i..i + 1));
+            lhs = link;
+        }
+        productions.push(Production::new(lhs, rhs[rhs.len() - 2..].to_vec()));
+        map.push(origin(rhs.len() - 2..rhs.len()));
+    }
+
+    let grammar = Grammar::from_parts(
+        symbols,
+        nonterminals,
+        grammar.terminals().iter().copied(),
+        productions,
+        grammar.start(),
+        synthetic,
+    );
+    (grammar, map)
+}
+
+/// A derivation tree that records the production used at each node, so that it
+/// can be carried back through a transform with its [`ProductionMap`].
+#[derive(Debug, Clone)]
+pub(crate) enum Derivation {
+    /// A terminal. Which one is given by the rhs of the parent's production.
+    Leaf,
+    /// The production at the given index with one child per rhs symbol.
+    Node(usize, Vec<Derivation>),
+}
+
+impl Derivation {
+    pub(crate) fn to_parse_tree<'a>(&self, grammar: &'a Grammar) -> ParseTree<'a> {
+        let Derivation::Node(idx, children) = self else {
+            panic!("Leaf at the root of a derivation");
+        };
+        let production = &grammar.productions()[*idx];
+        ParseTree::NonTerminal(
+            grammar.nonterminal(production.lhs()),
+            production
+                .rhs()
+                .iter()
+                .zip(children)
+                .map(|(&symbol, child)| match symbol {
+                    Symbol::Terminal(t) => ParseTree::Terminal(grammar.terminal(t)),
+                    Symbol::NonTerminal(_) => child.to_parse_tree(grammar),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Derivations of ε for the nullable nonterminals of a grammar.
+pub(crate) struct EpsilonDerivations {
+    /// Index of a production with an all nullable rhs for each nullable
+    /// nonterminal, chosen so that expanding them always terminates.
+    productions: HashMap<NonTerminalId, usize>,
+}
+
+impl EpsilonDerivations {
+    pub(crate) fn new(grammar: &Grammar) -> Self {
+        let mut productions = HashMap::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (idx, production) in grammar.productions().iter().enumerate() {
+                if !productions.contains_key(&production.lhs())
+                    && production.rhs().iter().all(|symbol| match symbol {
+                        Symbol::Terminal(_) => false,
+                        Symbol::NonTerminal(nt) => productions.contains_key(nt),
+                    })
+                {
+                    productions.insert(production.lhs(), idx);
+                    changed = true;
+                }
+            }
+        }
+        Self { productions }
+    }
+
+    pub(crate) fn derive(&self, grammar: &Grammar, nt: NonTerminalId) -> Derivation {
+        let idx = self.productions[&nt];
+        let children = grammar.productions()[idx]
+            .rhs()
+            .iter()
+            .map(|&symbol| match symbol {
+                Symbol::NonTerminal(nt) => self.derive(grammar, nt),
+                Symbol::Terminal(_) => unreachable!("Terminal in an ε-derivation"),
+            })
+            .collect();
+        Derivation::Node(idx, children)
+    }
+}
+
+/// Turns a derivation in a transformed grammar into one in `original`.
+///
+/// A production that maps to `None` must be one of the productions `S' -> S` or
+/// `S' -> ε` added for a fresh start symbol by
+/// [`remove_epsilon_productions`]. `epsilon` is only used for erased positions.
+pub(crate) fn undo(
+    derivation: Derivation,
+    map: &ProductionMap,
+    original: &Grammar,
+    epsilon: &EpsilonDerivations,
+) -> Derivation {
+    let Derivation::Node(idx, children) = derivation else {
+        return Derivation::Leaf;
+    };
+    let Some(origin) = &map[idx] else {
+        return children.into_iter().next().map_or_else(
+            || epsilon.derive(original, original.start()),
+            |child| undo(child, map, original, epsilon),
+        );
+    };
+    let mut full = vec![];
+    undo_link(origin, children, map, original, epsilon, &mut full);
+    origin
+        .units
+        .iter()
+        .rev()
+        .fold(Derivation::Node(origin.production, full), |inner, &unit| {
+            Derivation::Node(unit, vec![inner])
+        })
+}
+
+/// Appends the children of `origin.production` from the start of the span of
+/// `origin` to `full`, following the chain of links to the end of the rhs.
+fn undo_link(
+    origin: &Origin,
+    children: Vec<Derivation>,
+    map: &ProductionMap,
+    original: &Grammar,
+    epsilon: &EpsilonDerivations,
+    full: &mut Vec<Derivation>,
+) {
+    let rhs = original.productions()[origin.production].rhs();
+    let mut children = children.into_iter();
+    for (i, &symbol) in rhs.iter().enumerate().skip(origin.span.start) {
+        match symbol {
+            Symbol::NonTerminal(nt) if origin.erased.contains(&i) => {
+                full.push(epsilon.derive(original, nt))
+            }
+            _ if i < origin.span.end => {
+                full.push(undo(children.next().unwrap(), map, original, epsilon))
+            }
+            _ => {
+                let Some(Derivation::Node(next, grandchildren)) = children.next() else {
+                    unreachable!("Chain without a next link")
+                };
+                let next = map[next].as_ref().expect("Link without an origin");
+                return undo_link(next, grandchildren, map, original, epsilon, full);
+            }
+        }
+    }
+}
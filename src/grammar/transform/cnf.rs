@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use crate::{
+    grammar::{text::is_ident, Grammar, Production, Symbol},
+    parse_tree::ParseTree,
+};
+
+use super::{
+    binarise, fresh, remove_epsilon_productions, remove_unit_productions, undo, Derivation,
+    EpsilonDerivations, ProductionMap,
+};
+
+/// A grammar in Chomsky normal form, together with what is needed to turn its
+/// derivations back into parse trees of the grammar it was made from.
+///
+/// Every production of [`grammar`](Self::grammar) has the form `A -> B C` or
+/// `A -> a`, except for `S -> ε` when `S` is the start symbol and the original
+/// grammar accepts the empty string. The start symbol then never appears on a
+/// rhs.
+#[derive(Debug, Clone)]
+pub struct Cnf<'g> {
+    original: &'g Grammar,
+    /// `original` with every rhs split into chains of at most two symbols.
+    binarised: Grammar,
+    binary_map: ProductionMap,
+    /// `binarised` without ε-productions.
+    epsilon_free: Grammar,
+    epsilon_map: ProductionMap,
+    /// `epsilon_free` without unit productions.
+    unit_map: ProductionMap,
+    grammar: Grammar,
+    /// For each production of `grammar`, the production of the unit free grammar
+    /// it was made from, or `None` if it was introduced to wrap a terminal.
+    heads: Vec<Option<usize>>,
+}
+
+impl<'g> Cnf<'g> {
+    pub fn grammar(&self) -> &Grammar {
+        &self.grammar
+    }
+
+    pub fn original(&self) -> &'g Grammar {
+        self.original
+    }
+
+    /// Turns a derivation in [`grammar`](Self::grammar) into a derivation in the
+    /// unit free grammar by replacing terminal wrappers with their terminal.
+    fn unwrap_terminals(&self, derivation: Derivation) -> Derivation {
+        match derivation {
+            Derivation::Node(idx, children) => match self.heads[idx] {
+                Some(head) => Derivation::Node(
+                    head,
+                    children
+                        .into_iter()
+                        .map(|child| self.unwrap_terminals(child))
+                        .collect(),
+                ),
+                None => Derivation::Leaf,
+            },
+            Derivation::Leaf => Derivation::Leaf,
+        }
+    }
+
+    /// Turns a derivation in [`grammar`](Self::grammar) into a parse tree of the
+    /// original grammar.
+    pub(crate) fn to_original(&self, derivation: Derivation) -> ParseTree<'g> {
+        let derivation = self.unwrap_terminals(derivation);
+        let derivation = undo(
+            derivation,
+            &self.unit_map,
+            &self.epsilon_free,
+            &EpsilonDerivations::new(&self.epsilon_free),
+        );
+        let derivation = undo(
+            derivation,
+            &self.epsilon_map,
+            &self.binarised,
+            &EpsilonDerivations::new(&self.binarised),
+        );
+        let derivation = undo(
+            derivation,
+            &self.binary_map,
+            self.original,
+            &EpsilonDerivations::new(self.original),
+        );
+        derivation.to_parse_tree(self.original)
+    }
+}
+
+/// Converts `grammar` to Chomsky normal form.
+///
+/// First each production `A -> X1 X2 ... Xn` with `n > 2` is split into
+/// `A -> X1 A_1`, `A_1 -> X2 A_2`, ..., `A_n-2 -> Xn-1 Xn`, so that
+/// ε-productions and unit productions can then be removed with
+/// [`remove_epsilon_productions`] and [`remove_unit_productions`] without
+/// making more than four copies of any production. Finally each terminal `a` in
+/// a rhs of length two is replaced by a new nonterminal `T_a` with the
+/// production `T_a -> a`. All new nonterminals are synthetic.
+pub fn to_cnf(grammar: &Grammar) -> Cnf<'_> {
+    let (binarised, binary_map) = binarise(grammar, |_| true);
+    let (epsilon_free, epsilon_map) = remove_epsilon_productions(&binarised);
+    let (unit_free, unit_map) = remove_unit_productions(&epsilon_free);
+
+    let mut names = unit_free.symbols().clone();
+    let mut nonterminals = unit_free.nonterminals().to_vec();
+    let mut synthetic = unit_free.synthetic.clone();
+    let mut productions = vec![];
+    let mut heads = vec![];
+
+    let mut wrappers = HashMap::new();
+    for (idx, production) in unit_free.productions().iter().enumerate() {
+        if production.rhs().len() < 2 {
+            productions.push(production.clone());
+            heads.push(Some(idx));
+            continue;
+        }
+
+        let mut symbols = vec![];
+        for &symbol in production.rhs() {
+            let nt = match symbol {
+                Symbol::NonTerminal(nt) => nt,
+                Symbol::Terminal(t) => {
+                    let count = wrappers.len();
+                    *wrappers.entry(t).or_insert_with(|| {
+                        let name = &unit_free.terminal(t).0;
+                        let base = if is_ident(name) {
+                            format!("T_{}", name)
+                        } else {
+                            format!("T_{}", count)
+                        };
+                        let wrapper = fresh(&base, &mut names);
+                        productions.push(Production::new(wrapper, vec![symbol]));
+                        heads.push(None);
+                        nonterminals.push(wrapper);
+                        synthetic.insert(wrapper);
+                        wrapper
+                    })
+                }
+            };
+            symbols.push(Symbol::NonTerminal(nt));
+        }
+        productions.push(Production::new(production.lhs(), symbols));
+        heads.push(Some(idx));
+    }
+
+    let cnf = Grammar::from_parts(
+        names,
+        nonterminals,
+        unit_free.terminals().iter().copied(),
+        productions,
+        unit_free.start(),
+        synthetic,
+    );
+    Cnf {
+        original: grammar,
+        binarised,
+        binary_map,
+        epsilon_free,
+        epsilon_map,
+        unit_map,
+        grammar: cnf,
+        heads,
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+
+use crate::grammar::{Grammar, Production, Symbol};
+
+use super::{binarise, primed, Origin, ProductionMap};
+
+/// Removes every ε-production from `grammar` without changing the language,
+/// except for the empty string.
+///
+/// Each production `A -> X1 ... Xn` is replaced by a copy for every way of
+/// erasing some of its nullable `Xi`, leaving out copies with an empty rhs and
+/// duplicates. A production with more than two nullable symbols is first split
+/// into a chain of fresh synthetic nonterminals, as in [`to_cnf`](super::to_cnf),
+/// so that no production has more than four copies. If the start symbol `S` is
+/// nullable, a fresh synthetic start symbol `S'` is added with the productions
+/// `S' -> S` and `S' -> ε`, so the empty string is still accepted; these two
+/// productions map to `None`.
+pub fn remove_epsilon_productions(grammar: &Grammar) -> (Grammar, ProductionMap) {
+    let nullable_count = |production: &Production| {
+        production
+            .rhs()
+            .iter()
+            .filter(|&&symbol| matches!(symbol, Symbol::NonTerminal(nt) if grammar.is_nullable(nt)))
+            .count()
+    };
+    let (split, links) = binarise(grammar, |production| nullable_count(production) > 2);
+
+    let mut seen = HashSet::new();
+    let mut productions = vec![];
+    let mut map = vec![];
+    for (idx, production) in split.productions().iter().enumerate() {
+        let link = links[idx].as_ref().unwrap();
+        let len = grammar.productions()[link.production].rhs().len();
+        let positions = production
+            .rhs()
+            .iter()
+            .enumerate()
+            .filter(
+                |(_, &symbol)| matches!(symbol, Symbol::NonTerminal(nt) if split.is_nullable(nt)),
+            )
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        for mask in 0..1usize << positions.len() {
+            let erased = positions
+                .iter()
+                .enumerate()
+                .filter(|(bit, _)| mask & 1 << bit != 0)
+                .map(|(_, &i)| i)
+                .collect::<Vec<_>>();
+            let rhs = production
+                .rhs()
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !erased.contains(i))
+                .map(|(_, &symbol)| symbol)
+                .collect::<Vec<_>>();
+            if rhs.is_empty() || !seen.insert((production.lhs(), rhs.clone())) {
+                continue;
+            }
+            productions.push(Production::new(production.lhs(), rhs));
+            // Erasing the next link of a chain erases the rest of the rhs.
+            let erased = erased
+                .into_iter()
+                .flat_map(|i| {
+                    if i < link.span.len() {
+                        link.span.start + i..link.span.start + i + 1
+                    } else {
+                        link.span.end..len
+                    }
+                })
+                .collect();
+            map.push(Some(Origin {
+                production: link.production,
+                units: vec![],
+                erased,
+                span: link.span.clone(),
+            }));
+        }
+    }
+
+    let mut symbols = split.symbols().clone();
+    let mut nonterminals = split.nonterminals().to_vec();
+    let mut synthetic = split.synthetic.clone();
+    let mut start = split.start();
+    if split.is_nullable(start) {
+        let fresh = primed(start, &mut symbols);
+        productions.splice(
+            0..0,
+            [
+                Production::new(fresh, vec![Symbol::NonTerminal(start)]),
+                Production::new(fresh, vec![]),
+            ],
+        );
+        map.splice(0..0, [None, None]);
+        nonterminals.push(fresh);
+        synthetic.insert(fresh);
+        start = fresh;
+    }
+
+    let grammar = Grammar::from_parts(
+        symbols,
+        nonterminals,
+        split.terminals().iter().copied(),
+        productions,
+        start,
+        synthetic,
+    );
+    (grammar, map)
+}
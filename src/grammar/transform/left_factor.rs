@@ -0,0 +1,118 @@
+use crate::grammar::{Grammar, NonTerminalId, Production, Symbol, SymbolId, SymbolTable};
+
+use super::{ordered_nonterminals, primed};
+
+type Rhs = Vec<SymbolId>;
+
+fn common_prefix_len(alternatives: &[Rhs]) -> usize {
+    let (first, rest) = alternatives.split_first().unwrap();
+    (0..first.len())
+        .take_while(|&i| rest.iter().all(|rhs| rhs.get(i) == Some(&first[i])))
+        .count()
+}
+
+/// Factors the alternatives of `lhs` and pushes the resulting rules for `lhs`
+/// and its helpers onto `rules`. Returns if anything was factored.
+fn factor(
+    lhs: NonTerminalId,
+    alternatives: Vec<Rhs>,
+    symbols: &mut SymbolTable,
+    rules: &mut Vec<(NonTerminalId, Vec<Rhs>)>,
+) -> bool {
+    // Alternatives grouped by their first symbol, in order of first appearance.
+    let mut groups: Vec<Vec<Rhs>> = vec![];
+    for rhs in alternatives {
+        match groups
+            .iter_mut()
+            .find(|group| !rhs.is_empty() && group[0].first() == rhs.first())
+        {
+            Some(group) => group.push(rhs),
+            None => groups.push(vec![rhs]),
+        }
+    }
+
+    let mut factored = vec![];
+    let mut helpers = vec![];
+    for group in groups {
+        if group.len() == 1 {
+            factored.extend(group);
+            continue;
+        }
+        let len = common_prefix_len(&group);
+        let helper = primed(lhs, symbols);
+        let mut rhs = group[0][..len].to_vec();
+        rhs.push(Symbol::NonTerminal(helper));
+        factored.push(rhs);
+
+        let mut suffixes = vec![];
+        for rhs in group {
+            let suffix = rhs[len..].to_vec();
+            if !suffixes.contains(&suffix) {
+                suffixes.push(suffix);
+            }
+        }
+        helpers.push((helper, suffixes));
+    }
+
+    let changed = !helpers.is_empty();
+    rules.push((lhs, factored));
+    for (helper, suffixes) in helpers {
+        factor(helper, suffixes, symbols, rules);
+    }
+    changed
+}
+
+/// Left factors `grammar`, so that no two alternatives of a nonterminal start
+/// with the same symbol.
+///
+/// The alternatives of each nonterminal `A` that start with the same symbol are
+/// replaced by their longest common prefix `α` followed by a new nonterminal
+/// `A'`, whose alternatives are the remaining suffixes:
+///
+/// ```text
+/// A -> α β1 | ... | α βn | γ
+/// ```
+///
+/// becomes
+///
+/// ```text
+/// A  -> α A' | γ
+/// A' -> β1 | ... | βn
+/// ```
+///
+/// This is repeated on the new nonterminals until nothing changes. Also returns
+/// the nonterminals of `grammar` that were factored, in the order they were
+/// processed.
+pub fn left_factor(grammar: &Grammar) -> (Grammar, Vec<NonTerminalId>) {
+    let mut symbols = grammar.symbols().clone();
+    let mut rules = vec![];
+    let mut changed = vec![];
+    for nt in ordered_nonterminals(grammar) {
+        let alternatives = grammar
+            .productions_from(nt)
+            .map(|p| p.rhs().to_vec())
+            .collect();
+        if factor(nt, alternatives, &mut symbols, &mut rules) {
+            changed.push(nt);
+        }
+    }
+
+    let mut nonterminals = grammar.nonterminals().to_vec();
+    let mut productions = vec![];
+    for (lhs, alternatives) in rules {
+        for rhs in alternatives {
+            productions.push(Production::new(lhs, rhs));
+        }
+        nonterminals.push(lhs);
+    }
+
+    let grammar = Grammar::from_parts(
+        symbols,
+        nonterminals,
+        grammar.terminals().iter().copied(),
+        productions,
+        grammar.start(),
+        grammar.synthetic.clone(),
+    );
+    (grammar, changed)
+}
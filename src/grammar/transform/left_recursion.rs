@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use crate::grammar::{Grammar, Production, Symbol, SymbolId};
+
+use super::{ordered_nonterminals, primed};
+
+type Rhs = Vec<SymbolId>;
+
+/// Removes direct and indirect left recursion from `grammar`.
+///
+/// This is the standard algorithm: the nonterminals are ordered `A1, ..., An`
+/// (see [`ordered_nonterminals`]), and for each `Ai` every production
+/// `Ai -> Aj γ` with `j < i` is replaced by `Ai -> δ γ` for each production
+/// `Aj -> δ`, after which the direct left recursion of `Ai` is removed with a
+/// new nonterminal `Ai'`:
+///
+/// ```text
+/// A -> A α1 | ... | A αm | β1 | ... | βn
+/// ```
+///
+/// becomes
+///
+/// ```text
+/// A  -> β1 A' | ... | βn A'
+/// A' -> α1 A' | ... | αm A' | ε
+/// ```
+///
+/// Productions `A -> A` are dropped. As usual, the result is only guaranteed to
+/// be free of left recursion if `grammar` has no ε-productions and no cycles
+/// `A =>+ A`; left recursion hidden behind a nullable prefix is not removed.
+pub fn eliminate_left_recursion(grammar: &Grammar) -> Grammar {
+    let order = ordered_nonterminals(grammar);
+    let mut symbols = grammar.symbols().clone();
+
+    let mut rules = order
+        .iter()
+        .map(|&nt| (nt, vec![]))
+        .collect::<HashMap<_, Vec<Rhs>>>();
+    for production in grammar.productions() {
+        rules
+            .get_mut(&production.lhs())
+            .unwrap()
+            .push(production.rhs().to_vec());
+    }
+
+    let mut helpers = HashMap::new();
+    for (i, &ai) in order.iter().enumerate() {
+        for &aj in order[..i].iter() {
+            let alternatives = std::mem::take(rules.get_mut(&ai).unwrap());
+            let mut substituted = vec![];
+            for rhs in alternatives {
+                match rhs.first() {
+                    Some(&Symbol::NonTerminal(first)) if first == aj => {
+                        for delta in rules[&aj].iter() {
+                            let mut new = delta.clone();
+                            new.extend_from_slice(&rhs[1..]);
+                            substituted.push(new);
+                        }
+                    }
+                    _ => substituted.push(rhs),
+                }
+            }
+            *rules.get_mut(&ai).unwrap() = substituted;
+        }
+
+        let (recursive, other): (Vec<_>, Vec<_>) =
+            rules.remove(&ai).unwrap().into_iter().partition(
+                |rhs| matches!(rhs.first(), Some(&Symbol::NonTerminal(first)) if first == ai),
+            );
+        if recursive.is_empty() {
+            rules.insert(ai, other);
+            continue;
+        }
+
+        let helper = primed(ai, &mut symbols);
+        let tail = Symbol::NonTerminal(helper);
+        rules.insert(
+            ai,
+            other
+                .into_iter()
+                .map(|mut beta| {
+                    beta.push(tail);
+                    beta
+                })
+                .collect(),
+        );
+        let mut helper_rules = recursive
+            .into_iter()
+            .filter(|rhs| rhs.len() > 1)
+            .map(|mut alpha| {
+                alpha.remove(0);
+                alpha.push(tail);
+                alpha
+            })
+            .collect::<Vec<_>>();
+        helper_rules.push(vec![]);
+        helpers.insert(ai, (helper, helper_rules));
+    }
+
+    let mut nonterminals = grammar.nonterminals().to_vec();
+    let mut productions = vec![];
+    for nt in order {
+        for rhs in rules.remove(&nt).unwrap() {
+            productions.push(Production::new(nt, rhs));
+        }
+        if let Some((helper, helper_rules)) = helpers.remove(&nt) {
+            for rhs in helper_rules {
+                productions.push(Production::new(helper, rhs));
+            }
+            nonterminals.push(helper);
+        }
+    }
+
+    Grammar::from_parts(
+        symbols,
+        nonterminals,
+        grammar.terminals().iter().copied(),
+        productions,
+        grammar.start(),
+        grammar.synthetic.clone(),
+    )
+}
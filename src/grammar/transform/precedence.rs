@@ -0,0 +1,149 @@
+use crate::grammar::{Associativity, Grammar, NonTerminalId, Production, Symbol, SymbolId};
+
+use super::{fresh, ordered_nonterminals};
+
+/// The shape of an operator production of `E`, by where `E` occurs in its rhs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fixity {
+    /// `E -> E op E`, or any rhs that starts and ends with `E`.
+    Infix,
+    /// `E -> op E`.
+    Prefix,
+    /// `E -> E op`.
+    Postfix,
+}
+
+fn fixity(nt: NonTerminalId, rhs: &[SymbolId]) -> Option<Fixity> {
+    let is_nt = |symbol: Option<&SymbolId>| symbol == Some(&Symbol::NonTerminal(nt));
+    if rhs.len() < 2 {
+        return None;
+    }
+    match (is_nt(rhs.first()), is_nt(rhs.last())) {
+        (true, true) => Some(Fixity::Infix),
+        (false, true) => Some(Fixity::Prefix),
+        (true, false) => Some(Fixity::Postfix),
+        (false, false) => None,
+    }
+}
+
+/// Compiles the precedence declarations of `grammar` into its productions, so
+/// that the result is unambiguous where the declarations resolved every
+/// ambiguity.
+///
+/// The operator productions of a nonterminal `E` are those with a precedence
+/// (see [`Grammar::production_precedence`]) whose rhs starts or ends with `E`.
+/// With their distinct levels `l1 < ... < lm`, `E` is split into one
+/// nonterminal per level, `E` itself for `l1` and `E_prec2`, ..., `E_precm`
+/// for the others, and `E_prec{m+1}` for the remaining productions:
+///
+/// ```text
+/// %left "+"; %left "*";
+/// E -> E "+" E | E "*" E | "(" E ")" | x;
+/// ```
+///
+/// becomes
+///
+/// ```text
+/// E       -> E "+" E_prec2 | E_prec2
+/// E_prec2 -> E_prec2 "*" E_prec3 | E_prec3
+/// E_prec3 -> "(" E ")" | x
+/// ```
+///
+/// An operand on the side the associativity allows stays on the operator's
+/// level, and every other leading or trailing operand moves one level up. The
+/// operand of a prefix or postfix operator stays on its level, and occurrences
+/// of `E` inside an rhs are left alone. Unlike the filter of
+/// [`earley::parse`](crate::earley::parse), this rejects a prefix operator
+/// that binds looser than an infix operator as its right operand, as `not` in
+/// `a == not b`.
+///
+/// The result has no precedence declarations and keeps the left recursion of
+/// left-associative operators, so it needs [`eliminate_left_recursion`] and
+/// usually [`left_factor`] before [`ll1`](crate::ll1) can use it.
+///
+/// [`eliminate_left_recursion`]: super::eliminate_left_recursion
+/// [`left_factor`]: super::left_factor
+pub fn stratify_precedence(grammar: &Grammar) -> Grammar {
+    let mut symbols = grammar.symbols().clone();
+    let mut nonterminals = grammar.nonterminals().to_vec();
+    let mut productions = vec![];
+
+    for nt in ordered_nonterminals(grammar) {
+        let operator = |idx: usize| {
+            let precedence = grammar.production_precedence(idx)?;
+            let fixity = fixity(nt, grammar.productions()[idx].rhs())?;
+            Some((precedence, fixity))
+        };
+        let mut levels = grammar
+            .production_indices(nt)
+            .iter()
+            .filter_map(|&idx| operator(idx).map(|(precedence, _)| precedence.level))
+            .collect::<Vec<_>>();
+        levels.sort_unstable();
+        levels.dedup();
+        if levels.is_empty() {
+            productions.extend(grammar.productions_from(nt).cloned());
+            continue;
+        }
+
+        let name = grammar.nonterminal(nt).0.clone();
+        let mut strata = vec![nt];
+        for k in 2..=levels.len() + 1 {
+            let stratum = fresh(&format!("{}_prec{}", name, k), &mut symbols);
+            nonterminals.push(stratum);
+            strata.push(stratum);
+        }
+
+        for (k, &level) in levels.iter().enumerate() {
+            let (same, next) = (
+                Symbol::NonTerminal(strata[k]),
+                Symbol::NonTerminal(strata[k + 1]),
+            );
+            for &idx in grammar.production_indices(nt) {
+                let Some((precedence, fixity)) = operator(idx) else {
+                    continue;
+                };
+                if precedence.level != level {
+                    continue;
+                }
+                let mut rhs = grammar.productions()[idx].rhs().to_vec();
+                let last = rhs.len() - 1;
+                match fixity {
+                    Fixity::Infix => {
+                        let associativity = precedence.associativity;
+                        rhs[0] = if associativity == Associativity::Left {
+                            same
+                        } else {
+                            next
+                        };
+                        rhs[last] = if associativity == Associativity::Right {
+                            same
+                        } else {
+                            next
+                        };
+                    }
+                    Fixity::Prefix => rhs[last] = same,
+                    Fixity::Postfix => rhs[0] = same,
+                }
+                productions.push(Production::new(strata[k], rhs));
+            }
+            productions.push(Production::new(strata[k], vec![next]));
+        }
+        let atoms = *strata.last().unwrap();
+        for &idx in grammar.production_indices(nt) {
+            if operator(idx).is_none() {
+                let rhs = grammar.productions()[idx].rhs().to_vec();
+                productions.push(Production::new(atoms, rhs));
+            }
+        }
+    }
+
+    Grammar::from_parts(
+        symbols,
+        nonterminals,
+        grammar.terminals().iter().copied(),
+        productions,
+        grammar.start(),
+        grammar.synthetic.clone(),
+    )
+}
@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+
+use crate::grammar::{
+    validate::reachable_symbols, Grammar, NonTerminal, Symbol, SymbolId, Terminal,
+};
+
+/// What [`Grammar::reduce`] removed. Symbols are sorted by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reduction {
+    /// Nonterminals that derive no string of terminals. The start symbol is
+    /// listed here if the language is empty, but is kept in the grammar.
+    pub unproductive: Vec<NonTerminal>,
+    /// Productive nonterminals that no sentential form of the start symbol uses.
+    pub unreachable_nonterminals: Vec<NonTerminal>,
+    /// Declared terminals that no remaining production uses.
+    pub unreachable_terminals: Vec<Terminal>,
+    /// Indices into the original [`Grammar::productions`] of removed productions.
+    pub productions: Vec<usize>,
+}
+
+impl Reduction {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+impl Grammar {
+    /// Removes useless symbols and the productions that use them.
+    ///
+    /// First every production that mentions an unproductive nonterminal is
+    /// dropped, then every production of a nonterminal that is unreachable from
+    /// the start symbol in what is left. (The other order can leave useless
+    /// symbols behind.) In the result, the nonterminals are the start symbol and
+    /// the lhs of each production, and the terminals are the declared terminals
+    /// used by some production.
+    pub fn reduce(&self) -> (Grammar, Reduction) {
+        let is_productive = |symbol: &SymbolId| match *symbol {
+            Symbol::Terminal(_) => true,
+            Symbol::NonTerminal(nt) => self.is_productive(nt),
+        };
+        let productive_grammar = Grammar::from_parts(
+            self.symbols.clone(),
+            self.nonterminals().iter().copied(),
+            self.terminals().iter().copied(),
+            self.productions()
+                .iter()
+                .filter(|p| self.is_productive(p.lhs()) && p.rhs().iter().all(is_productive))
+                .cloned()
+                .collect(),
+            self.start(),
+            self.synthetic.clone(),
+        );
+        let (reachable, _) = reachable_symbols(&productive_grammar);
+
+        let mut kept = vec![];
+        let mut removed = vec![];
+        for (idx, production) in self.productions().iter().enumerate() {
+            if self.is_productive(production.lhs())
+                && reachable.contains(&production.lhs())
+                && production.rhs().iter().all(is_productive)
+            {
+                kept.push(production.clone());
+            } else {
+                removed.push(idx);
+            }
+        }
+
+        let mut nonterminals = HashSet::from([self.start()]);
+        nonterminals.extend(kept.iter().map(|p| p.lhs()));
+        let used_terminals = kept
+            .iter()
+            .flat_map(|p| p.rhs())
+            .filter_map(|&symbol| match symbol {
+                Symbol::Terminal(t) => Some(t),
+                Symbol::NonTerminal(_) => None,
+            })
+            .collect::<HashSet<_>>();
+        let terminals = self
+            .terminals()
+            .iter()
+            .filter(|t| used_terminals.contains(t))
+            .copied()
+            .collect::<HashSet<_>>();
+
+        let mut unproductive = self
+            .nonterminals()
+            .iter()
+            .chain(std::iter::once(&self.start()))
+            .filter(|&&nt| !self.is_productive(nt))
+            .map(|&nt| self.nonterminal(nt).clone())
+            .collect::<Vec<_>>();
+        unproductive.sort();
+        unproductive.dedup();
+        let mut unreachable_nonterminals = self
+            .nonterminals()
+            .iter()
+            .filter(|&&nt| self.is_productive(nt) && !reachable.contains(&nt))
+            .map(|&nt| self.nonterminal(nt).clone())
+            .collect::<Vec<_>>();
+        unreachable_nonterminals.sort();
+        let mut unreachable_terminals = self
+            .terminals()
+            .iter()
+            .filter(|t| !terminals.contains(t))
+            .map(|&t| self.terminal(t).clone())
+            .collect::<Vec<_>>();
+        unreachable_terminals.sort();
+
+        let synthetic = self
+            .synthetic
+            .iter()
+            .filter(|nt| nonterminals.contains(nt))
+            .copied()
+            .collect();
+        let grammar = Grammar::from_parts(
+            self.symbols.clone(),
+            nonterminals,
+            terminals,
+            kept,
+            self.start(),
+            synthetic,
+        );
+        let reduction = Reduction {
+            unproductive,
+            unreachable_nonterminals,
+            unreachable_terminals,
+            productions: removed,
+        };
+        (grammar, reduction)
+    }
+}
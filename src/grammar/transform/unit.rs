@@ -0,0 +1,75 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::grammar::{Grammar, NonTerminalId, Production, Symbol};
+
+use super::{ordered_nonterminals, Origin, ProductionMap};
+
+fn is_unit(production: &Production) -> bool {
+    matches!(production.rhs(), [Symbol::NonTerminal(_)])
+}
+
+/// Returns every nonterminal reachable from `from` through unit productions,
+/// including `from` itself, with the indices of the shortest chain of unit
+/// productions leading to it.
+fn unit_closure(grammar: &Grammar, from: NonTerminalId) -> Vec<(NonTerminalId, Vec<usize>)> {
+    let mut chains = HashMap::from([(from, vec![])]);
+    let mut order = vec![from];
+    let mut queue = VecDeque::from([from]);
+    while let Some(nt) = queue.pop_front() {
+        for &idx in grammar.production_indices(nt) {
+            let [Symbol::NonTerminal(next)] = *grammar.productions()[idx].rhs() else {
+                continue;
+            };
+            if !chains.contains_key(&next) {
+                let mut chain = chains[&nt].clone();
+                chain.push(idx);
+                chains.insert(next, chain);
+                order.push(next);
+                queue.push_back(next);
+            }
+        }
+    }
+    order
+        .into_iter()
+        .map(|nt| (nt, chains.remove(&nt).unwrap()))
+        .collect()
+}
+
+/// Removes every unit production `A -> B` from `grammar` without changing the
+/// language.
+///
+/// For each pair `A =>* B` through unit productions, `A` gets a copy of every
+/// production of `B` that is not a unit production. The [`Origin`] of a copy
+/// records the bypassed unit productions along the shortest such chain.
+pub fn remove_unit_productions(grammar: &Grammar) -> (Grammar, ProductionMap) {
+    let mut seen = HashSet::new();
+    let mut productions = vec![];
+    let mut map = vec![];
+    for lhs in ordered_nonterminals(grammar) {
+        for (nt, units) in unit_closure(grammar, lhs) {
+            for &idx in grammar.production_indices(nt) {
+                let production = &grammar.productions()[idx];
+                if is_unit(production) || !seen.insert((lhs, production.rhs())) {
+                    continue;
+                }
+                productions.push(Production::new(lhs, production.rhs().to_vec()));
+                map.push(Some(Origin {
+                    production: idx,
+                    units: units.clone(),
+                    erased: vec![],
+                    span: 0..production.rhs().len(),
+                }));
+            }
+        }
+    }
+
+    let grammar = Grammar::from_parts(
+        grammar.symbols().clone(),
+        grammar.nonterminals().iter().copied(),
+        grammar.terminals().iter().copied(),
+        productions,
+        grammar.start(),
+        grammar.synthetic.clone(),
+    );
+    (grammar, map)
+}
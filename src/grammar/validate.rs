@@ -0,0 +1,250 @@
+//! Static checks for mistakes in a [`Grammar`].
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use super::{Grammar, NonTerminal, NonTerminalId, Symbol, Terminal, TerminalId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The grammar is usable but probably not what was intended.
+    Warning,
+    /// The grammar is malformed.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+    /// A terminal on a rhs is missing from [`Grammar::terminals`].
+    UndeclaredTerminal(Terminal),
+    /// A nonterminal on a rhs is missing from [`Grammar::nonterminals`].
+    UndefinedNonTerminal(NonTerminal),
+    /// A nonterminal has no productions.
+    NoProductions(NonTerminal),
+    /// The start symbol is not a nonterminal of the grammar.
+    UndefinedStart(NonTerminal),
+    UnreachableNonTerminal(NonTerminal),
+    UnreachableTerminal(Terminal),
+    /// A nonterminal derives no string of terminals.
+    UnproductiveNonTerminal(NonTerminal),
+    /// The production is the same as the production at the given index.
+    DuplicateProduction(usize),
+}
+
+impl DiagnosticKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            DiagnosticKind::UndeclaredTerminal(_)
+            | DiagnosticKind::UndefinedNonTerminal(_)
+            | DiagnosticKind::UndefinedStart(_) => Severity::Error,
+            DiagnosticKind::NoProductions(_)
+            | DiagnosticKind::UnreachableNonTerminal(_)
+            | DiagnosticKind::UnreachableTerminal(_)
+            | DiagnosticKind::UnproductiveNonTerminal(_)
+            | DiagnosticKind::DuplicateProduction(_) => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticKind::UndeclaredTerminal(t) => write!(f, "undeclared terminal `{}`", t),
+            DiagnosticKind::UndefinedNonTerminal(nt) => {
+                write!(f, "undefined nonterminal `{}`", nt)
+            }
+            DiagnosticKind::NoProductions(nt) => write!(f, "`{}` has no productions", nt),
+            DiagnosticKind::UndefinedStart(nt) => write!(f, "undefined start symbol `{}`", nt),
+            DiagnosticKind::UnreachableNonTerminal(nt) => {
+                write!(f, "`{}` is unreachable from the start symbol", nt)
+            }
+            DiagnosticKind::UnreachableTerminal(t) => {
+                write!(f, "`{}` is unreachable from the start symbol", t)
+            }
+            DiagnosticKind::UnproductiveNonTerminal(nt) => {
+                write!(f, "`{}` derives no terminal string", nt)
+            }
+            DiagnosticKind::DuplicateProduction(idx) => {
+                write!(f, "duplicate of production {}", idx)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    /// Index into [`Grammar::productions`] of the production involved, if any.
+    pub production: Option<usize>,
+}
+
+impl Diagnostic {
+    fn new(kind: DiagnosticKind, production: Option<usize>) -> Self {
+        Self {
+            severity: kind.severity(),
+            kind,
+            production,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.kind)?;
+        if let Some(idx) = self.production {
+            write!(f, " (in production {})", idx)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the symbols that appear in some sentential form derived from the
+/// start symbol.
+pub(crate) fn reachable_symbols(
+    grammar: &Grammar,
+) -> (HashSet<NonTerminalId>, HashSet<TerminalId>) {
+    let mut nonterminals = HashSet::from([grammar.start()]);
+    let mut terminals = HashSet::new();
+    let mut stack = vec![grammar.start()];
+    while let Some(nt) = stack.pop() {
+        for production in grammar.productions_from(nt) {
+            for &symbol in production.rhs() {
+                match symbol {
+                    Symbol::Terminal(t) => {
+                        terminals.insert(t);
+                    }
+                    Symbol::NonTerminal(nt) => {
+                        if nonterminals.insert(nt) {
+                            stack.push(nt);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    (nonterminals, terminals)
+}
+
+impl Grammar {
+    /// Checks the grammar for common mistakes.
+    ///
+    /// Diagnostics about a particular production come first, in production
+    /// order, followed by diagnostics about symbols sorted by name.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+
+        let mut seen = HashMap::new();
+        for (idx, production) in self.productions().iter().enumerate() {
+            if !self.is_nonterminal(production.lhs()) {
+                diagnostics.push(Diagnostic::new(
+                    DiagnosticKind::UndefinedNonTerminal(
+                        self.nonterminal(production.lhs()).clone(),
+                    ),
+                    Some(idx),
+                ));
+            }
+            for &symbol in production.rhs() {
+                let kind = match symbol {
+                    Symbol::Terminal(t) if !self.is_terminal(t) => {
+                        DiagnosticKind::UndeclaredTerminal(self.terminal(t).clone())
+                    }
+                    Symbol::NonTerminal(nt) if !self.is_nonterminal(nt) => {
+                        DiagnosticKind::UndefinedNonTerminal(self.nonterminal(nt).clone())
+                    }
+                    _ => continue,
+                };
+                diagnostics.push(Diagnostic::new(kind, Some(idx)));
+            }
+            if let Some(&first) = seen.get(production) {
+                diagnostics.push(Diagnostic::new(
+                    DiagnosticKind::DuplicateProduction(first),
+                    Some(idx),
+                ));
+            } else {
+                seen.insert(production, idx);
+            }
+        }
+
+        if !self.is_nonterminal(self.start()) {
+            diagnostics.push(Diagnostic::new(
+                DiagnosticKind::UndefinedStart(self.nonterminal(self.start()).clone()),
+                None,
+            ));
+        }
+
+        // Sorts by name so that diagnostics come out in a stable order.
+        let sorted_nonterminals = |filter: &dyn Fn(NonTerminalId) -> bool| {
+            let mut v = self
+                .nonterminals()
+                .iter()
+                .copied()
+                .filter(|&nt| filter(nt))
+                .map(|nt| (self.nonterminal(nt), nt))
+                .collect::<Vec<_>>();
+            v.sort();
+            v
+        };
+
+        let with_productions = self
+            .productions()
+            .iter()
+            .map(|p| p.lhs())
+            .collect::<HashSet<_>>();
+        for (nt, _) in sorted_nonterminals(&|nt| !with_productions.contains(&nt)) {
+            diagnostics.push(Diagnostic::new(
+                DiagnosticKind::NoProductions(nt.clone()),
+                None,
+            ));
+        }
+
+        let (reachable_nonterminals, reachable_terminals) = reachable_symbols(self);
+        for (nt, _) in sorted_nonterminals(&|nt| !reachable_nonterminals.contains(&nt)) {
+            diagnostics.push(Diagnostic::new(
+                DiagnosticKind::UnreachableNonTerminal(nt.clone()),
+                None,
+            ));
+        }
+        let mut unreachable_terminals = self
+            .terminals()
+            .iter()
+            .filter(|t| !reachable_terminals.contains(t))
+            .map(|&t| self.terminal(t))
+            .collect::<Vec<_>>();
+        unreachable_terminals.sort();
+        for t in unreachable_terminals {
+            diagnostics.push(Diagnostic::new(
+                DiagnosticKind::UnreachableTerminal(t.clone()),
+                None,
+            ));
+        }
+
+        // Nonterminals without productions are already reported.
+        let mut unproductive = with_productions
+            .iter()
+            .filter(|&&nt| !self.is_productive(nt))
+            .map(|&nt| (self.nonterminal(nt), nt))
+            .collect::<Vec<_>>();
+        unproductive.sort();
+        for (name, nt) in unproductive {
+            let production = self.production_indices(nt).first().copied();
+            diagnostics.push(Diagnostic::new(
+                DiagnosticKind::UnproductiveNonTerminal(name.clone()),
+                production,
+            ));
+        }
+
+        diagnostics
+    }
+}
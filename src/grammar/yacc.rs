@@ -0,0 +1,751 @@
+//! Reader for yacc and bison grammar files.
+//!
+//! Only the parts of a `.y` file that describe the language are translated:
+//!
+//! - `%token` declares terminals, and a string alias such as
+//!   `%token EQ "=="` lets rules write the terminal as `"=="`.
+//! - `%start` sets the start symbol, which must have rules, and defaults to the
+//!   lhs of the first rule.
+//! - Every rule of the rules section becomes productions. A character literal
+//!   `'+'` is the terminal `+`, and `%empty` or an empty alternative is ε.
+//! - `%left`, `%right`, `%nonassoc` and `%prec` become the precedence
+//!   declarations of the grammar (see [`Precedence`](super::Precedence)).
+//!
+//! Code blocks (`%{ ... %}`), actions, `<type>` tags, named references such as
+//! `exp[lhs]` and the epilogue after the second `%%` are skipped. Constructs
+//! that change what the generated parser accepts but have no equivalent in a
+//! [`Grammar`], such as `%precedence` and mid-rule actions, are dropped with a
+//! [`Warning`].
+//!
+//! ```text
+//! %token NUM
+//! %left '+'
+//! %%
+//! exp : exp '+' exp   { $$ = $1 + $3; }
+//!     | NUM
+//!     ;
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt, fs,
+    iter::Peekable,
+    path::Path,
+    str::Chars,
+};
+
+use super::{
+    text::{LoadError, ParseError, ParseErrorKind, Position},
+    Associativity, Grammar, NonTerminal, Symbol, Terminal,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarningKind {
+    /// A declaration such as `%left` or `%glr-parser` was skipped.
+    IgnoredDeclaration(String),
+    /// A `%prec` annotation was dropped because the given symbol has no
+    /// precedence.
+    Prec(String),
+    /// An action in the middle of a rule was dropped.
+    MidRuleAction,
+    /// The predefined `error` token was kept as an ordinary terminal.
+    ErrorToken,
+    /// A symbol with no rules was not declared with `%token`. It is treated as
+    /// a terminal.
+    UndeclaredToken(String),
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarningKind::IgnoredDeclaration(d) => write!(f, "ignored declaration `%{}`", d),
+            WarningKind::Prec(s) => {
+                write!(f, "ignored `%prec {}`, which has no precedence", s)
+            }
+            WarningKind::MidRuleAction => write!(f, "ignored mid-rule action"),
+            WarningKind::ErrorToken => {
+                write!(f, "`error` is treated as an ordinary terminal")
+            }
+            WarningKind::UndeclaredToken(s) => {
+                write!(f, "`{}` has no rules and is not a declared token", s)
+            }
+        }
+    }
+}
+
+/// A construct of the yacc file that could not be translated.
+///
+/// `line` and `column` are 1-based and count characters, not bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub line: usize,
+    pub column: usize,
+    pub kind: WarningKind,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: warning: {}", self.line, self.column, self.kind)
+    }
+}
+
+/// Declarations that only affect the generated code, and are skipped without a
+/// warning.
+const CODE_DECLARATIONS: &[&str] = &[
+    "code",
+    "debug",
+    "define",
+    "defines",
+    "destructor",
+    "error-verbose",
+    "expect",
+    "expect-rr",
+    "file-prefix",
+    "header",
+    "initial-action",
+    "language",
+    "lex-param",
+    "locations",
+    "name-prefix",
+    "no-lines",
+    "nterm",
+    "output",
+    "param",
+    "parse-param",
+    "printer",
+    "pure-parser",
+    "require",
+    "skeleton",
+    "token-table",
+    "type",
+    "union",
+    "verbose",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    /// A character literal such as `'+'`.
+    Char(String),
+    /// A string literal such as `"=="`.
+    Str(String),
+    Number,
+    /// A `<type>` tag.
+    Tag,
+    /// A named reference such as `[lhs]`.
+    NamedRef,
+    Directive(String),
+    /// A `{ ... }` action or braced code.
+    Action,
+    /// A `%{ ... %}` code block.
+    Code,
+    Separator,
+    Colon,
+    Pipe,
+    Semi,
+    Eof,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(s) => write!(f, "`{}`", s),
+            Token::Char(s) => write!(f, "'{}'", s),
+            Token::Str(s) => write!(f, "{:?}", s),
+            Token::Number => write!(f, "number"),
+            Token::Tag => write!(f, "type tag"),
+            Token::NamedRef => write!(f, "named reference"),
+            Token::Directive(s) => write!(f, "`%{}`", s),
+            Token::Action => write!(f, "action"),
+            Token::Code => write!(f, "code block"),
+            Token::Separator => write!(f, "`%%`"),
+            Token::Colon => write!(f, "`:`"),
+            Token::Pipe => write!(f, "`|`"),
+            Token::Semi => write!(f, "`;`"),
+            Token::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '.'
+}
+
+fn is_ident_continue(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-')
+}
+
+struct Lexer<'s> {
+    chars: Peekable<Chars<'s>>,
+    position: Position,
+}
+
+impl<'s> Lexer<'s> {
+    fn new(source: &'s str) -> Self {
+        Self {
+            chars: source.chars().peekable(),
+            position: Position { line: 1, column: 1 },
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Skips the rest of a comment whose leading `/` has been consumed.
+    /// Returns `false` if the `/` does not start a comment.
+    fn comment(&mut self, start: Position) -> Result<bool, ParseError> {
+        match self.peek() {
+            Some('/') => {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.bump();
+                }
+                Ok(true)
+            }
+            Some('*') => {
+                self.bump();
+                let mut star = false;
+                loop {
+                    match self.bump() {
+                        None => return Err(start.error(ParseErrorKind::UnterminatedBlock)),
+                        Some('/') if star => return Ok(true),
+                        Some(c) => star = c == '*',
+                    }
+                }
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('/') => {
+                    let position = self.position;
+                    self.bump();
+                    if !self.comment(position)? {
+                        return Err(position.error(ParseErrorKind::UnexpectedChar('/')));
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn ident(&mut self) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.peek().filter(|&c| is_ident_continue(c)) {
+            s.push(c);
+            self.bump();
+        }
+        s
+    }
+
+    /// Reads a literal whose opening `quote` has been consumed.
+    fn literal(&mut self, start: Position, quote: char) -> Result<String, ParseError> {
+        let mut s = String::new();
+        loop {
+            let position = self.position;
+            match self.bump() {
+                None | Some('\n') => return Err(start.error(ParseErrorKind::UnterminatedLiteral)),
+                Some(c) if c == quote => return Ok(s),
+                Some('\\') => match self.bump() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some('r') => s.push('\r'),
+                    Some('0') => s.push('\0'),
+                    Some(c @ ('\'' | '"' | '\\')) => s.push(c),
+                    Some(c) => return Err(position.error(ParseErrorKind::InvalidEscape(c))),
+                    None => return Err(start.error(ParseErrorKind::UnterminatedLiteral)),
+                },
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    /// Skips C code up to the `close` that balances an already consumed
+    /// opening bracket, stepping over literals and comments.
+    fn balanced(&mut self, start: Position, open: char, close: char) -> Result<(), ParseError> {
+        let mut depth = 1;
+        loop {
+            let position = self.position;
+            match self.bump() {
+                None => return Err(start.error(ParseErrorKind::UnterminatedBlock)),
+                Some(c) if c == open => depth += 1,
+                Some(c) if c == close => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                Some(quote @ ('\'' | '"')) if open == '{' => {
+                    while let Some(c) = self.bump() {
+                        match c {
+                            '\\' => {
+                                self.bump();
+                            }
+                            c if c == quote => break,
+                            '\n' => return Err(position.error(ParseErrorKind::UnterminatedLiteral)),
+                            _ => {}
+                        }
+                    }
+                }
+                Some('/') if open == '{' => {
+                    self.comment(position)?;
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// Skips a `%{ ... %}` block whose `%{` has been consumed.
+    fn code(&mut self, start: Position) -> Result<(), ParseError> {
+        let mut percent = false;
+        loop {
+            match self.bump() {
+                None => return Err(start.error(ParseErrorKind::UnterminatedBlock)),
+                Some('}') if percent => return Ok(()),
+                Some(c) => percent = c == '%',
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<(Token, Position), ParseError> {
+        self.skip_trivia()?;
+        let position = self.position;
+        let Some(c) = self.peek() else {
+            return Ok((Token::Eof, position));
+        };
+        self.bump();
+        let token = match c {
+            c if is_ident_start(c) => {
+                let mut s = c.to_string();
+                s.push_str(&self.ident());
+                Token::Ident(s)
+            }
+            c if c.is_ascii_digit() => {
+                while self.peek().is_some_and(|c| c.is_ascii_alphanumeric()) {
+                    self.bump();
+                }
+                Token::Number
+            }
+            '\'' => Token::Char(self.literal(position, '\'')?),
+            '"' => Token::Str(self.literal(position, '"')?),
+            '%' => match self.peek() {
+                Some('%') => {
+                    self.bump();
+                    Token::Separator
+                }
+                Some('{') => {
+                    self.bump();
+                    self.code(position)?;
+                    Token::Code
+                }
+                _ => Token::Directive(self.ident()),
+            },
+            '{' => {
+                self.balanced(position, '{', '}')?;
+                Token::Action
+            }
+            '<' => {
+                self.balanced(position, '<', '>')?;
+                Token::Tag
+            }
+            '[' => {
+                self.balanced(position, '[', ']')?;
+                Token::NamedRef
+            }
+            ':' => Token::Colon,
+            '|' => Token::Pipe,
+            ';' => Token::Semi,
+            c => return Err(position.error(ParseErrorKind::UnexpectedChar(c))),
+        };
+        Ok((token, position))
+    }
+}
+
+/// A symbol on a rhs, before it is known whether a name is a terminal.
+enum Item {
+    Name(String, Position),
+    Literal(String),
+}
+
+struct Rule {
+    lhs: String,
+    position: Position,
+    items: Vec<Item>,
+    /// The `%prec` symbol of the rule and where it was given.
+    prec: Option<(String, Position)>,
+}
+
+struct Parser<'s> {
+    lexer: Lexer<'s>,
+    lookahead: VecDeque<(Token, Position)>,
+    warnings: Vec<Warning>,
+    /// Terminals in declaration order.
+    terminals: Vec<String>,
+    declared: HashSet<String>,
+    /// Maps string aliases to the token names they stand for.
+    aliases: HashMap<String, String>,
+    levels: Vec<(Associativity, Vec<String>)>,
+    /// The `%start` symbol and where it was given.
+    start: Option<(String, Position)>,
+    rules: Vec<Rule>,
+}
+
+impl<'s> Parser<'s> {
+    fn new(source: &'s str) -> Self {
+        Self {
+            lexer: Lexer::new(source),
+            lookahead: VecDeque::new(),
+            warnings: vec![],
+            terminals: vec![],
+            declared: HashSet::new(),
+            aliases: HashMap::new(),
+            levels: vec![],
+            start: None,
+            rules: vec![],
+        }
+    }
+
+    /// Returns the token `n` tokens ahead.
+    fn peek_nth(&mut self, n: usize) -> Result<&Token, ParseError> {
+        while self.lookahead.len() <= n {
+            let token = self.lexer.next_token()?;
+            self.lookahead.push_back(token);
+        }
+        Ok(&self.lookahead[n].0)
+    }
+
+    fn peek(&mut self) -> Result<&Token, ParseError> {
+        self.peek_nth(0)
+    }
+
+    fn next(&mut self) -> Result<(Token, Position), ParseError> {
+        self.peek()?;
+        Ok(self.lookahead.pop_front().unwrap())
+    }
+
+    fn warn(&mut self, position: Position, kind: WarningKind) {
+        self.warnings.push(Warning {
+            line: position.line,
+            column: position.column,
+            kind,
+        });
+    }
+
+    fn expected(expected: &'static str, (token, position): (Token, Position)) -> ParseError {
+        position.error(ParseErrorKind::Expected {
+            expected,
+            found: token.to_string(),
+        })
+    }
+
+    fn declare_terminal(&mut self, name: String) {
+        if self.declared.insert(name.clone()) {
+            self.terminals.push(name);
+        }
+    }
+
+    /// The terminal written as the string literal `s`.
+    fn string_terminal(&self, s: String) -> String {
+        self.aliases.get(&s).cloned().unwrap_or(s)
+    }
+
+    fn at_declaration_end(&mut self) -> Result<bool, ParseError> {
+        Ok(matches!(
+            self.peek()?,
+            Token::Directive(_) | Token::Code | Token::Separator | Token::Eof
+        ))
+    }
+
+    /// Parses the symbols of `%token` and the precedence declarations, and
+    /// returns the terminals they name.
+    fn token_list(&mut self) -> Result<Vec<String>, ParseError> {
+        let mut names = vec![];
+        // The name an alias that follows would stand for, as in `NUM 258 "number"`.
+        let mut previous = None;
+        while !self.at_declaration_end()? {
+            match self.next()? {
+                (Token::Number, _) => {}
+                (Token::Tag, _) => previous = None,
+                (Token::Ident(name), _) => {
+                    self.declare_terminal(name.clone());
+                    names.push(name.clone());
+                    previous = Some(name);
+                }
+                (Token::Str(s), _) => match previous.take() {
+                    Some(name) => {
+                        self.aliases.insert(s, name);
+                    }
+                    None => {
+                        let name = self.string_terminal(s);
+                        self.declare_terminal(name.clone());
+                        names.push(name);
+                    }
+                },
+                (Token::Char(c), _) => {
+                    self.declare_terminal(c.clone());
+                    names.push(c);
+                    previous = None;
+                }
+                other => return Err(Self::expected("a token", other)),
+            }
+        }
+        Ok(names)
+    }
+
+    fn skip_declaration(&mut self) -> Result<(), ParseError> {
+        while !self.at_declaration_end()? {
+            self.next()?;
+        }
+        Ok(())
+    }
+
+    fn declarations(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.next()? {
+                (Token::Separator, _) => return Ok(()),
+                (Token::Code, _) => {}
+                (Token::Directive(d), position) => match d.as_str() {
+                    "token" => {
+                        self.token_list()?;
+                    }
+                    "left" | "right" | "nonassoc" => {
+                        let associativity = match d.as_str() {
+                            "left" => Associativity::Left,
+                            "right" => Associativity::Right,
+                            _ => Associativity::NonAssoc,
+                        };
+                        let names = self.token_list()?;
+                        self.levels.push((associativity, names));
+                    }
+                    // A level without associativity has no equivalent.
+                    "precedence" => {
+                        self.warn(position, WarningKind::IgnoredDeclaration(d));
+                        self.token_list()?;
+                    }
+                    "start" => {
+                        let (token, position) = self.next()?;
+                        let Token::Ident(name) = token else {
+                            return Err(Self::expected("a nonterminal", (token, position)));
+                        };
+                        if self.start.replace((name, position)).is_some() {
+                            return Err(position.error(ParseErrorKind::DuplicateStart));
+                        }
+                    }
+                    d if CODE_DECLARATIONS.contains(&d) => self.skip_declaration()?,
+                    _ => {
+                        self.warn(position, WarningKind::IgnoredDeclaration(d));
+                        self.skip_declaration()?;
+                    }
+                },
+                other => return Err(Self::expected("a declaration or `%%`", other)),
+            }
+        }
+    }
+
+    /// Returns if the next tokens start a new rule, `name :` or
+    /// `name [ref] :`.
+    fn at_rule_start(&mut self) -> Result<bool, ParseError> {
+        if !matches!(self.peek()?, Token::Ident(_)) {
+            return Ok(false);
+        }
+        let colon = if self.peek_nth(1)? == &Token::NamedRef {
+            2
+        } else {
+            1
+        };
+        Ok(self.peek_nth(colon)? == &Token::Colon)
+    }
+
+    fn alternative(&mut self, lhs: &str, lhs_position: Position) -> Result<(), ParseError> {
+        let mut items = vec![];
+        let mut action = None;
+        let mut prec = None;
+        loop {
+            if self.at_rule_start()? {
+                break;
+            }
+            match self.peek()? {
+                Token::Pipe | Token::Semi | Token::Separator | Token::Eof => break,
+                _ => {}
+            }
+            let (token, position) = self.next()?;
+            let item = match token {
+                Token::Ident(name) => Item::Name(name, position),
+                Token::Char(c) => Item::Literal(c),
+                Token::Str(s) => Item::Literal(self.string_terminal(s)),
+                Token::NamedRef => continue,
+                Token::Action => {
+                    action = Some(position);
+                    continue;
+                }
+                Token::Directive(d) if d == "empty" => continue,
+                Token::Directive(d) if d == "prec" => {
+                    let symbol = match self.next()? {
+                        (Token::Ident(s) | Token::Char(s), _) => s,
+                        (Token::Str(s), _) => self.string_terminal(s),
+                        other => return Err(Self::expected("a token", other)),
+                    };
+                    prec = Some((symbol, position));
+                    continue;
+                }
+                Token::Directive(d) if d == "dprec" || d == "merge" => {
+                    self.next()?;
+                    self.warn(position, WarningKind::IgnoredDeclaration(d));
+                    continue;
+                }
+                token => return Err(Self::expected("a symbol", (token, position))),
+            };
+            if let Some(position) = action.take() {
+                self.warn(position, WarningKind::MidRuleAction);
+            }
+            items.push(item);
+        }
+        self.rules.push(Rule {
+            lhs: lhs.to_string(),
+            position: lhs_position,
+            items,
+            prec,
+        });
+        Ok(())
+    }
+
+    fn rules(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.next()? {
+                (Token::Separator | Token::Eof, _) => return Ok(()),
+                (Token::Semi, _) => {}
+                (Token::Ident(lhs), position) => {
+                    if self.peek()? == &Token::NamedRef {
+                        self.next()?;
+                    }
+                    match self.next()? {
+                        (Token::Colon, _) => {}
+                        other => return Err(Self::expected("`:`", other)),
+                    }
+                    self.alternative(&lhs, position)?;
+                    while self.peek()? == &Token::Pipe {
+                        self.next()?;
+                        self.alternative(&lhs, position)?;
+                    }
+                }
+                other => return Err(Self::expected("a rule", other)),
+            }
+        }
+    }
+
+    fn parse(mut self) -> Result<(Grammar, Vec<Warning>), ParseError> {
+        self.declarations()?;
+        self.rules()?;
+
+        let mut nonterminals = vec![];
+        let mut seen = HashSet::new();
+        for rule in &self.rules {
+            if self.declared.contains(&rule.lhs) {
+                let kind = ParseErrorKind::TerminalAndNonTerminal(rule.lhs.clone());
+                return Err(rule.position.error(kind));
+            }
+            if seen.insert(rule.lhs.clone()) {
+                nonterminals.push(rule.lhs.clone());
+            }
+        }
+        let start = match (self.start.take(), self.rules.first()) {
+            (Some((start, position)), _) if self.declared.contains(&start) => {
+                return Err(position.error(ParseErrorKind::TerminalAndNonTerminal(start)));
+            }
+            (Some((start, position)), _) if !seen.contains(&start) => {
+                return Err(position.error(ParseErrorKind::StartWithoutRules(start)));
+            }
+            (Some((start, _)), _) => start,
+            (None, Some(rule)) => rule.lhs.clone(),
+            (None, None) => return Err(self.lexer.position.error(ParseErrorKind::NoStart)),
+        };
+
+        let with_precedence: HashSet<_> = self
+            .levels
+            .iter()
+            .flat_map(|(_, names)| names.iter().cloned())
+            .collect();
+        let mut overrides = vec![];
+        let mut productions = vec![];
+        for (idx, rule) in std::mem::take(&mut self.rules).into_iter().enumerate() {
+            if let Some((symbol, position)) = rule.prec {
+                if with_precedence.contains(&symbol) {
+                    overrides.push((idx, Terminal(symbol)));
+                } else {
+                    self.warn(position, WarningKind::Prec(symbol));
+                }
+            }
+            let mut rhs = vec![];
+            for item in rule.items {
+                rhs.push(match item {
+                    Item::Name(name, _) if seen.contains(&name) => {
+                        Symbol::NonTerminal(NonTerminal(name))
+                    }
+                    Item::Name(name, position) => {
+                        if !self.declared.contains(&name) {
+                            let kind = if name == "error" {
+                                WarningKind::ErrorToken
+                            } else {
+                                WarningKind::UndeclaredToken(name.clone())
+                            };
+                            self.warn(position, kind);
+                            self.declare_terminal(name.clone());
+                        }
+                        Symbol::Terminal(Terminal(name))
+                    }
+                    Item::Literal(s) => {
+                        self.declare_terminal(s.clone());
+                        Symbol::Terminal(Terminal(s))
+                    }
+                });
+            }
+            productions.push((NonTerminal(rule.lhs), rhs));
+        }
+
+        let mut grammar = Grammar::from_named(
+            nonterminals.into_iter().map(NonTerminal),
+            self.terminals.into_iter().map(Terminal),
+            productions,
+            NonTerminal(start),
+            [],
+        );
+        let levels = std::mem::take(&mut self.levels)
+            .into_iter()
+            .map(|(associativity, names)| {
+                (associativity, names.into_iter().map(Terminal).collect())
+            })
+            .collect();
+        grammar.set_precedence(levels, overrides);
+        self.warnings
+            .sort_by_key(|warning| (warning.line, warning.column));
+        Ok((grammar, self.warnings))
+    }
+}
+
+/// Translates the yacc or bison grammar in `source`, returning the grammar and
+/// the constructs that could not be translated.
+pub fn parse(source: &str) -> Result<(Grammar, Vec<Warning>), ParseError> {
+    Parser::new(source).parse()
+}
+
+/// Reads and translates the yacc or bison grammar file at `path`.
+pub fn load(path: impl AsRef<Path>) -> Result<(Grammar, Vec<Warning>), LoadError> {
+    Ok(parse(&fs::read_to_string(path)?)?)
+}
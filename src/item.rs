@@ -1,19 +1,27 @@
 use std::{fmt, marker::PhantomData};
 
-use crate::grammar::{NonTerminal, Production, Symbol, Terminal};
+use crate::grammar::{Grammar, Production, SymbolId};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Complete;
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Incomplete;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy)]
 pub struct ItemBase<'a, C> {
     production: &'a Production,
     dot_idx: usize,
     _complete: PhantomData<C>,
 }
 
+/// Items of the same grammar are equal if they point at the same production, so
+/// comparing them never compares symbols.
+impl<'a, C> PartialEq for ItemBase<'a, C> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.production, other.production) && self.dot_idx == other.dot_idx
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Item<'a> {
     Incomplete(ItemBase<'a, Incomplete>),
@@ -52,8 +60,8 @@ impl<'a> Item<'a> {
 }
 
 impl<'a> ItemBase<'a, Incomplete> {
-    pub fn next_symbol(&self) -> &'a Symbol<Terminal, NonTerminal> {
-        &self.production.rhs()[self.dot_idx]
+    pub fn next_symbol(&self) -> SymbolId {
+        self.production.rhs()[self.dot_idx]
     }
 
     pub fn to_next(mut self) -> Item<'a> {
@@ -76,23 +84,49 @@ impl<'a> From<ItemBase<'a, Incomplete>> for ItemBase<'a, Complete> {
     }
 }
 
-impl<'a, C> fmt::Display for ItemBase<'a, C> {
+impl<'a, C> ItemBase<'a, C> {
+    /// Returns something that displays the item with the symbol names of
+    /// `grammar`.
+    pub fn display(&self, grammar: &'a Grammar) -> DisplayItem<'a> {
+        DisplayItem {
+            production: self.production,
+            dot_idx: self.dot_idx,
+            grammar,
+        }
+    }
+}
+
+impl<'a> Item<'a> {
+    pub fn display(&self, grammar: &'a Grammar) -> DisplayItem<'a> {
+        match self {
+            Item::Incomplete(item) => item.display(grammar),
+            Item::Complete(item) => item.display(grammar),
+        }
+    }
+}
+
+pub struct DisplayItem<'a> {
+    production: &'a Production,
+    dot_idx: usize,
+    grammar: &'a Grammar,
+}
+
+impl<'a> fmt::Display for DisplayItem<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let before = self.production.rhs()[..self.dot_idx]
-            .iter()
-            .map(ToString::to_string)
-            .collect::<Vec<_>>()
-            .join(" ");
-        let after = self.production.rhs()[self.dot_idx..]
-            .iter()
-            .map(ToString::to_string)
-            .collect::<Vec<_>>()
-            .join(" ");
+        let names = |symbols: &[SymbolId]| {
+            symbols
+                .iter()
+                .map(|&symbol| self.grammar.symbol(symbol).to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let before = names(&self.production.rhs()[..self.dot_idx]);
+        let after = names(&self.production.rhs()[self.dot_idx..]);
 
         write!(
             f,
             "{} -> {}{}.{}{}",
-            self.production.lhs().0,
+            self.grammar.nonterminal(self.production.lhs()).0,
             before,
             if before.is_empty() { "" } else { " " },
             if after.is_empty() { "" } else { " " },
@@ -100,12 +134,3 @@ impl<'a, C> fmt::Display for ItemBase<'a, C> {
         )
     }
 }
-
-impl<'a> fmt::Display for Item<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Item::Incomplete(item) => item.fmt(f),
-            Item::Complete(item) => item.fmt(f),
-        }
-    }
-}
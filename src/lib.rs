@@ -1,7 +1,13 @@
+pub mod actions;
+pub mod analysis;
+pub mod attributes;
+pub mod cyk;
 pub mod earley;
 pub mod first_follow;
+pub mod generate;
 pub mod grammar;
 pub mod item;
 pub mod ll1;
+pub mod llk;
 pub mod parse_tree;
 pub mod recursive_descent;
@@ -1,165 +1,517 @@
-use std::collections::HashMap;
+use std::{
+    cell::Cell,
+    collections::{HashSet, VecDeque},
+    fmt,
+};
 
 use crate::{
+    actions::{Actions, Reduce, Trees},
     first_follow::{create_first, create_follow, first_rhs, FirstSet, FollowSet},
-    grammar::{Grammar, NonTerminal, Production, Symbol, Terminal},
+    grammar::{Grammar, NonTerminalId, Symbol, SymbolId, TerminalId},
     parse_tree::ParseTree,
 };
 
-pub type LL1Table<'a> = HashMap<(&'a NonTerminal, &'a Terminal), Vec<&'a Production>>;
+/// Parse table with a cell for every pair of nonterminal and terminal of a grammar,
+/// holding the indices of the productions to expand by.
+///
+/// With the `serde` feature, this is serialized as
+/// `{"terminal_count": 9, "cells": [[], [0], ...]}`, where the cell for `nt` and
+/// `t` is at `nt * terminal_count + t`. The ids and production indices refer to
+/// the grammar the table was built for, so a stored table should be used with
+/// that grammar as deserialized from its own serialized form; see
+/// [`LL1Table::fits`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "LL1TableData")
+)]
+pub struct LL1Table {
+    terminal_count: usize,
+    cells: Vec<Vec<usize>>,
+}
 
-#[derive(Debug, Clone)]
-enum Tree<'a> {
-    Terminal(&'a Terminal),
-    Nonterminal(&'a NonTerminal, usize),
+impl LL1Table {
+    /// Indices into [`Grammar::productions`] of the productions for `nt` on
+    /// lookahead `t`.
+    pub fn get(&self, nt: NonTerminalId, t: TerminalId) -> &[usize] {
+        &self.cells[nt.index() * self.terminal_count + t.index()]
+    }
+
+    fn get_mut(&mut self, nt: NonTerminalId, t: TerminalId) -> &mut Vec<usize> {
+        &mut self.cells[nt.index() * self.terminal_count + t.index()]
+    }
+
+    /// Iterates over the nonempty cells in id order.
+    pub fn iter(&self) -> impl Iterator<Item = ((NonTerminalId, TerminalId), &[usize])> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| !cell.is_empty())
+            .map(|(idx, cell)| {
+                let nt = NonTerminalId::new(idx / self.terminal_count);
+                let t = TerminalId::new(idx % self.terminal_count);
+                ((nt, t), cell.as_slice())
+            })
+    }
+
+    pub fn has_conflict(&self) -> bool {
+        self.cells.iter().any(|cell| cell.len() > 1)
+    }
+
+    /// Whether the table can be used with `grammar`: it has a cell for every
+    /// pair of nonterminal and terminal of `grammar`, and each cell holds only
+    /// productions of its nonterminal. This only checks the shape of the table,
+    /// so a table built for a different grammar with as many symbols and
+    /// matching lhs still fits.
+    pub fn fits(&self, grammar: &Grammar) -> bool {
+        self.has_shape_of(grammar)
+            && self
+                .iter()
+                .all(|((nt, _), cell)| cell.iter().all(|&idx| expands(grammar, nt, idx)))
+    }
+
+    fn has_shape_of(&self, grammar: &Grammar) -> bool {
+        self.terminal_count == grammar.terminal_count()
+            && self.cells.len() == grammar.nonterminal_count() * grammar.terminal_count()
+    }
+}
+
+/// Whether `idx` is the index of a production of `nt` in `grammar`.
+fn expands(grammar: &Grammar, nt: NonTerminalId, idx: usize) -> bool {
+    grammar
+        .productions()
+        .get(idx)
+        .is_some_and(|production| production.lhs() == nt)
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct LL1TableData {
+    terminal_count: usize,
+    cells: Vec<Vec<usize>>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<LL1TableData> for LL1Table {
+    type Error = &'static str;
+
+    fn try_from(data: LL1TableData) -> Result<Self, Self::Error> {
+        if data.terminal_count == 0 || !data.cells.len().is_multiple_of(data.terminal_count) {
+            return Err("number of cells is not a multiple of terminal_count");
+        }
+        Ok(Self {
+            terminal_count: data.terminal_count,
+            cells: data.cells,
+        })
+    }
 }
 
+/// The result of [`parse`] and [`parse_with_table`], and, with the conflicts
+/// of its own tables, of [`llk::parse`](crate::llk::parse).
 #[derive(Debug, Clone)]
-pub enum ParseResult<'a> {
-    Conflict,
+pub enum ParseResult<'a, C = Conflict> {
+    /// The grammar's table has conflicts, so it cannot be parsed predictively.
+    Conflict(Vec<C>),
+    /// The table given to [`parse_with_table`] does not fit the grammar.
+    TableMismatch,
     NoParse,
     Parse(ParseTree<'a>),
 }
 
-pub fn create_table<'a>(
-    grammar: &'a Grammar,
-    first: &FirstSet<'a>,
-    follow: &FollowSet<'a>,
-) -> LL1Table<'a> {
-    let mut table = HashMap::new();
-    for nt in grammar.nonterminals() {
-        for t in grammar
-            .terminals()
-            .iter()
-            .chain(std::iter::once(Terminal::eoim()))
-        {
-            table.insert((nt, t), vec![]);
-        }
-    }
+/// The result of [`evaluate`] and [`evaluate_with_table`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalResult<T> {
+    Conflict(Vec<Conflict>),
+    /// The table given to [`evaluate_with_table`] does not fit the grammar.
+    TableMismatch,
+    NoParse,
+    Value(T),
+}
+
+pub fn create_table(grammar: &Grammar, first: &FirstSet, follow: &FollowSet) -> LL1Table {
+    let mut table = LL1Table {
+        terminal_count: grammar.terminal_count(),
+        cells: vec![vec![]; grammar.nonterminal_count() * grammar.terminal_count()],
+    };
 
-    for production in grammar.productions() {
+    for (idx, production) in grammar.productions().iter().enumerate() {
         let lhs = production.lhs();
         let (terminals, nullable) = first_rhs(production.rhs(), first);
 
-        for terminal in terminals.iter() {
-            table.get_mut(&(lhs, terminal)).unwrap().push(production);
+        for &terminal in terminals.iter() {
+            table.get_mut(lhs, terminal).push(idx);
         }
 
         if nullable {
-            for terminal in follow[lhs].iter() {
-                table.get_mut(&(lhs, terminal)).unwrap().push(production);
+            for &terminal in follow[lhs.index()].iter() {
+                table.get_mut(lhs, terminal).push(idx);
             }
         }
     }
+    for cell in table.cells.iter_mut() {
+        cell.sort();
+    }
+    table
+}
+
+pub fn table_to_string(grammar: &Grammar, table: &LL1Table) -> String {
     table
+        .iter()
+        .map(|((nt, t), v)| {
+            format!(
+                "{} {}\t{}",
+                grammar.nonterminal(nt),
+                grammar.terminal(t),
+                v.iter()
+                    .map(|&idx| grammar.productions()[idx].display(grammar).to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+        .chain(std::iter::once("".to_string()))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-pub fn table_to_string(table: &LL1Table) -> String {
+/// Why the productions of a cell of an [`LL1Table`] conflict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// At least two of the productions derive strings that start with the
+    /// terminal, or at least two are nullable.
+    FirstFirst,
+    /// One production derives strings that start with the terminal, and the
+    /// other is nullable and the terminal can follow the nonterminal.
+    FirstFollow,
+}
+
+impl fmt::Display for ConflictKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConflictKind::FirstFirst => write!(f, "FIRST/FIRST"),
+            ConflictKind::FirstFollow => write!(f, "FIRST/FOLLOW"),
+        }
+    }
+}
+
+/// A cell of an [`LL1Table`] with more than one production.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub nonterminal: NonTerminalId,
+    pub terminal: TerminalId,
+    /// Indices into [`Grammar::productions`], in order.
+    pub productions: Vec<usize>,
+    pub kind: ConflictKind,
+    /// A sentential form of a leftmost derivation that has only terminals
+    /// before `nonterminal`, and where `terminal` can come next, so that the
+    /// parser reaches the cell. `None` if none was found within the search
+    /// bounds.
+    pub example: Option<Vec<SymbolId>>,
+}
+
+/// The number of sentential forms [`conflicts`] searches for each example.
+const EXAMPLE_SEARCH_LIMIT: usize = 10_000;
+/// The number of symbols after the terminal prefix of the sentential forms
+/// [`conflicts`] searches.
+const EXAMPLE_MAX_LEN: usize = 8;
+
+/// Lists the cells of `table` with more than one production, in the order of
+/// [`LL1Table::iter`]. `first` and `follow` must be the sets `table` was built
+/// from.
+pub fn conflicts(
+    grammar: &Grammar,
+    first: &FirstSet,
+    follow: &FollowSet,
+    table: &LL1Table,
+) -> Vec<Conflict> {
     table
         .iter()
-        .filter_map(|(k, v)| {
-            if v.is_empty() {
-                None
+        .filter(|(_, cell)| cell.len() > 1)
+        .map(|((nt, t), cell)| {
+            let (mut starts, mut nullable) = (0, 0);
+            for &idx in cell {
+                let (terminals, is_nullable) = first_rhs(grammar.productions()[idx].rhs(), first);
+                if terminals.contains(&t) {
+                    starts += 1;
+                } else if is_nullable && follow[nt.index()].contains(&t) {
+                    nullable += 1;
+                }
+            }
+            let kind = if starts > 1 || nullable > 1 {
+                ConflictKind::FirstFirst
             } else {
-                Some(format!(
-                    "{} {}\t{}",
-                    k.0,
-                    k.1,
-                    v.iter()
-                        .map(ToString::to_string)
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                ))
+                ConflictKind::FirstFollow
+            };
+            Conflict {
+                nonterminal: nt,
+                terminal: t,
+                productions: cell.to_vec(),
+                kind,
+                example: example(
+                    grammar,
+                    first,
+                    nt,
+                    (kind == ConflictKind::FirstFollow).then_some(t),
+                ),
+            }
+        })
+        .collect()
+}
+
+/// Searches the leftmost derivations breadth first for a sentential form with
+/// only terminals before `nt`, and, if `next` is given, where that terminal can
+/// come after `nt`.
+fn example(
+    grammar: &Grammar,
+    first: &FirstSet,
+    nt: NonTerminalId,
+    next: Option<TerminalId>,
+) -> Option<Vec<SymbolId>> {
+    // Each form is split into its terminal prefix and the rest, which starts
+    // with a nonterminal.
+    let mut queue = VecDeque::from([(vec![], vec![Symbol::NonTerminal(grammar.start())])]);
+    let mut seen = HashSet::new();
+    let mut searched = 0;
+    while let Some((prefix, rest)) = queue.pop_front() {
+        searched += 1;
+        if searched > EXAMPLE_SEARCH_LIMIT {
+            return None;
+        }
+        let Some(&Symbol::NonTerminal(leftmost)) = rest.first() else {
+            continue;
+        };
+        if leftmost == nt {
+            let reached = match next {
+                None => true,
+                Some(t) => {
+                    let (terminals, nullable) = first_rhs(&rest[1..], first);
+                    terminals.contains(&t) || (nullable && t == TerminalId::eoim())
+                }
+            };
+            if reached {
+                return Some(prefix.into_iter().chain(rest).collect());
+            }
+        }
+        for production in grammar.productions_from(leftmost) {
+            let mut prefix = prefix.clone();
+            let mut rest = production
+                .rhs()
+                .iter()
+                .chain(&rest[1..])
+                .copied()
+                .collect::<VecDeque<_>>();
+            while let Some(&Symbol::Terminal(t)) = rest.front() {
+                prefix.push(Symbol::Terminal(t));
+                rest.pop_front();
+            }
+            let rest = Vec::from(rest);
+            if rest.len() <= EXAMPLE_MAX_LEN && seen.insert(rest.clone()) {
+                queue.push_back((prefix, rest));
+            }
+        }
+    }
+    None
+}
+
+/// Renders one line per conflict: the cell, the kind, the productions and
+/// the example, if any.
+pub fn conflicts_to_string(grammar: &Grammar, conflicts: &[Conflict]) -> String {
+    conflicts
+        .iter()
+        .map(|conflict| {
+            let productions = conflict
+                .productions
+                .iter()
+                .map(|&idx| grammar.productions()[idx].display(grammar).to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let mut line = format!(
+                "{} {}\t{}\t{}",
+                grammar.nonterminal(conflict.nonterminal),
+                grammar.terminal(conflict.terminal),
+                conflict.kind,
+                productions
+            );
+            if let Some(example) = &conflict.example {
+                let example = example
+                    .iter()
+                    .map(|&symbol| grammar.symbol(symbol).to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                line.push_str(&format!("\te.g. {}", example));
             }
+            line
         })
         .chain(std::iter::once("".to_string()))
         .collect::<Vec<_>>()
         .join("\n")
 }
 
-fn gen_parse_tree<'a>(trees: &[Vec<Tree<'a>>], current: &Tree<'a>) -> ParseTree<'a> {
-    match *current {
-        Tree::Terminal(t) => ParseTree::Terminal(t),
-        Tree::Nonterminal(nt, children_idx) => ParseTree::NonTerminal(
-            nt,
-            trees[children_idx]
-                .iter()
-                .map(|child| gen_parse_tree(trees, child))
-                .collect(),
-        ),
+/// A node whose children are still being parsed.
+struct Frame<V> {
+    production: usize,
+    len: usize,
+    children: Vec<V>,
+}
+
+/// Adds `value` to the children of the innermost open node, and reduces every
+/// node that this completes. Returns the value of the root once it is complete.
+fn add_child<'a, R: Reduce<'a>>(
+    frames: &mut Vec<Frame<R::Value>>,
+    reducer: &R,
+    mut value: R::Value,
+) -> Option<R::Value> {
+    loop {
+        let Some(frame) = frames.last_mut() else {
+            return Some(value);
+        };
+        frame.children.push(value);
+        if frame.children.len() < frame.len {
+            return None;
+        }
+        let frame = frames.pop().unwrap();
+        value = reducer.production(frame.production, frame.children);
     }
 }
 
-pub fn parse_with_table<'a>(
+/// Runs the predictive parser, reducing each node as soon as its last child is
+/// parsed. `predict` returns the index of the production to expand a
+/// nonterminal by when the next token is at the given index of `tokens`.
+/// Returns `None` if `tokens` is not a sentence of the grammar.
+pub(crate) fn run<'a, R: Reduce<'a>>(
     grammar: &'a Grammar,
     tokens: &[&str],
-    table: &LL1Table<'a>,
-) -> ParseResult<'a> {
+    predict: impl Fn(NonTerminalId, usize) -> Option<usize>,
+    reducer: &R,
+) -> Option<R::Value> {
     let start = Symbol::NonTerminal(grammar.start());
-    let eoim = Symbol::<&Terminal, &NonTerminal>::eoim();
-    let mut stack = vec![(eoim.clone(), 0), (start, 0)];
+    let eoim = Symbol::Terminal(TerminalId::eoim());
+    let mut stack = vec![eoim, start];
 
-    let mut trees = vec![vec![]];
+    let mut frames = vec![];
+    let mut root = None;
 
     let mut idx = 0;
 
-    while let Some((top, parent)) = stack.pop() {
+    while let Some(top) = stack.pop() {
         if top == eoim {
+            if idx < tokens.len() {
+                // input left over
+                return None;
+            }
             break;
         }
         match top {
             Symbol::Terminal(top) => {
-                if top.0 == tokens[idx] {
+                if idx < tokens.len() && grammar.terminal_id(tokens[idx]) == Some(top) {
                     idx += 1;
-                    // Add the terminal into the subtree of the parent.
-                    trees[parent].push(Tree::Terminal(top));
+                    let value = reducer.terminal(grammar.terminal(top));
+                    root = root.or(add_child(&mut frames, reducer, value));
                 } else {
                     // fail to parse
-                    return ParseResult::NoParse;
+                    return None;
                 }
             }
             Symbol::NonTerminal(top) => {
-                // HACK:
-                // Set the token to the end-of-input marker if we have reached the end of input.
                 assert!(idx <= tokens.len());
-                let token = Terminal(
-                    tokens
-                        .get(idx)
-                        .copied()
-                        .unwrap_or_else(|| Terminal::eoim().0.as_str())
-                        .to_string(),
-                );
-                let entry = &table[&(top, &token)];
-                assert!(entry.len() < 2);
-                if let Some(production) = entry.first() {
-                    assert_eq!(top, production.lhs());
-
-                    // Add the subtree for this production.
-                    trees.push(vec![]);
-
-                    let value = trees.len() - 1;
-                    // Add the subtree into the children of the parent.
-                    trees[parent].push(Tree::Nonterminal(top, value));
-
-                    // Push the rhs onto the stack in reverse.
-                    stack.extend(
-                        production
-                            .rhs()
-                            .iter()
-                            .map(Symbol::as_ref)
-                            .rev()
-                            .zip(std::iter::repeat(value)),
-                    );
+                // fail to parse if there is no production
+                let production_idx = predict(top, idx)?;
+                let production = &grammar.productions()[production_idx];
+                assert_eq!(top, production.lhs());
+
+                if production.rhs().is_empty() {
+                    let value = reducer.production(production_idx, vec![]);
+                    root = root.or(add_child(&mut frames, reducer, value));
                 } else {
-                    // fail to parse
-                    return ParseResult::NoParse;
+                    frames.push(Frame {
+                        production: production_idx,
+                        len: production.rhs().len(),
+                        children: vec![],
+                    });
                 }
+
+                // Push the rhs onto the stack in reverse.
+                stack.extend(production.rhs().iter().copied().rev());
             }
         }
     }
 
-    assert_eq!(trees[0].len(), 1);
-    ParseResult::Parse(gen_parse_tree(&trees, &trees[0][0]))
+    assert!(frames.is_empty());
+    root
+}
+
+/// Predicts with `table`, on the next token or the end-of-input marker once we
+/// have reached the end of input. Only the cells that are used are checked
+/// against the grammar, and `mismatch` is set when one of them does not fit.
+fn predict_with<'t>(
+    grammar: &'t Grammar,
+    tokens: &'t [&str],
+    table: &'t LL1Table,
+    mismatch: &'t Cell<bool>,
+) -> impl Fn(NonTerminalId, usize) -> Option<usize> + 't {
+    move |nt, idx| {
+        let token = match tokens.get(idx) {
+            Some(token) => grammar.terminal_id(token)?,
+            None => TerminalId::eoim(),
+        };
+        let entry = table.get(nt, token);
+        assert!(entry.len() < 2);
+        let production = entry.first().copied()?;
+        if !expands(grammar, nt, production) {
+            mismatch.set(true);
+            return None;
+        }
+        Some(production)
+    }
+}
+
+/// Parses `tokens` with a table built for `grammar`. Returns
+/// [`ParseResult::TableMismatch`] if the table does not have the shape of the
+/// grammar's table, or if a cell used for `tokens` holds a production that is
+/// not one of its nonterminal.
+pub fn parse_with_table<'a>(
+    grammar: &'a Grammar,
+    tokens: &[&str],
+    table: &LL1Table,
+) -> ParseResult<'a> {
+    if !table.has_shape_of(grammar) {
+        return ParseResult::TableMismatch;
+    }
+    let mismatch = Cell::new(false);
+    match run(
+        grammar,
+        tokens,
+        predict_with(grammar, tokens, table, &mismatch),
+        &Trees(grammar),
+    ) {
+        Some(tree) => ParseResult::Parse(tree),
+        None if mismatch.get() => ParseResult::TableMismatch,
+        None => ParseResult::NoParse,
+    }
+}
+
+/// Like [`parse_with_table`], but computes the value of the sentence with
+/// `actions` instead of its parse tree, calling the action of each production
+/// as soon as its children are parsed.
+pub fn evaluate_with_table<'a, T>(
+    actions: &Actions<'a, T>,
+    tokens: &[&str],
+    table: &LL1Table,
+) -> EvalResult<T> {
+    if !table.has_shape_of(actions.grammar()) {
+        return EvalResult::TableMismatch;
+    }
+    let mismatch = Cell::new(false);
+    match run(
+        actions.grammar(),
+        tokens,
+        predict_with(actions.grammar(), tokens, table, &mismatch),
+        actions,
+    ) {
+        Some(value) => EvalResult::Value(value.into_value()),
+        None if mismatch.get() => EvalResult::TableMismatch,
+        None => EvalResult::NoParse,
+    }
 }
 
 pub fn parse<'a>(grammar: &'a Grammar, tokens: &[&str]) -> ParseResult<'a> {
@@ -167,15 +519,26 @@ pub fn parse<'a>(grammar: &'a Grammar, tokens: &[&str]) -> ParseResult<'a> {
     let follow = create_follow(grammar, &first);
     let table = create_table(grammar, &first, &follow);
 
-    // println!("First:\n{}", first_to_string(&first));
-    // println!("Follow:\n{}", follow_to_string(&follow));
-    // println!("Table:\n{}", table_to_string(&table));
+    // println!("First:\n{}", first_to_string(grammar, &first));
+    // println!("Follow:\n{}", follow_to_string(grammar, &follow));
+    // println!("Table:\n{}", table_to_string(grammar, &table));
 
-    for v in table.values() {
-        if v.len() > 1 {
-            return ParseResult::Conflict;
-        }
+    if table.has_conflict() {
+        return ParseResult::Conflict(conflicts(grammar, &first, &follow, &table));
     }
 
     parse_with_table(grammar, tokens, &table)
 }
+
+/// Like [`parse`], but computes the value of the sentence with `actions`; see
+/// [`evaluate_with_table`].
+pub fn evaluate<T>(actions: &Actions<'_, T>, tokens: &[&str]) -> EvalResult<T> {
+    let grammar = actions.grammar();
+    let first = create_first(grammar);
+    let follow = create_follow(grammar, &first);
+    let table = create_table(grammar, &first, &follow);
+    if table.has_conflict() {
+        return EvalResult::Conflict(conflicts(grammar, &first, &follow, &table));
+    }
+    evaluate_with_table(actions, tokens, &table)
+}
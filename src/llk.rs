@@ -0,0 +1,189 @@
+//! Strong LL(k) parsing, for grammars that need more than one token of
+//! lookahead.
+//!
+//! The table has a cell for every nonterminal and lookahead: a sequence of `k`
+//! terminals, or fewer followed by the end-of-input marker. The production
+//! `A -> α` goes in the cells of `A` for first_k(`α`) k-concatenated with
+//! follow_k(`A`). For `k = 1` this is the table of [`ll1`](crate::ll1).
+
+use std::collections::HashMap;
+
+use crate::{
+    actions::Trees,
+    first_follow::{concat_k, create_first_k, create_follow_k, first_rhs_k, FirstKSet, FollowKSet},
+    grammar::{Grammar, NonTerminalId, TerminalId},
+    ll1::{self, ParseResult},
+};
+
+/// Parse table of a grammar for a fixed `k`, holding for each pair of
+/// nonterminal and lookahead the indices of the productions to expand by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LLkTable {
+    k: usize,
+    cells: HashMap<NonTerminalId, HashMap<Vec<TerminalId>, Vec<usize>>>,
+}
+
+impl LLkTable {
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Indices into [`Grammar::productions`] of the productions for `nt` on
+    /// `lookahead`.
+    pub fn get(&self, nt: NonTerminalId, lookahead: &[TerminalId]) -> &[usize] {
+        self.cells
+            .get(&nt)
+            .and_then(|row| row.get(lookahead))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Iterates over the nonempty cells in order of nonterminal id and
+    /// lookahead.
+    pub fn iter(&self) -> impl Iterator<Item = ((NonTerminalId, &[TerminalId]), &[usize])> {
+        let mut cells = self
+            .cells
+            .iter()
+            .flat_map(|(&nt, row)| {
+                row.iter()
+                    .map(move |(lookahead, cell)| ((nt, lookahead.as_slice()), cell.as_slice()))
+            })
+            .collect::<Vec<_>>();
+        cells.sort_by_key(|&(key, _)| key);
+        cells.into_iter()
+    }
+
+    pub fn has_conflict(&self) -> bool {
+        self.cells
+            .values()
+            .flat_map(HashMap::values)
+            .any(|cell| cell.len() > 1)
+    }
+}
+
+/// A cell of an [`LLkTable`] with more than one production.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub nonterminal: NonTerminalId,
+    pub lookahead: Vec<TerminalId>,
+    /// Indices into [`Grammar::productions`], in order.
+    pub productions: Vec<usize>,
+}
+
+/// Lists the cells of `table` with more than one production, in the order of
+/// [`LLkTable::iter`].
+pub fn conflicts(table: &LLkTable) -> Vec<Conflict> {
+    table
+        .iter()
+        .filter(|(_, cell)| cell.len() > 1)
+        .map(|((nt, lookahead), cell)| Conflict {
+            nonterminal: nt,
+            lookahead: lookahead.to_vec(),
+            productions: cell.to_vec(),
+        })
+        .collect()
+}
+
+pub fn create_table(
+    grammar: &Grammar,
+    first: &FirstKSet,
+    follow: &FollowKSet,
+    k: usize,
+) -> LLkTable {
+    let mut table = LLkTable {
+        k,
+        cells: HashMap::new(),
+    };
+
+    for (idx, production) in grammar.productions().iter().enumerate() {
+        let lhs = production.lhs();
+        let rhs = first_rhs_k(production.rhs(), first, k);
+        for lookahead in concat_k(&rhs, &follow[lhs.index()], k) {
+            table
+                .cells
+                .entry(lhs)
+                .or_default()
+                .entry(lookahead)
+                .or_default()
+                .push(idx);
+        }
+    }
+    for cell in table.cells.values_mut().flat_map(HashMap::values_mut) {
+        cell.sort();
+    }
+    table
+}
+
+pub fn table_to_string(grammar: &Grammar, table: &LLkTable) -> String {
+    table
+        .iter()
+        .map(|((nt, lookahead), v)| {
+            format!(
+                "{} {}\t{}",
+                grammar.nonterminal(nt),
+                lookahead
+                    .iter()
+                    .map(|&t| grammar.terminal(t).to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                v.iter()
+                    .map(|&idx| grammar.productions()[idx].display(grammar).to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+        .chain(std::iter::once("".to_string()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Like [`ll1::parse_with_table`], with the next `k` tokens as lookahead.
+pub fn parse_with_table<'a>(
+    grammar: &'a Grammar,
+    tokens: &[&str],
+    table: &LLkTable,
+) -> ParseResult<'a, Conflict> {
+    // The tokens followed by the end-of-input marker, so the lookahead at each
+    // position is the next k of them, or all that are left.
+    let Some(mut ids) = grammar
+        .token_ids(tokens)
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+    else {
+        return ParseResult::NoParse;
+    };
+    ids.push(TerminalId::eoim());
+    let predict = |nt, idx: usize| {
+        let lookahead = &ids[idx..ids.len().min(idx + table.k)];
+        let entry = table.get(nt, lookahead);
+        assert!(entry.len() < 2);
+        entry.first().copied()
+    };
+    match ll1::run(grammar, tokens, predict, &Trees(grammar)) {
+        Some(tree) => ParseResult::Parse(tree),
+        None => ParseResult::NoParse,
+    }
+}
+
+/// Builds the table for `k` and parses `tokens` with it, or returns the
+/// conflicts of the table if the grammar is not strong LL(k).
+pub fn parse<'a>(grammar: &'a Grammar, tokens: &[&str], k: usize) -> ParseResult<'a, Conflict> {
+    let table = table_for(grammar, k);
+    if table.has_conflict() {
+        return ParseResult::Conflict(conflicts(&table));
+    }
+    parse_with_table(grammar, tokens, &table)
+}
+
+fn table_for(grammar: &Grammar, k: usize) -> LLkTable {
+    let first = create_first_k(grammar, k);
+    let follow = create_follow_k(grammar, &first, k);
+    create_table(grammar, &first, &follow, k)
+}
+
+/// Returns the smallest `k` from 1 to `max_k` for which `grammar` is strong
+/// LL(k), with its table.
+pub fn smallest_k(grammar: &Grammar, max_k: usize) -> Option<(usize, LLkTable)> {
+    (1..=max_k)
+        .map(|k| (k, table_for(grammar, k)))
+        .find(|(_, table)| !table.has_conflict())
+}
@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::grammar::{NonTerminal, Terminal};
+use crate::grammar::{Grammar, NonTerminal, Terminal};
 
 #[derive(Debug, Clone)]
 pub enum ParseTree<'a> {
@@ -8,15 +8,110 @@ pub enum ParseTree<'a> {
     NonTerminal(&'a NonTerminal, Vec<ParseTree<'a>>),
 }
 
+/// A [`ParseTree`] that owns its symbols, so that it can outlive its grammar.
+///
+/// With the `serde` feature, a leaf is serialized as `{"terminal": "x"}` and a
+/// node as `{"nonterminal": ["E", [children...]]}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum OwnedParseTree {
+    Terminal(Terminal),
+    NonTerminal(NonTerminal, Vec<OwnedParseTree>),
+}
+
+impl OwnedParseTree {
+    pub fn as_tree(&self) -> ParseTree<'_> {
+        match self {
+            OwnedParseTree::Terminal(t) => ParseTree::Terminal(t),
+            OwnedParseTree::NonTerminal(nt, children) => {
+                ParseTree::NonTerminal(nt, children.iter().map(Self::as_tree).collect())
+            }
+        }
+    }
+}
+
+impl fmt::Display for OwnedParseTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_tree().fmt(f)
+    }
+}
+
 impl<'a> ParseTree<'a> {
-    fn write_tree(&self, f: &mut fmt::Formatter<'_>, level: usize) -> fmt::Result {
+    pub fn to_owned_tree(&self) -> OwnedParseTree {
+        match self {
+            ParseTree::Terminal(t) => OwnedParseTree::Terminal((*t).clone()),
+            ParseTree::NonTerminal(nt, children) => OwnedParseTree::NonTerminal(
+                (*nt).clone(),
+                children.iter().map(Self::to_owned_tree).collect(),
+            ),
+        }
+    }
+
+    /// Returns the tree with every node of a synthetic nonterminal below the
+    /// root replaced by its children.
+    pub fn without_synthetic(&self, grammar: &Grammar) -> ParseTree<'a> {
+        match self {
+            ParseTree::Terminal(t) => ParseTree::Terminal(t),
+            ParseTree::NonTerminal(nt, children) => {
+                let mut spliced = vec![];
+                Self::splice_synthetic(children, grammar, &mut spliced);
+                ParseTree::NonTerminal(nt, spliced)
+            }
+        }
+    }
+
+    fn splice_synthetic(
+        children: &[ParseTree<'a>],
+        grammar: &Grammar,
+        out: &mut Vec<ParseTree<'a>>,
+    ) {
+        for child in children {
+            match child {
+                ParseTree::NonTerminal(nt, grandchildren)
+                    if grammar
+                        .nonterminal_id(&nt.0)
+                        .is_some_and(|nt| grammar.is_synthetic(nt)) =>
+                {
+                    Self::splice_synthetic(grandchildren, grammar, out)
+                }
+                child => out.push(child.without_synthetic(grammar)),
+            }
+        }
+    }
+
+    /// Displays the tree like its [`Display`](fmt::Display) impl, but with the
+    /// [display name](Grammar::display_name) of each nonterminal, so that the
+    /// instances of a parameterised nonterminal show as `list(Expr)`.
+    pub fn display<'t>(&'t self, grammar: &'t Grammar) -> DisplayTree<'t, 'a> {
+        DisplayTree {
+            tree: self,
+            grammar,
+        }
+    }
+
+    fn write_tree(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        level: usize,
+        grammar: Option<&Grammar>,
+    ) -> fmt::Result {
         match self {
             ParseTree::Terminal(t) => write!(f, "{}", t.0)?,
             ParseTree::NonTerminal(nt, children) => {
-                write!(f, "{}", nt.0)?;
+                let name = grammar
+                    .and_then(|grammar| {
+                        let id = grammar.nonterminal_id(&nt.0)?;
+                        Some(grammar.display_name(id))
+                    })
+                    .unwrap_or(&nt.0);
+                write!(f, "{}", name)?;
                 for (i, child) in children.iter().enumerate() {
                     write!(f, "{}", "\t".repeat(if i == 0 { 1 } else { level + 1 }))?;
-                    child.write_tree(f, level + 1)?;
+                    child.write_tree(f, level + 1, grammar)?;
                     if i + 1 < children.len() {
                         writeln!(f)?;
                     }
@@ -29,6 +124,17 @@ impl<'a> ParseTree<'a> {
 
 impl<'a> fmt::Display for ParseTree<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.write_tree(f, 0)
+        self.write_tree(f, 0, None)
+    }
+}
+
+pub struct DisplayTree<'t, 'a> {
+    tree: &'t ParseTree<'a>,
+    grammar: &'t Grammar,
+}
+
+impl<'t, 'a> fmt::Display for DisplayTree<'t, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tree.write_tree(f, 0, Some(self.grammar))
     }
 }
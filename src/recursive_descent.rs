@@ -1,15 +1,16 @@
 use std::collections::VecDeque;
 
 use crate::{
-    grammar::{Grammar, NonTerminal, Symbol, Terminal},
+    actions::{Actions, Reduce, Trees},
+    grammar::{Grammar, NonTerminalId, Symbol, TerminalId},
     parse_tree::ParseTree,
 };
 
 #[derive(Debug, Clone)]
-enum Elem<'a> {
-    Terminal(&'a Terminal),
-    Unexpanded(&'a NonTerminal),
-    Expanded(&'a NonTerminal, usize), // usize is arena_idx of child tree
+enum Elem {
+    Terminal(TerminalId),
+    Unexpanded(NonTerminalId),
+    Expanded(usize, usize), // production index, and arena_idx of child tree
 }
 
 #[derive(Debug, Clone)]
@@ -45,7 +46,7 @@ struct Complete;
 #[derive(Debug, Clone)]
 struct Tree<'a, C> {
     grammar: &'a Grammar,
-    arena: Vec<Vec<Elem<'a>>>,
+    arena: Vec<Vec<Elem>>,
     // Invariant: position must be valid
     position: (usize, usize),            // (arena_idx, rhs_idx)
     parent: Vec<Option<(usize, usize)>>, // [position]. Parent of root is None
@@ -76,10 +77,12 @@ impl<'a> Tree<'a, Incomplete> {
         TreeResult::Incomplete(self)
     }
 
-    fn step(mut self, token: Option<&str>) -> Step<'a> {
+    /// `token` is `None` at the end of input, and `Some(None)` for a token that is
+    /// not a terminal of the grammar.
+    fn step(mut self, token: Option<Option<TerminalId>>) -> Step<'a> {
         match self.arena[self.position.0][self.position.1] {
             Elem::Terminal(t) => {
-                if Some(t.0.as_str()) != token {
+                if Some(Some(t)) != token {
                     // HACK:
                     return Step::NonTerminal([].into());
                 }
@@ -88,18 +91,19 @@ impl<'a> Tree<'a, Incomplete> {
             }
             Elem::Unexpanded(nt) => {
                 let mut new_trees = vec![];
-                for production in self.grammar.productions_from(nt) {
+                let indices = self.grammar.production_indices(nt);
+                for (production, &idx) in self.grammar.productions_from(nt).zip(indices) {
                     let mut new_tree = self.clone();
                     let subtree = production
                         .rhs()
                         .iter()
-                        .map(|s| match s {
+                        .map(|&s| match s {
                             Symbol::Terminal(t) => Elem::Terminal(t),
                             Symbol::NonTerminal(nt) => Elem::Unexpanded(nt),
                         })
                         .collect();
                     new_tree.arena[self.position.0][self.position.1] =
-                        Elem::Expanded(nt, self.arena.len());
+                        Elem::Expanded(idx, self.arena.len());
                     new_tree.position = (self.arena.len(), 0);
                     new_tree.arena.push(subtree);
                     new_tree.parent.push(Some(self.position));
@@ -114,33 +118,32 @@ impl<'a> Tree<'a, Incomplete> {
 }
 
 impl<'a> Tree<'a, Complete> {
-    fn idx_to_astnodes(&self, arena_idx: usize) -> Vec<ParseTree<'a>> {
-        let elems = &self.arena[arena_idx];
-        let children = elems
+    fn reduce_children<R: Reduce<'a>>(&self, arena_idx: usize, reducer: &R) -> Vec<R::Value> {
+        self.arena[arena_idx]
             .iter()
-            .map(|elem| match elem {
-                Elem::Terminal(t) => ParseTree::Terminal(t),
-                Elem::Unexpanded(_) => unreachable!("Trying to convert unexpanded into AST"),
-                &Elem::Expanded(nt, arena_idx) => {
-                    ParseTree::NonTerminal(nt, self.idx_to_astnodes(arena_idx))
+            .map(|elem| match *elem {
+                Elem::Terminal(t) => reducer.terminal(self.grammar.terminal(t)),
+                Elem::Unexpanded(_) => unreachable!("Trying to reduce unexpanded nonterminal"),
+                Elem::Expanded(production, arena_idx) => {
+                    reducer.production(production, self.reduce_children(arena_idx, reducer))
                 }
             })
-            .collect::<Vec<_>>();
-        children
+            .collect()
     }
 
-    fn to_ast(&self) -> ParseTree<'a> {
-        self.idx_to_astnodes(0)
+    fn reduce<R: Reduce<'a>>(&self, reducer: &R) -> R::Value {
+        self.reduce_children(0, reducer)
             .pop()
             .expect("Parse tree was empty. No root node?")
     }
 }
 
-pub fn parse<'a>(grammar: &'a Grammar, tokens: &[&str]) -> Option<ParseTree<'a>> {
+/// Searches the leftmost derivations of `tokens` breadth first, and returns
+/// the first complete one.
+fn search<'a>(grammar: &'a Grammar, tokens: &[&str]) -> Option<Tree<'a, Complete>> {
+    let tokens = grammar.token_ids(tokens);
     let mut bag = VecDeque::from([(TreeResult::new(grammar), 0)]);
-    let mut steps = 0;
     while let Some((tree_result, idx)) = bag.pop_back() {
-        steps += 1;
         match tree_result {
             TreeResult::Incomplete(tree) => match tree.step(tokens.get(idx).copied()) {
                 Step::Terminal(tree_result) => bag.push_front((tree_result, idx + 1)),
@@ -153,12 +156,20 @@ pub fn parse<'a>(grammar: &'a Grammar, tokens: &[&str]) -> Option<ParseTree<'a>>
 
             TreeResult::Complete(tree) => {
                 if idx == tokens.len() {
-                    dbg!(steps);
-                    return Some(tree.to_ast());
+                    return Some(tree);
                 }
             }
         }
     }
-    dbg!(steps);
     None
 }
+
+pub fn parse<'a>(grammar: &'a Grammar, tokens: &[&str]) -> Option<ParseTree<'a>> {
+    search(grammar, tokens).map(|tree| tree.reduce(&Trees(grammar)))
+}
+
+/// Like [`parse`], but computes the value of the sentence with `actions`
+/// instead of its parse tree.
+pub fn evaluate<T>(actions: &Actions<'_, T>, tokens: &[&str]) -> Option<T> {
+    search(actions.grammar(), tokens).map(|tree| tree.reduce(actions).into_value())
+}
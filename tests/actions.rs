@@ -0,0 +1,110 @@
+mod common;
+
+use common::tokens;
+use parsing::{actions::Actions, earley, grammar::Grammar, ll1, recursive_descent};
+
+/// A calculator over `grammars/expr_ll1.grammar`, with `w`, `x`, `y` and `z`
+/// standing for 1, 2, 3 and 4.
+fn calculator(grammar: &Grammar) -> Actions<'_, i64> {
+    let mut actions = Actions::new(grammar);
+    actions
+        .on("E -> T E'", |c| c[0].value() + c[1].value())
+        .on("E' -> + T E'", |c| c[1].value() + c[2].value())
+        .on("E' -> ε", |_| 0)
+        .on("T -> F T'", |c| c[0].value() * c[1].value())
+        .on("T' -> * F T'", |c| c[1].value() * c[2].value())
+        .on("T' ->", |_| 1)
+        .on("F -> ( E )", |c| *c[1].value());
+    for (value, name) in (1..).zip(["w", "x", "y", "z"]) {
+        actions.on(&format!("ID -> {}", name), move |_| value);
+    }
+    actions
+}
+
+#[test]
+fn calculator_ll1() {
+    let grammar = Grammar::load("grammars/expr_ll1.grammar").unwrap();
+    let actions = calculator(&grammar);
+    let input = tokens("w + x * ( y + z ) * w + y * x");
+    assert_eq!(ll1::evaluate(&actions, &input), ll1::EvalResult::Value(21));
+    assert_eq!(
+        ll1::evaluate(&actions, &tokens("x * ( w")),
+        ll1::EvalResult::NoParse
+    );
+    assert_eq!(recursive_descent::evaluate(&actions, &input), Some(21));
+    assert_eq!(earley::evaluate(&actions, &input), [21]);
+
+    let tree = recursive_descent::parse(&grammar, &input).unwrap();
+    assert_eq!(actions.evaluate(&tree), Some(21));
+}
+
+#[test]
+fn values_per_tree() {
+    let grammar = "E -> E \"+\" E | E \"*\" E | x;"
+        .parse::<Grammar>()
+        .unwrap();
+    let mut actions = Actions::new(&grammar);
+    actions
+        .on("E -> E + E", |c| c[0].value() + c[2].value())
+        .on("E -> E * E", |c| c[0].value() * c[2].value())
+        .on("E -> x", |_| 3);
+    let mut values = earley::evaluate(&actions, &tokens("x + x * x"));
+    values.sort();
+    assert_eq!(values, [12, 18]);
+    assert!(matches!(
+        ll1::evaluate(&actions, &tokens("x")),
+        ll1::EvalResult::Conflict(conflicts) if conflicts.len() == 1
+    ));
+
+    let precedence = format!("%left \"+\";\n%left \"*\";\n{}", grammar);
+    let grammar = precedence.parse::<Grammar>().unwrap();
+    let mut actions = Actions::new(&grammar);
+    actions
+        .on_index(0, |c| c[0].value() + c[2].value())
+        .on_index(1, |c| c[0].value() * c[2].value())
+        .on_index(2, |_| 3);
+    assert_eq!(earley::evaluate(&actions, &tokens("x + x * x")), [12]);
+}
+
+#[test]
+fn terminals_and_defaults() {
+    let grammar = "S -> L; L -> I L | ε; I -> a | b;"
+        .parse::<Grammar>()
+        .unwrap();
+    let mut actions = Actions::<String>::new(&grammar);
+    actions
+        .on("L -> I L", |c| {
+            let [i, l] = <[_; 2]>::try_from(c)
+                .unwrap()
+                .map(|child| child.into_value());
+            i + &l
+        })
+        .on("L ->", |_| String::new())
+        .on("I -> a", |c| c[0].terminal().0.to_uppercase())
+        .on("I -> b", |c| c[0].terminal().0.clone());
+    // `S -> L` has no action, so it passes on the value of `L`.
+    assert_eq!(
+        ll1::evaluate(&actions, &tokens("a b a")),
+        ll1::EvalResult::Value("AbA".to_string())
+    );
+    assert_eq!(
+        recursive_descent::evaluate(&actions, &tokens("b b")),
+        Some("bb".to_string())
+    );
+}
+
+#[test]
+#[should_panic(expected = "no production `E -> E - E`")]
+fn unknown_production() {
+    let grammar = "E -> E \"+\" E | x;".parse::<Grammar>().unwrap();
+    Actions::<()>::new(&grammar).on("E -> E - E", |_| ());
+}
+
+#[test]
+#[should_panic(expected = "no action for production `E -> E + E`")]
+fn missing_action() {
+    let grammar = "E -> E \"+\" E | x;".parse::<Grammar>().unwrap();
+    let mut actions = Actions::new(&grammar);
+    actions.on("E -> x", |_| 1);
+    earley::evaluate(&actions, &tokens("x + x"));
+}
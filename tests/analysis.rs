@@ -0,0 +1,77 @@
+use parsing::{analysis::find_ambiguity, earley, grammar::Grammar};
+
+fn rules(grammar: &Grammar, productions: [usize; 2]) -> [String; 2] {
+    productions.map(|idx| grammar.productions()[idx].display(grammar).to_string())
+}
+
+#[test]
+fn ambiguous_expressions() {
+    let grammar = include_str!("../grammars/expr_ambiguous.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    let ambiguity = find_ambiguity(&grammar, 5).unwrap();
+    assert_eq!(ambiguity.tokens, ["w", "*", "w", "*", "w"]);
+    // Both trees start with `E -> E * E`, and then expand its first `E`
+    // differently.
+    assert_eq!(grammar.nonterminal(ambiguity.nonterminal).0, "E");
+    let mut rules = rules(&grammar, ambiguity.productions);
+    rules.sort();
+    assert_eq!(rules, ["E -> E * E", "E -> ID"]);
+
+    let mut trees = ambiguity.trees.map(|tree| tree.to_string());
+    trees.sort();
+    let mut parses = earley::parse(&grammar, &ambiguity.tokens)
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    parses.sort();
+    assert_eq!(parses, trees);
+}
+
+#[test]
+fn unambiguous() {
+    let grammar = include_str!("../grammars/expr.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    assert!(find_ambiguity(&grammar, 5).is_none());
+    // Ambiguity that only shows in longer sentences is not found.
+    let grammar = "%terminal if then else a;\n\
+                   S -> if S then S | if S then S else S | a;"
+        .parse::<Grammar>()
+        .unwrap();
+    assert!(find_ambiguity(&grammar, 8).is_none());
+    let ambiguity = find_ambiguity(&grammar, 10).unwrap();
+    assert_eq!(ambiguity.tokens.join(" "), "if a then if a then a else a");
+    assert_eq!(grammar.nonterminal(ambiguity.nonterminal).0, "S");
+}
+
+#[test]
+fn divergence_below_the_root() {
+    let grammar = "%terminal a c;\nS -> c A;\nA -> B | C;\nB -> a;\nC -> a;"
+        .parse::<Grammar>()
+        .unwrap();
+    let ambiguity = find_ambiguity(&grammar, 3).unwrap();
+    assert_eq!(ambiguity.tokens, ["c", "a"]);
+    assert_eq!(grammar.nonterminal(ambiguity.nonterminal).0, "A");
+    assert_eq!(rules(&grammar, ambiguity.productions), ["A -> B", "A -> C"]);
+}
+
+#[test]
+fn cycles_and_duplicates() {
+    // The trees of a duplicated production look the same.
+    let grammar = "%terminal a;\nS -> a | a;".parse::<Grammar>().unwrap();
+    let ambiguity = find_ambiguity(&grammar, 1).unwrap();
+    assert_eq!(ambiguity.productions, [0, 1]);
+    assert_eq!(
+        ambiguity.trees[0].to_string(),
+        ambiguity.trees[1].to_string()
+    );
+
+    let grammar = "%terminal a;\nS -> S | a;".parse::<Grammar>().unwrap();
+    let ambiguity = find_ambiguity(&grammar, 1).unwrap();
+    assert_eq!(ambiguity.tokens, ["a"]);
+    assert_ne!(
+        ambiguity.trees[0].to_string(),
+        ambiguity.trees[1].to_string()
+    );
+}
@@ -0,0 +1,228 @@
+mod common;
+
+use common::tokens;
+use parsing::{
+    attributes::{AttributeError, AttributeGrammar, AttributeGrammarBuilder, EvaluationError},
+    earley,
+    grammar::Grammar,
+};
+
+fn binary_grammar() -> Grammar {
+    "N -> L \".\" L | L;\nL -> L B | B;\nB -> \"0\" | \"1\";"
+        .parse()
+        .unwrap()
+}
+
+/// Knuth's attribute grammar for binary numbers with a fraction, where the
+/// scale of each bit is inherited and the scale of the fraction depends on
+/// its length.
+fn binary(grammar: &Grammar) -> AttributeGrammarBuilder<'_, f64> {
+    let mut builder = AttributeGrammar::builder(grammar);
+    builder
+        .synthesized("N", "val")
+        .inherited("L", "scale")
+        .synthesized("L", "val")
+        .synthesized("L", "len")
+        .inherited("B", "scale")
+        .synthesized("B", "val")
+        .equation("N -> L . L", "$1.scale", &[], |_| 0.0)
+        .equation("N -> L . L", "$3.scale", &["$3.len"], |v| -v[0])
+        .equation("N -> L . L", "$$.val", &["$1.val", "$3.val"], |v| {
+            v[0] + v[1]
+        })
+        .equation("N -> L", "$1.scale", &[], |_| 0.0)
+        .equation("N -> L", "$$.val", &["$1.val"], |v| *v[0])
+        .equation("L -> L B", "$1.scale", &["$$.scale"], |v| v[0] + 1.0)
+        .equation("L -> L B", "$2.scale", &["$$.scale"], |v| *v[0])
+        .equation("L -> L B", "$$.val", &["$1.val", "$2.val"], |v| v[0] + v[1])
+        .equation("L -> L B", "$$.len", &["$1.len"], |v| v[0] + 1.0)
+        .equation("L -> B", "$1.scale", &["$$.scale"], |v| *v[0])
+        .equation("L -> B", "$$.val", &["$1.val"], |v| *v[0])
+        .equation("L -> B", "$$.len", &[], |_| 1.0)
+        .equation("B -> 0", "$$.val", &[], |_| 0.0)
+        .equation("B -> 1", "$$.val", &["$$.scale"], |v| 2f64.powf(*v[0]));
+    builder
+}
+
+#[test]
+fn binary_numbers() {
+    let grammar = binary_grammar();
+    let attributes = binary(&grammar).build().unwrap();
+    let trees = earley::parse(&grammar, &tokens("1 1 0 1 . 0 1"));
+    assert_eq!(trees.len(), 1);
+    let evaluation = attributes.evaluate(&trees[0], vec![]).unwrap();
+    assert_eq!(evaluation.root("val"), Some(&13.25));
+    assert_eq!(evaluation.get(&[0], "len"), Some(&4.0));
+    assert_eq!(evaluation.get(&[2], "scale"), Some(&-2.0));
+    assert_eq!(evaluation.get(&[2, 1], "val"), Some(&0.25));
+    assert_eq!(evaluation.get(&[1], "val"), None);
+    assert_eq!(evaluation.get(&[0], "missing"), None);
+
+    // A subtree can be evaluated on its own, given its inherited attributes.
+    let trees = earley::parse(&grammar, &tokens("1 0 1"));
+    let parsing::parse_tree::ParseTree::NonTerminal(_, children) = &trees[0] else {
+        panic!()
+    };
+    let evaluation = attributes
+        .evaluate(&children[0], vec![("scale", 1.0)])
+        .unwrap();
+    assert_eq!(evaluation.root("val"), Some(&10.0));
+    assert_eq!(
+        attributes.evaluate(&children[0], vec![]).unwrap_err(),
+        EvaluationError::MissingInherited("scale".to_string())
+    );
+    assert_eq!(
+        attributes
+            .evaluate(&children[0], vec![("val", 1.0)])
+            .unwrap_err(),
+        EvaluationError::UnknownInherited("val".to_string())
+    );
+
+    let other = "N -> x;".parse::<Grammar>().unwrap();
+    let trees = earley::parse(&other, &["x"]);
+    assert_eq!(
+        attributes.evaluate(&trees[0], vec![]).unwrap_err(),
+        EvaluationError::NotAProduction("N".to_string())
+    );
+}
+
+#[test]
+fn circular() {
+    let grammar = "S -> A;\nA -> a;".parse::<Grammar>().unwrap();
+    let mut builder = AttributeGrammar::<u32>::builder(&grammar);
+    builder
+        .synthesized("S", "v")
+        .inherited("A", "i")
+        .synthesized("A", "s")
+        .equation("S -> A", "$1.i", &["$1.s"], |v| *v[0])
+        .equation("S -> A", "$$.v", &["$1.s"], |v| *v[0])
+        .equation("A -> a", "$$.s", &["$$.i"], |v| *v[0]);
+    let error = builder.build().unwrap_err();
+    assert_eq!(
+        error,
+        AttributeError::Circular {
+            production: 0,
+            cycle: vec!["$1.i".to_string(), "$1.s".to_string()],
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "attribute `$1.i` of production 0 depends on itself: $1.i -> $1.s -> $1.i"
+    );
+
+    // Without the dependency of `s` on `i` in the subtree there is no cycle.
+    let mut builder = AttributeGrammar::<u32>::builder(&grammar);
+    builder
+        .synthesized("S", "v")
+        .inherited("A", "i")
+        .synthesized("A", "s")
+        .equation("S -> A", "$1.i", &["$1.s"], |v| *v[0])
+        .equation("S -> A", "$$.v", &["$1.i"], |v| *v[0])
+        .equation("A -> a", "$$.s", &[], |_| 7);
+    let attributes = builder.build().unwrap();
+    let trees = earley::parse(&grammar, &["a"]);
+    let evaluation = attributes.evaluate(&trees[0], vec![]).unwrap();
+    assert_eq!(evaluation.root("v"), Some(&7));
+
+    let grammar = "E -> x;".parse::<Grammar>().unwrap();
+    let mut builder = AttributeGrammar::<u32>::builder(&grammar);
+    builder
+        .synthesized("E", "a")
+        .synthesized("E", "b")
+        .equation("E -> x", "$$.a", &["$$.b"], |v| *v[0])
+        .equation("E -> x", "$$.b", &["$$.a"], |v| *v[0]);
+    assert_eq!(
+        builder.build().unwrap_err(),
+        AttributeError::Circular {
+            production: 0,
+            cycle: vec!["$$.a".to_string(), "$$.b".to_string()],
+        }
+    );
+}
+
+#[test]
+fn errors() {
+    let grammar = binary_grammar();
+    let build = |f: &dyn Fn(&mut AttributeGrammarBuilder<'_, f64>)| {
+        let mut builder = binary(&grammar);
+        f(&mut builder);
+        builder.build().unwrap_err()
+    };
+    let invalid = |production, attribute: &str| AttributeError::InvalidAttribute {
+        production,
+        attribute: attribute.to_string(),
+    };
+
+    assert_eq!(
+        build(&|b| {
+            b.synthesized("X", "val");
+        }),
+        AttributeError::UndeclaredNonTerminal("X".to_string())
+    );
+    assert_eq!(
+        build(&|b| {
+            b.inherited("L", "len");
+        }),
+        AttributeError::DuplicateAttribute {
+            nonterminal: "L".to_string(),
+            attribute: "len".to_string()
+        }
+    );
+    assert_eq!(
+        build(&|b| {
+            b.equation("B -> 2", "$$.val", &[], |_| 2.0);
+        }),
+        AttributeError::UnknownProduction("B -> 2".to_string())
+    );
+    for attribute in ["$2.val", "$4.val", "$0.val", "$1.size", "val", "$x.val"] {
+        assert_eq!(
+            build(&|b| {
+                b.equation("N -> L . L", "$$.val", &[attribute], |_| 0.0);
+            }),
+            invalid(0, attribute)
+        );
+    }
+    assert_eq!(
+        build(&|b| {
+            b.equation("L -> B", "$$.scale", &[], |_| 0.0);
+        }),
+        AttributeError::NotDefinable {
+            production: 3,
+            attribute: "$$.scale".to_string()
+        }
+    );
+    assert_eq!(
+        build(&|b| {
+            b.equation("L -> B", "$1.val", &[], |_| 0.0);
+        }),
+        AttributeError::NotDefinable {
+            production: 3,
+            attribute: "$1.val".to_string()
+        }
+    );
+    assert_eq!(
+        build(&|b| {
+            b.equation("L -> B", "$$.len", &[], |_| 0.0);
+        }),
+        AttributeError::DuplicateEquation {
+            production: 3,
+            attribute: "$$.len".to_string()
+        }
+    );
+    assert_eq!(
+        build(&|b| {
+            b.synthesized("B", "len");
+        }),
+        AttributeError::MissingEquation {
+            production: 4,
+            attribute: "$$.len".to_string()
+        }
+    );
+    assert_eq!(
+        build(&|b| {
+            b.synthesized("N", "len");
+        })
+        .to_string(),
+        "production 0 has no equation for `$$.len`"
+    );
+}
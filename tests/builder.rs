@@ -0,0 +1,76 @@
+use parsing::{
+    grammar::{Grammar, GrammarError},
+    ll1,
+};
+
+fn expr() -> Result<Grammar, GrammarError> {
+    let mut builder = Grammar::builder();
+    for t in ["+", "*", "(", ")", "x"] {
+        builder.add_terminal(t);
+    }
+    for nt in ["E", "E'", "T", "T'", "F"] {
+        builder.add_nonterminal(nt);
+    }
+    builder
+        .add_production("E", ["T", "E'"])
+        .add_production("E'", ["+", "T", "E'"])
+        .add_production("E'", [] as [&str; 0])
+        .add_production("T", ["F", "T'"])
+        .add_production("T'", ["*", "F", "T'"])
+        .add_production("T'", [] as [&str; 0])
+        .add_production("F", ["(", "E", ")"])
+        .add_production("F", ["x"])
+        .set_start("E")
+        .build()
+}
+
+#[test]
+fn builds_usable_grammar() {
+    let grammar = expr().unwrap();
+    assert_eq!(grammar.validate(), []);
+    let tokens = ["x", "+", "x", "*", "x"];
+    assert!(matches!(
+        ll1::parse(&grammar, &tokens),
+        ll1::ParseResult::Parse(_)
+    ));
+}
+
+#[test]
+fn rejects_malformed_grammars() {
+    let mut builder = Grammar::builder();
+    builder
+        .add_terminal("a")
+        .add_nonterminal("S")
+        .add_production("S", ["a", "S"]);
+    assert_eq!(builder.build().unwrap_err(), GrammarError::NoStart);
+
+    builder.set_start("T");
+    assert_eq!(
+        builder.build().unwrap_err(),
+        GrammarError::UndeclaredStart("T".to_string())
+    );
+
+    builder.set_start("S").add_production("T", ["a"]);
+    assert_eq!(
+        builder.build().unwrap_err(),
+        GrammarError::UndeclaredLhs {
+            production: 1,
+            symbol: "T".to_string()
+        }
+    );
+
+    builder.add_nonterminal("T").add_production("S", ["b"]);
+    assert_eq!(
+        builder.build().unwrap_err(),
+        GrammarError::UndeclaredSymbol {
+            production: 2,
+            symbol: "b".to_string()
+        }
+    );
+
+    builder.add_terminal("b").add_terminal("T");
+    assert_eq!(
+        builder.build().unwrap_err(),
+        GrammarError::TerminalAndNonTerminal("T".to_string())
+    );
+}
@@ -0,0 +1,18 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use parsing::grammar::Grammar;
+
+pub fn tokens(input: &str) -> Vec<&str> {
+    input.split_whitespace().collect()
+}
+
+/// The productions of `grammar` as displayed, in order.
+pub fn productions(grammar: &Grammar) -> Vec<String> {
+    grammar
+        .productions()
+        .iter()
+        .map(|p| p.display(grammar).to_string())
+        .collect()
+}
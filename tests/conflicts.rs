@@ -0,0 +1,68 @@
+use parsing::{
+    first_follow::{create_first, create_follow},
+    grammar::{Grammar, Symbol},
+    ll1::{self, ConflictKind},
+};
+
+fn find_conflicts(grammar: &Grammar) -> Vec<ll1::Conflict> {
+    let first = create_first(grammar);
+    let follow = create_follow(grammar, &first);
+    let table = ll1::create_table(grammar, &first, &follow);
+    ll1::conflicts(grammar, &first, &follow, &table)
+}
+
+#[test]
+fn first_first() {
+    let grammar = include_str!("../grammars/expr.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    let conflicts = find_conflicts(&grammar);
+    assert_eq!(conflicts.len(), 10);
+    let conflict = &conflicts[0];
+    assert_eq!(conflict.nonterminal, grammar.nonterminal_id("E").unwrap());
+    assert_eq!(conflict.terminal, grammar.terminal_id("(").unwrap());
+    assert_eq!(conflict.productions, [0, 1]);
+    assert_eq!(conflict.kind, ConflictKind::FirstFirst);
+    assert_eq!(
+        conflict.example,
+        Some(vec![Symbol::NonTerminal(conflict.nonterminal)])
+    );
+    let report = ll1::conflicts_to_string(&grammar, &conflicts);
+    assert!(report.starts_with("E (\tFIRST/FIRST\tE -> E + T, E -> T\te.g. E\n"));
+    assert!(report.contains("T x\tFIRST/FIRST\tT -> T * F, T -> F\te.g. T\n"));
+    let ll1::ParseResult::Conflict(reported) = ll1::parse(&grammar, &["x"]) else {
+        panic!("expected a conflict");
+    };
+    assert_eq!(reported, conflicts);
+
+    let grammar = include_str!("../grammars/expr_ll1.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    assert_eq!(find_conflicts(&grammar), []);
+    assert_eq!(ll1::conflicts_to_string(&grammar, &[]), "");
+}
+
+#[test]
+fn first_follow() {
+    let grammar = "%terminal if then else x;\n\
+                   S -> if x then S E | x;\n\
+                   E -> else S | ε;"
+        .parse::<Grammar>()
+        .unwrap();
+    let conflicts = find_conflicts(&grammar);
+    assert_eq!(
+        ll1::conflicts_to_string(&grammar, &conflicts),
+        "E else\tFIRST/FOLLOW\tE -> else S, E -> \te.g. if x then if x then x E E\n"
+    );
+    assert_eq!(conflicts[0].kind, ConflictKind::FirstFollow);
+
+    // Two ε-productions conflict on everything that follows.
+    let grammar = "%terminal a;\nS -> A a;\nA -> B | ε;\nB -> ε;"
+        .parse::<Grammar>()
+        .unwrap();
+    let conflicts = find_conflicts(&grammar);
+    assert_eq!(
+        ll1::conflicts_to_string(&grammar, &conflicts),
+        "A a\tFIRST/FIRST\tA -> B, A -> \te.g. A a\n"
+    );
+}
@@ -0,0 +1,85 @@
+use parsing::{
+    cyk, earley,
+    grammar::{transform, Grammar, Symbol},
+};
+
+fn check(source: &str, inputs: &[&str]) {
+    let grammar = source.parse::<Grammar>().unwrap();
+    let cnf = transform::to_cnf(&grammar);
+    for production in cnf.grammar().productions() {
+        match production.rhs() {
+            [Symbol::Terminal(_)] | [Symbol::NonTerminal(_), Symbol::NonTerminal(_)] => {}
+            [] => assert_eq!(production.lhs(), cnf.grammar().start()),
+            _ => panic!(
+                "{} is not in Chomsky normal form",
+                production.display(cnf.grammar())
+            ),
+        }
+    }
+
+    for input in inputs {
+        let tokens = input.split_whitespace().collect::<Vec<_>>();
+        let expected = earley::parse(&grammar, &tokens)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        match cyk::parse_with_cnf(&cnf, &tokens) {
+            Some(tree) => assert!(
+                expected.contains(&tree.to_string()),
+                "{:?}: {} is not an Earley parse",
+                input,
+                tree
+            ),
+            None => assert!(expected.is_empty(), "{:?} was not parsed", input),
+        }
+    }
+}
+
+#[test]
+fn matches_earley() {
+    check(
+        include_str!("../grammars/expr_ll1.grammar"),
+        &["w + x * ( y + z ) * w + y * x", "w", "( w )", "w + ", "w w"],
+    );
+    check(
+        include_str!("../grammars/expr_ambiguous.grammar"),
+        &["w + x * y", "( w + x ) * y", "w * * x"],
+    );
+    check(
+        include_str!("../grammars/english.grammar"),
+        &["they can fish", "they can fish in rivers", "can can can"],
+    );
+    check(
+        include_str!("../grammars/binary.grammar"),
+        &["b", "b b b", ""],
+    );
+}
+
+#[test]
+fn nullable_start() {
+    check(
+        "%terminal a b;\nS -> A S B | ;\nA -> a | ;\nB -> b;",
+        &["", "b", "a b", "a b b", "a a b b", "b a"],
+    );
+    let grammar = "%terminal a;\nS -> a S | ;".parse::<Grammar>().unwrap();
+    let tree = cyk::parse(&grammar, &[]).unwrap();
+    assert_eq!(tree.to_string(), "S");
+}
+
+#[test]
+fn long_nullable_rhs() {
+    let rhs = (0..17).map(|i| format!("x{}?", i)).collect::<Vec<_>>();
+    let all = (0..17).map(|i| format!("x{}", i)).collect::<Vec<_>>();
+    check(
+        &format!("S -> {};", rhs.join(" ")),
+        &[
+            "",
+            "x3",
+            "x0 x16",
+            "x2 x5 x7",
+            &all.join(" "),
+            "x16 x0",
+            "x3 x3",
+        ],
+    );
+}
@@ -0,0 +1,54 @@
+use parsing::{earley, grammar::Grammar, ll1, recursive_descent};
+
+const EXPR: &str = r#"
+%terminal w x y z;
+E -> T ("+" T)*;
+T -> F ("*" F)*;
+F -> "(" E ")" | w | x | y | z;
+"#;
+
+#[test]
+fn lowering() {
+    let grammar = "S -> a? (b | c)+ d*;".parse::<Grammar>().unwrap();
+    let productions = grammar
+        .productions()
+        .iter()
+        .map(|p| p.display(&grammar).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        productions,
+        [
+            "S -> S_opt1 S_group1 S_rep1 S_rep2",
+            "S_opt1 -> a",
+            "S_opt1 -> ",
+            "S_rep1 -> b S_rep1",
+            "S_rep1 -> c S_rep1",
+            "S_rep1 -> ",
+            "S_group1 -> b",
+            "S_group1 -> c",
+            "S_rep2 -> d S_rep2",
+            "S_rep2 -> ",
+        ]
+    );
+    assert!(grammar.is_synthetic(grammar.nonterminal_id("S_rep1").unwrap()));
+    assert!(!grammar.is_synthetic(grammar.start()));
+}
+
+#[test]
+fn parsers_accept_ebnf() {
+    let grammar = EXPR.parse::<Grammar>().unwrap();
+    let tokens = "w + x * ( y + z )".split_whitespace().collect::<Vec<_>>();
+    let ans = "E\tT\tF\tw\n\t+\n\tT\tF\tx\n\t\t*\n\t\tF\t(\n\t\t\tE\tT\tF\ty\n\t\t\t\t+\n\t\t\t\tT\tF\tz\n\t\t\t)";
+
+    let ll1::ParseResult::Parse(tree) = ll1::parse(&grammar, &tokens) else {
+        panic!();
+    };
+    assert_eq!(tree.without_synthetic(&grammar).to_string(), ans);
+
+    let trees = earley::parse(&grammar, &tokens);
+    assert_eq!(trees.len(), 1);
+    assert_eq!(trees[0].without_synthetic(&grammar).to_string(), ans);
+
+    let tree = recursive_descent::parse(&grammar, &tokens).unwrap();
+    assert_eq!(tree.without_synthetic(&grammar).to_string(), ans);
+}
@@ -0,0 +1,85 @@
+use parsing::grammar::{export, Grammar};
+
+const GRAMMAR: &str = r#"
+%terminal id;
+List -> Item List' | ε;
+List' -> "," Item List' | ;
+Item -> id | "==" | "'";
+"#;
+
+#[test]
+fn bison() {
+    let grammar = GRAMMAR.parse::<Grammar>().unwrap();
+    assert_eq!(
+        export::to_bison(&grammar),
+        r#"%token TOK_3 "=="
+%token id
+%start List
+
+%%
+
+List
+    : Item List_p {}
+    | %empty {}
+    ;
+
+List_p
+    : ',' Item List_p {}
+    | %empty {}
+    ;
+
+Item
+    : id {}
+    | "==" {}
+    | '\'' {}
+    ;
+
+%%
+"#
+    );
+}
+
+#[test]
+fn antlr() {
+    let grammar = GRAMMAR.parse::<Grammar>().unwrap();
+    assert_eq!(
+        export::to_antlr(&grammar, "List"),
+        r#"grammar List;
+
+list
+    : item list_p
+    | /* ε */
+    ;
+
+list_p
+    : ',' item list_p
+    | /* ε */
+    ;
+
+item
+    : 'id'
+    | '=='
+    | '\''
+    ;
+
+WS : [ \t\r\n]+ -> skip ;
+"#
+    );
+}
+
+#[test]
+fn w3c_ebnf() {
+    let grammar = GRAMMAR.parse::<Grammar>().unwrap();
+    assert_eq!(
+        export::to_w3c_ebnf(&grammar),
+        r#"List ::= ( Item List_p )?
+List_p ::= ( "," Item List_p )?
+Item ::= "id" | "==" | "'"
+"#
+    );
+    let grammar = "S -> \"a'\\\"b\" | ε;".parse::<Grammar>().unwrap();
+    assert_eq!(
+        export::to_w3c_ebnf(&grammar),
+        "S ::= ( ( \"a'\" '\"' \"b\" ) )?\n"
+    );
+}
@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+
+use parsing::{
+    first_follow::{
+        concat_k, create_first, create_first_k, create_follow, create_follow_k, first_k_to_string,
+        first_rhs_k, follow_k_to_string,
+    },
+    grammar::{Grammar, Symbol, TerminalId},
+};
+
+/// A grammar that needs two tokens of lookahead to choose a production of `S`.
+const LL2: &str = "%terminal a b c;\n\
+                   S -> A a b | A b c;\n\
+                   A -> a A | ε;";
+
+#[test]
+fn k_equal_one() {
+    let grammar = include_str!("../grammars/expr_ll1.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    let first = create_first(&grammar);
+    let follow = create_follow(&grammar, &first);
+    let first_k = create_first_k(&grammar, 1);
+    let follow_k = create_follow_k(&grammar, &first_k, 1);
+    for &nt in grammar.nonterminals() {
+        let expected = first[nt.index()]
+            .iter()
+            .map(|t| t.iter().copied().collect::<Vec<_>>())
+            .collect::<HashSet<_>>();
+        assert_eq!(first_k[nt.index()], expected);
+        let expected = follow[nt.index()]
+            .iter()
+            .map(|&t| vec![t])
+            .collect::<HashSet<_>>();
+        assert_eq!(follow_k[nt.index()], expected);
+    }
+}
+
+#[test]
+fn k_equal_two() {
+    let grammar = LL2.parse::<Grammar>().unwrap();
+    let first = create_first_k(&grammar, 2);
+    assert_eq!(
+        first_k_to_string(&grammar, &first),
+        "A: ε, a, a a\nS: a a, a b, b c\n"
+    );
+    let follow = create_follow_k(&grammar, &first, 2);
+    assert_eq!(follow_k_to_string(&grammar, &follow), "A: a b, b c\nS: $\n");
+
+    let first = create_first_k(&grammar, 3);
+    assert_eq!(
+        first_k_to_string(&grammar, &first),
+        "A: ε, a, a a, a a a\nS: a a a, a a b, a b, a b c, b c\n"
+    );
+    assert_eq!(
+        follow_k_to_string(&grammar, &create_follow_k(&grammar, &first, 3)),
+        "A: a b $, b c $\nS: $\n"
+    );
+}
+
+#[test]
+fn rhs_and_concatenation() {
+    let grammar = LL2.parse::<Grammar>().unwrap();
+    let t = |name: &str| grammar.terminal_id(name).unwrap();
+    let nt = |name: &str| Symbol::NonTerminal(grammar.nonterminal_id(name).unwrap());
+    let first = create_first_k(&grammar, 2);
+
+    let set =
+        |sequences: &[&[TerminalId]]| sequences.iter().map(|s| s.to_vec()).collect::<HashSet<_>>();
+    assert_eq!(
+        first_rhs_k(&[nt("A"), nt("A")], &first, 2),
+        set(&[&[], &[t("a")], &[t("a"), t("a")]])
+    );
+    assert_eq!(
+        first_rhs_k(&[Symbol::Terminal(t("c")), nt("A")], &first, 2),
+        set(&[&[t("c")], &[t("c"), t("a")]])
+    );
+    assert_eq!(first_rhs_k(&[], &first, 2), set(&[&[]]));
+
+    assert_eq!(
+        concat_k(
+            &set(&[&[t("a")], &[t("b"), t("b")]]),
+            &set(&[&[], &[t("c"), t("c")]]),
+            2
+        ),
+        set(&[&[t("a")], &[t("a"), t("c")], &[t("b"), t("b")]])
+    );
+}
+
+#[test]
+#[should_panic(expected = "k must be at least 1")]
+fn k_equal_zero() {
+    let grammar = LL2.parse::<Grammar>().unwrap();
+    let first = create_first_k(&grammar, 1);
+    create_follow_k(&grammar, &first, 0);
+}
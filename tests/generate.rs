@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+
+use parsing::{
+    earley,
+    generate::{self, RandomGenerator},
+    grammar::{Grammar, Symbol},
+    ll1,
+    parse_tree::ParseTree,
+    recursive_descent,
+};
+
+fn height(tree: &ParseTree) -> usize {
+    match tree {
+        ParseTree::Terminal(_) => 0,
+        ParseTree::NonTerminal(_, children) => 1 + children.iter().map(height).max().unwrap_or(0),
+    }
+}
+
+#[test]
+fn random_sentences() {
+    let grammar = include_str!("../grammars/expr.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    let mut generator = RandomGenerator::new(&grammar, 7)
+        .max_depth(12)
+        .target_length(15);
+    let mut lengths = vec![];
+    for _ in 0..100 {
+        let (tokens, tree) = generator.generate_with_tree().unwrap();
+        assert!(height(&tree) <= 12);
+        let trees = earley::parse(&grammar, &tokens);
+        assert_eq!(trees.len(), 1);
+        assert_eq!(trees[0].to_string(), tree.to_string());
+        lengths.push(tokens.len());
+    }
+    assert!(lengths.iter().any(|&len| len > 5));
+
+    // The same seed gives the same sentences.
+    let sentences = |seed| {
+        let mut generator = RandomGenerator::new(&grammar, seed);
+        (0..10)
+            .map(|_| generator.generate().unwrap().join(" "))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(sentences(1), sentences(1));
+    assert_ne!(sentences(1), sentences(2));
+
+    // `E -> T -> F -> ID -> w` is the shallowest derivation.
+    let mut generator = RandomGenerator::new(&grammar, 0).max_depth(4);
+    assert_eq!(generator.generate().unwrap().len(), 1);
+    let mut generator = RandomGenerator::new(&grammar, 0).max_depth(3);
+    assert_eq!(generator.generate(), None);
+}
+
+#[test]
+fn short_target_length() {
+    let grammar = "%terminal a b;\nS -> a S | S b | ε;"
+        .parse::<Grammar>()
+        .unwrap();
+    let mut generator = RandomGenerator::new(&grammar, 3).target_length(0);
+    for _ in 0..20 {
+        assert_eq!(generator.generate().unwrap(), Vec::<&str>::new());
+    }
+}
+
+#[test]
+fn exhaustive() {
+    let grammar = "%terminal a b;\nS -> a S b | ε;"
+        .parse::<Grammar>()
+        .unwrap();
+    assert_eq!(
+        generate::enumerate(&grammar, 7)
+            .iter()
+            .map(|tokens| tokens.join(" "))
+            .collect::<Vec<_>>(),
+        ["", "a b", "a a b b", "a a a b b b"]
+    );
+
+    let grammar = "%terminal a b;\nS -> S S | a | b | ε;"
+        .parse::<Grammar>()
+        .unwrap();
+    let sentences = generate::enumerate_with_trees(&grammar, 2);
+    assert_eq!(
+        sentences
+            .iter()
+            .map(|(tokens, _)| tokens.join(" "))
+            .collect::<Vec<_>>(),
+        ["", "a", "b", "a a", "a b", "b a", "b b"]
+    );
+    assert_eq!(sentences[3].1.to_string(), "S\tS\ta\n\tS\ta");
+
+    // The tree of an ambiguous sentence is one of its parse trees.
+    let grammar = "%terminal a b;\nS -> S S | a | b;"
+        .parse::<Grammar>()
+        .unwrap();
+    let sentences = generate::enumerate_with_trees(&grammar, 3);
+    assert_eq!(sentences.len(), 2 + 4 + 8);
+    for (tokens, tree) in &sentences {
+        assert!(earley::parse(&grammar, tokens)
+            .iter()
+            .any(|t| t.to_string() == tree.to_string()));
+    }
+}
+
+#[test]
+fn enumeration_matches_parser() {
+    let grammar = include_str!("../grammars/expr.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    let sentences = generate::enumerate(&grammar, 5);
+    // 4 identifiers, 2 * 16 binary expressions, 4 in parentheses, and the
+    // length 5 sentences.
+    assert_eq!(
+        sentences.iter().filter(|s| s.len() <= 3).count(),
+        4 + 32 + 4
+    );
+    for tokens in &sentences {
+        assert_eq!(earley::parse(&grammar, tokens).len(), 1);
+    }
+}
+
+/// Adds the productions used in `tree` to `productions`, and the pairs of a
+/// production and the production of one of its children to `pairs`.
+fn used(
+    tree: &ParseTree,
+    productions: &mut HashSet<String>,
+    pairs: &mut HashSet<(String, String)>,
+) -> String {
+    let ParseTree::NonTerminal(nt, children) = tree else {
+        unreachable!()
+    };
+    let rhs = children
+        .iter()
+        .map(|child| match child {
+            ParseTree::Terminal(t) => t.to_string(),
+            ParseTree::NonTerminal(nt, _) => nt.to_string(),
+        })
+        .collect::<Vec<_>>();
+    let production = format!("{} -> {}", nt, rhs.join(" "));
+    for child in children {
+        if let ParseTree::NonTerminal(..) = child {
+            let child = used(child, productions, pairs);
+            pairs.insert((production.clone(), child));
+        }
+    }
+    productions.insert(production.clone());
+    production
+}
+
+#[test]
+fn coverage() {
+    let grammar = include_str!("../grammars/expr_ll1.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    let all_productions = grammar
+        .productions()
+        .iter()
+        .map(|p| p.display(&grammar).to_string())
+        .collect::<HashSet<_>>();
+    let mut all_pairs = HashSet::new();
+    for parent in grammar.productions() {
+        for &symbol in parent.rhs() {
+            if let Symbol::NonTerminal(nt) = symbol {
+                for child in grammar.productions_from(nt) {
+                    all_pairs.insert((
+                        parent.display(&grammar).to_string(),
+                        child.display(&grammar).to_string(),
+                    ));
+                }
+            }
+        }
+    }
+
+    let sentences = generate::production_coverage(&grammar);
+    assert!(sentences.len() < grammar.productions().len());
+    let mut productions = HashSet::new();
+    for tokens in &sentences {
+        let ll1::ParseResult::Parse(tree) = ll1::parse(&grammar, tokens) else {
+            panic!("{:?} does not parse", tokens);
+        };
+        used(&tree, &mut productions, &mut HashSet::new());
+    }
+    assert_eq!(productions, all_productions);
+
+    let sentences = generate::pair_coverage(&grammar);
+    let mut productions = HashSet::new();
+    let mut pairs = HashSet::new();
+    for tokens in &sentences {
+        let tree = recursive_descent::parse(&grammar, tokens).unwrap();
+        used(&tree, &mut productions, &mut pairs);
+    }
+    assert_eq!(productions, all_productions);
+    assert_eq!(pairs, all_pairs);
+}
+
+#[test]
+fn coverage_skips_useless_productions() {
+    let grammar = "%terminal a b c;\nS -> a | S b | D;\nD -> D c;\nU -> c;"
+        .parse::<Grammar>()
+        .unwrap();
+    assert_eq!(
+        generate::production_coverage(&grammar),
+        [vec!["a"], vec!["a", "b"]]
+    );
+    assert_eq!(
+        generate::pair_coverage(&grammar),
+        [vec!["a", "b"], vec!["a", "b", "b"]]
+    );
+}
@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+
+use parsing::grammar::{
+    build_grammar,
+    text::{ParseError, ParseErrorKind},
+    Grammar,
+};
+
+const EXPR: &str = include_str!("../grammars/expr_ll1.grammar");
+
+/// Everything about a grammar by name, as ids depend on the order of interning.
+fn summary(grammar: &Grammar) -> (Vec<String>, Vec<String>, Vec<String>, String) {
+    let sorted = |mut v: Vec<String>| {
+        v.sort();
+        v
+    };
+    (
+        sorted(
+            grammar
+                .nonterminals()
+                .iter()
+                .map(|&nt| grammar.nonterminal(nt).to_string())
+                .collect(),
+        ),
+        sorted(
+            grammar
+                .terminals()
+                .iter()
+                .map(|&t| grammar.terminal(t).to_string())
+                .collect(),
+        ),
+        grammar
+            .productions()
+            .iter()
+            .map(|p| p.display(grammar).to_string())
+            .collect(),
+        grammar.nonterminal(grammar.start()).to_string(),
+    )
+}
+
+#[test]
+fn matches_build_grammar() {
+    let expected = build_grammar(
+        "E E' T T' F ID",
+        "+ * ( ) x y z w",
+        vec![
+            ("E", "T E'"),
+            ("E'", "+ T E' | "),
+            ("T", "F T'"),
+            ("T'", "* F T' | "),
+            ("F", "( E ) | ID"),
+            ("ID", "w | x | y | z"),
+        ],
+        "E",
+    );
+    let grammar = EXPR.parse::<Grammar>().unwrap();
+    assert_eq!(summary(&grammar), summary(&expected));
+}
+
+#[test]
+fn round_trip() {
+    let grammar = EXPR.parse::<Grammar>().unwrap();
+    let text = grammar.to_string();
+    let reparsed = text.parse::<Grammar>().unwrap();
+    assert_eq!(summary(&reparsed), summary(&grammar));
+    assert_eq!(reparsed.to_string(), text);
+}
+
+#[test]
+fn quoted_literals_and_undeclared_terminals() {
+    let grammar = "S -> \"a \\\"b\\\"\" S | c | ;".parse::<Grammar>().unwrap();
+    let terminals = grammar
+        .terminals()
+        .iter()
+        .map(|&t| grammar.terminal(t).0.as_str())
+        .collect::<HashSet<_>>();
+    // `c` is used but never declared.
+    assert_eq!(terminals, HashSet::from(["a \"b\""]));
+    assert_eq!(grammar.productions().len(), 3);
+    assert!(grammar.productions()[2].rhs().is_empty());
+}
+
+#[test]
+fn errors_have_positions() {
+    let err = "S -> a;\n%start S;\nS -> b\n"
+        .parse::<Grammar>()
+        .unwrap_err();
+    assert_eq!((err.line, err.column), (4, 1));
+    assert!(matches!(err.kind, ParseErrorKind::Expected { .. }));
+
+    let err = "S -> \"a;".parse::<Grammar>().unwrap_err();
+    assert_eq!(
+        err,
+        ParseError {
+            line: 1,
+            column: 6,
+            kind: ParseErrorKind::UnterminatedLiteral
+        }
+    );
+
+    let err = "%terminal S;\nS -> a;".parse::<Grammar>().unwrap_err();
+    assert_eq!((err.line, err.column), (1, 11));
+    assert_eq!(
+        err.kind,
+        ParseErrorKind::TerminalAndNonTerminal("S".to_string())
+    );
+}
+
+#[test]
+fn load() {
+    let grammar = Grammar::load("grammars/english.grammar").unwrap();
+    assert_eq!(grammar.nonterminal(grammar.start()).0, "S");
+    assert!(Grammar::load("grammars/missing.grammar").is_err());
+}
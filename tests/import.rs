@@ -0,0 +1,165 @@
+mod common;
+
+use common::{productions, tokens};
+use parsing::{
+    earley,
+    grammar::{
+        text::{LoadError, ParseErrorKind},
+        Grammar, GrammarError,
+    },
+};
+
+fn expr() -> Grammar {
+    Grammar::load("grammars/modules/expr.grammar").unwrap()
+}
+
+#[test]
+fn load_with_import() {
+    let grammar = Grammar::load("grammars/modules/stmt.grammar").unwrap();
+    assert_eq!(grammar.nonterminal(grammar.start()).0, "Program");
+    assert_eq!(
+        productions(&grammar),
+        [
+            "Program -> Program_rep1",
+            "Program_rep1 -> Stmt Program_rep1",
+            "Program_rep1 -> ",
+            "Stmt -> id = expr.E ;",
+            "Stmt -> print expr.E ;",
+            "expr.Atom -> ( expr.E )",
+            "expr.Atom -> num",
+            "expr.Atom -> id",
+            "expr.E -> expr.E + expr.E",
+            "expr.E -> expr.E - expr.E",
+            "expr.E -> expr.E * expr.E",
+            "expr.E -> expr.E / expr.E",
+            "expr.E -> expr.Atom",
+        ]
+    );
+    assert_eq!(grammar.precedence_levels().len(), 2);
+    assert_eq!(grammar.validate(), []);
+
+    // The imported precedence still applies.
+    let trees = earley::parse(&grammar, &tokens("id = id + num * ( id ) ; print id ;"));
+    assert_eq!(trees.len(), 1);
+
+    // Parsing the display gives the same grammar without the import.
+    let reparsed = grammar.to_string().parse::<Grammar>().unwrap();
+    assert_eq!(reparsed.to_string(), grammar.to_string());
+}
+
+#[test]
+fn import_errors() {
+    let error = "%import e \"grammars/modules/expr.grammar\";\n\
+                 %import e \"grammars/modules/expr.grammar\";\n\
+                 S -> e.E;"
+        .parse::<Grammar>()
+        .unwrap_err();
+    assert_eq!(
+        (error.line, error.kind),
+        (
+            2,
+            ParseErrorKind::ImportClash {
+                namespace: "e".to_string(),
+                symbol: "e.Atom".to_string()
+            }
+        )
+    );
+
+    // `num` is a terminal of the import.
+    let error = "%import e \"grammars/modules/expr.grammar\";\nS -> e.E;\nnum -> x;"
+        .parse::<Grammar>()
+        .unwrap_err();
+    assert_eq!(
+        error.kind,
+        ParseErrorKind::ImportClash {
+            namespace: "e".to_string(),
+            symbol: "num".to_string()
+        }
+    );
+
+    let error = "%import e \"grammars/modules/missing.grammar\";\nS -> e.E;"
+        .parse::<Grammar>()
+        .unwrap_err();
+    assert_eq!((error.line, error.column), (1, 11));
+    assert!(matches!(
+        error.kind,
+        ParseErrorKind::Import { path, .. } if path == "grammars/modules/missing.grammar"
+    ));
+
+    let LoadError::Parse(error) = Grammar::load("grammars/modules/cycle.grammar").unwrap_err()
+    else {
+        panic!("expected a parse error");
+    };
+    assert_eq!(
+        error.kind,
+        ParseErrorKind::ImportCycle("cycle.grammar".to_string())
+    );
+}
+
+#[test]
+fn builder_import() {
+    let mut builder = Grammar::builder();
+    builder
+        .import("expr", &expr())
+        .add_terminal("id")
+        .add_terminal("=")
+        .add_nonterminal("Assign")
+        .add_production("Assign", ["id", "=", "expr.E"])
+        .add_production("expr.Atom", ["id"])
+        .set_start("Assign");
+    let grammar = builder.build().unwrap();
+    assert_eq!(
+        productions(&grammar),
+        [
+            "Assign -> id = expr.E",
+            "expr.Atom -> id",
+            "expr.E -> expr.E + expr.E",
+            "expr.E -> expr.E - expr.E",
+            "expr.E -> expr.E * expr.E",
+            "expr.E -> expr.E / expr.E",
+            "expr.E -> expr.Atom",
+        ]
+    );
+    assert_eq!(
+        earley::parse(&grammar, &tokens("id = id - id - id")).len(),
+        1
+    );
+
+    builder.import("expr", &expr());
+    assert_eq!(
+        builder.build().unwrap_err(),
+        GrammarError::ImportClash {
+            namespace: "expr".to_string(),
+            symbol: "expr.Atom".to_string()
+        }
+    );
+
+    let mut builder = Grammar::builder();
+    builder
+        .import("expr", &expr())
+        .add_nonterminal("num")
+        .add_production("num", ["expr.E"])
+        .set_start("num");
+    assert_eq!(
+        builder.build().unwrap_err(),
+        GrammarError::ImportClash {
+            namespace: "expr".to_string(),
+            symbol: "num".to_string()
+        }
+    );
+}
+
+#[test]
+fn dotted_names() {
+    // A `.` is part of an identifier even without an import.
+    let grammar = "S -> a.b c;".parse::<Grammar>().unwrap();
+    assert_eq!(productions(&grammar), ["S -> a.b c"]);
+    assert!(grammar.terminal_id("a.b").is_some());
+    // `a.b`, `c` and the end-of-input marker.
+    assert_eq!(grammar.terminal_count(), 3);
+
+    // So the display of a grammar with imports parses back to it.
+    let grammar = Grammar::load("grammars/modules/stmt.grammar").unwrap();
+    let back = grammar.to_string().parse::<Grammar>().unwrap();
+    assert_eq!(productions(&back), productions(&grammar));
+}
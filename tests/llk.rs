@@ -0,0 +1,111 @@
+mod common;
+
+use common::tokens;
+use parsing::{
+    first_follow::{create_first_k, create_follow_k},
+    grammar::Grammar,
+    ll1::{self, ParseResult},
+    llk,
+};
+
+/// An assignment and an expression both start with `id`, so choosing between
+/// them needs the token after it.
+const ASSIGNMENT: &str = "%terminal id num;\n\
+                          S -> X \"=\" E | E;\n\
+                          X -> id;\n\
+                          E -> id | num;";
+
+fn tree<C>(result: ParseResult<C>) -> Option<String> {
+    match result {
+        ParseResult::Parse(tree) => Some(tree.to_string()),
+        ParseResult::NoParse => None,
+        ParseResult::Conflict(_) => panic!("conflict"),
+        ParseResult::TableMismatch => panic!("table mismatch"),
+    }
+}
+
+#[test]
+fn ll2() {
+    let grammar = ASSIGNMENT.parse::<Grammar>().unwrap();
+    assert!(matches!(
+        ll1::parse(&grammar, &tokens("id = num")),
+        ParseResult::Conflict(_)
+    ));
+    let ParseResult::Conflict(conflicts) = llk::parse(&grammar, &tokens("id = num"), 1) else {
+        panic!("expected a conflict");
+    };
+    assert_eq!(
+        conflicts,
+        [llk::Conflict {
+            nonterminal: grammar.nonterminal_id("S").unwrap(),
+            lookahead: vec![grammar.terminal_id("id").unwrap()],
+            productions: vec![0, 1],
+        }]
+    );
+
+    let first = create_first_k(&grammar, 2);
+    let follow = create_follow_k(&grammar, &first, 2);
+    let table = llk::create_table(&grammar, &first, &follow, 2);
+    assert_eq!(table.k(), 2);
+    assert!(!table.has_conflict());
+    assert_eq!(
+        llk::table_to_string(&grammar, &table),
+        "E id $\tE -> id\n\
+         E num $\tE -> num\n\
+         S id $\tS -> E\n\
+         S id =\tS -> X = E\n\
+         S num $\tS -> E\n\
+         X id =\tX -> id\n"
+    );
+
+    assert_eq!(
+        tree(llk::parse_with_table(&grammar, &tokens("id = num"), &table)).unwrap(),
+        "S\tX\tid\n\t=\n\tE\tnum"
+    );
+    assert_eq!(
+        tree(llk::parse(&grammar, &tokens("id"), 2)).unwrap(),
+        "S\tE\tid"
+    );
+    for input in ["id =", "num = id", "id num", "", "id = id id", "id = x"] {
+        assert_eq!(
+            tree(llk::parse_with_table(&grammar, &tokens(input), &table)),
+            None,
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn smallest_k() {
+    let grammar = ASSIGNMENT.parse::<Grammar>().unwrap();
+    assert_eq!(llk::smallest_k(&grammar, 1), None);
+    let (k, table) = llk::smallest_k(&grammar, 5).unwrap();
+    assert_eq!(k, 2);
+    assert_eq!(table.k(), 2);
+
+    let grammar = "S -> a b c | a b d | e;".parse::<Grammar>().unwrap();
+    assert_eq!(llk::smallest_k(&grammar, 5).unwrap().0, 3);
+
+    // The number of `a`s before the choice between `a b` and `b c` is unbounded.
+    let grammar = "S -> A a b | A b c;\nA -> a A | ε;"
+        .parse::<Grammar>()
+        .unwrap();
+    assert_eq!(llk::smallest_k(&grammar, 4), None);
+
+    // For k = 1 the parser agrees with the LL(1) parser.
+    let grammar = include_str!("../grammars/expr_ll1.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    let (k, table) = llk::smallest_k(&grammar, 3).unwrap();
+    assert_eq!(k, 1);
+    let input = tokens("w + x * ( y + z ) * w");
+    assert_eq!(
+        tree(llk::parse_with_table(&grammar, &input, &table)),
+        tree(ll1::parse(&grammar, &input))
+    );
+    assert_eq!(
+        tree(llk::parse(&grammar, &input, 3)),
+        tree(ll1::parse(&grammar, &input))
+    );
+}
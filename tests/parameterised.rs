@@ -0,0 +1,149 @@
+mod common;
+
+use common::{productions, tokens};
+use parsing::{
+    earley,
+    grammar::{text::ParseErrorKind, Grammar},
+};
+
+const CALLS: &str = "%terminal id num;\n\
+                     Program -> list(Stmt);\n\
+                     Stmt -> id \"(\" sep_by(Expr, \",\") \")\" \";\";\n\
+                     Expr -> id | num | \"[\" sep_by(Expr, \",\") \"]\";\n\
+                     list(X) -> X list(X) | ε;\n\
+                     sep_by(X, S) -> X (S X)* | ε;";
+
+fn error(source: &str) -> ParseErrorKind {
+    source.parse::<Grammar>().unwrap_err().kind
+}
+
+#[test]
+fn instantiation() {
+    let grammar = CALLS.parse::<Grammar>().unwrap();
+    assert_eq!(
+        productions(&grammar),
+        [
+            "Program -> list_Stmt",
+            "Stmt -> id ( sep_by_Expr_comma ) ;",
+            "Expr -> id",
+            "Expr -> num",
+            "Expr -> [ sep_by_Expr_comma ]",
+            "list_Stmt -> Stmt list_Stmt",
+            "list_Stmt -> ",
+            "sep_by_Expr_comma -> Expr sep_by_Expr_comma_rep1",
+            "sep_by_Expr_comma -> ",
+            "sep_by_Expr_comma_rep1 -> , Expr sep_by_Expr_comma_rep1",
+            "sep_by_Expr_comma_rep1 -> ",
+        ]
+    );
+    assert_eq!(grammar.validate(), []);
+
+    let display_name = |name: &str| grammar.display_name(grammar.nonterminal_id(name).unwrap());
+    assert_eq!(display_name("list_Stmt"), "list(Stmt)");
+    assert_eq!(display_name("sep_by_Expr_comma"), "sep_by(Expr, \",\")");
+    assert_eq!(
+        display_name("sep_by_Expr_comma_rep1"),
+        "sep_by_Expr_comma_rep1"
+    );
+    assert_eq!(display_name("Stmt"), "Stmt");
+
+    let trees = earley::parse(&grammar, &tokens("id ( num , [ id ] ) ;"));
+    assert_eq!(trees.len(), 1);
+    let tree = &trees[0];
+    assert!(tree
+        .to_string()
+        .starts_with("Program\tlist_Stmt\tStmt\tid\n"));
+    let displayed = tree.display(&grammar).to_string();
+    assert!(displayed.starts_with("Program\tlist(Stmt)\tStmt\tid\n"));
+    assert!(displayed.contains("\tsep_by(Expr, \",\")\tExpr\tnum\n"));
+
+    // The instances are ordinary rules in the displayed grammar.
+    let reparsed = grammar.to_string().parse::<Grammar>().unwrap();
+    assert_eq!(reparsed.to_string(), grammar.to_string());
+}
+
+#[test]
+fn nested_and_group_arguments() {
+    let grammar = "S -> list(pair(id, \"=\")) option((\"!\" | \"?\"));\n\
+                   list(X) -> X list(X) | ε;\n\
+                   pair(A, B) -> A B;\n\
+                   option(X) -> X | ε;"
+        .parse::<Grammar>()
+        .unwrap();
+    assert_eq!(
+        productions(&grammar),
+        [
+            "S -> list_pair_id_eq option_group",
+            "list_pair_id_eq -> pair_id_eq list_pair_id_eq",
+            "list_pair_id_eq -> ",
+            "option_group -> option_group_group1",
+            "option_group -> ",
+            "option_group_group1 -> !",
+            "option_group_group1 -> ?",
+            "pair_id_eq -> id =",
+        ]
+    );
+    let display_name = |name: &str| grammar.display_name(grammar.nonterminal_id(name).unwrap());
+    assert_eq!(display_name("list_pair_id_eq"), "list(pair(id, \"=\"))");
+    assert_eq!(display_name("option_group"), "option((\"!\" | \"?\"))");
+    assert_eq!(earley::parse(&grammar, &tokens("id = id = ?")).len(), 1);
+}
+
+#[test]
+fn errors() {
+    let wrong_count = |found| ParseErrorKind::WrongArgumentCount {
+        name: "list".to_string(),
+        expected: 1,
+        found,
+    };
+    assert_eq!(
+        error("S -> list(x, y);\nlist(X) -> X list(X) | ε;"),
+        wrong_count(2)
+    );
+    assert_eq!(
+        error("S -> list;\nlist(X) -> X list(X) | ε;"),
+        wrong_count(0)
+    );
+    assert_eq!(
+        error("S -> a;\nlist(X) -> X;\nlist(Y) -> Y;"),
+        ParseErrorKind::DuplicateTemplate("list".to_string())
+    );
+    assert!(matches!(
+        error("S -> f(a);\nf(X) -> X | f(g(X));\ng(X) -> X;"),
+        ParseErrorKind::InstantiationTooDeep(_)
+    ));
+    // Wrapping the argument in a group, or widening it, also never stops.
+    assert!(matches!(
+        error("S -> f(a);\nf(X) -> X | f((X));"),
+        ParseErrorKind::InstantiationTooDeep(_)
+    ));
+    assert!(matches!(
+        error("S -> f(a);\nf(X) -> X | f((X X X X));"),
+        ParseErrorKind::InstantiationTooDeep(_)
+    ));
+}
+
+#[test]
+fn groups() {
+    // A space before `(` makes it a group.
+    let grammar = "S -> a (b | c);".parse::<Grammar>().unwrap();
+    assert_eq!(
+        productions(&grammar),
+        ["S -> a S_group1", "S_group1 -> b", "S_group1 -> c"]
+    );
+    // So does a name that is not a parameterised nonterminal.
+    for source in ["S -> a(b | c);", "S -> a(b | c);\nf(X) -> X;"] {
+        let grammar = source.parse::<Grammar>().unwrap();
+        assert_eq!(
+            productions(&grammar),
+            ["S -> a S_group1", "S_group1 -> b", "S_group1 -> c"]
+        );
+    }
+    let grammar = "S -> a(b)* c;".parse::<Grammar>().unwrap();
+    assert_eq!(
+        productions(&grammar),
+        ["S -> a S_rep1 c", "S_rep1 -> b S_rep1", "S_rep1 -> "]
+    );
+    let error = "S -> a(b;".parse::<Grammar>().unwrap_err();
+    assert_eq!((error.line, error.column), (1, 9));
+}
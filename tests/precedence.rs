@@ -0,0 +1,244 @@
+mod common;
+
+use common::tokens;
+use parsing::{
+    earley,
+    grammar::{export, transform, yacc, Associativity, Grammar, GrammarError, Precedence, Symbol},
+    ll1,
+    parse_tree::ParseTree,
+};
+
+const EXPR: &str = "%left \"+\" \"-\";\n\
+                    %left \"*\";\n\
+                    %right \"^\";\n\
+                    %nonassoc \"==\";\n\
+                    %right NEG;\n\
+                    E -> E \"+\" E | E \"-\" E | E \"*\" E | E \"^\" E | E \"==\" E\n\
+                       | \"-\" E %prec NEG | \"(\" E \")\" | x;";
+
+/// Parses `input` with the Earley parser and returns the trees with each
+/// operator application written in parentheses.
+fn bracketed(grammar: &Grammar, input: &str) -> Vec<String> {
+    earley::parse(grammar, &tokens(input))
+        .iter()
+        .map(|tree| {
+            let mut out = String::new();
+            write_bracketed(tree, &mut out);
+            out
+        })
+        .collect()
+}
+
+fn write_bracketed(tree: &ParseTree, out: &mut String) {
+    match tree {
+        ParseTree::Terminal(t) => out.push_str(&t.0),
+        ParseTree::NonTerminal(_, children) => {
+            let wrap =
+                children.len() > 1 && !matches!(&children[0], ParseTree::Terminal(t) if t.0 == "(");
+            if wrap {
+                out.push('[');
+            }
+            for (i, child) in children.iter().enumerate() {
+                if i > 0 {
+                    out.push(' ');
+                }
+                write_bracketed(child, out);
+            }
+            if wrap {
+                out.push(']');
+            }
+        }
+    }
+}
+
+#[test]
+fn declarations() {
+    let grammar = EXPR.parse::<Grammar>().unwrap();
+    let t = |name: &str| grammar.terminal_id(name).unwrap();
+    assert_eq!(
+        grammar
+            .precedence_levels()
+            .map(|(associativity, terminals)| (associativity, terminals.to_vec()))
+            .collect::<Vec<_>>(),
+        [
+            (Associativity::Left, vec![t("+"), t("-")]),
+            (Associativity::Left, vec![t("*")]),
+            (Associativity::Right, vec![t("^")]),
+            (Associativity::NonAssoc, vec![t("==")]),
+            (Associativity::Right, vec![t("NEG")]),
+        ]
+    );
+    assert_eq!(
+        grammar.terminal_precedence(t("*")),
+        Some(Precedence {
+            level: 1,
+            associativity: Associativity::Left
+        })
+    );
+    assert_eq!(grammar.terminal_precedence(t("x")), None);
+    assert_eq!(grammar.precedence_override(5), Some(t("NEG")));
+    assert_eq!(grammar.production_precedence(5).unwrap().level, 4);
+    assert_eq!(grammar.production_precedence(0).unwrap().level, 0);
+    assert_eq!(grammar.production_precedence(6), None);
+
+    let displayed = grammar.to_string();
+    assert!(displayed.contains("%left \"+\" \"-\";\n%left \"*\";\n%right \"^\";"));
+    assert!(displayed.contains("\"-\" E %prec NEG"));
+    let reparsed = displayed.parse::<Grammar>().unwrap();
+    assert_eq!(reparsed.to_string(), displayed);
+
+    let error = "%left E;\nE -> E \"+\" E | x;".parse::<Grammar>();
+    assert!(error.is_err());
+}
+
+#[test]
+fn earley_filters_trees() {
+    let grammar = EXPR.parse::<Grammar>().unwrap();
+    assert_eq!(bracketed(&grammar, "x + x * x"), ["[x + [x * x]]"]);
+    assert_eq!(bracketed(&grammar, "x * x + x"), ["[[x * x] + x]"]);
+    assert_eq!(bracketed(&grammar, "x - x + x"), ["[[x - x] + x]"]);
+    assert_eq!(bracketed(&grammar, "x ^ x ^ x"), ["[x ^ [x ^ x]]"]);
+    assert_eq!(bracketed(&grammar, "- x ^ x"), ["[[- x] ^ x]"]);
+    assert_eq!(bracketed(&grammar, "- x * x"), ["[[- x] * x]"]);
+    assert_eq!(bracketed(&grammar, "x * - x"), ["[x * [- x]]"]);
+    assert_eq!(
+        bracketed(&grammar, "x == ( x == x )"),
+        ["[x == ( [x == x] )]"]
+    );
+    assert_eq!(bracketed(&grammar, "x == x == x"), [] as [&str; 0]);
+
+    // Without declarations every tree is kept.
+    let plain = "E -> E \"+\" E | E \"*\" E | x;"
+        .parse::<Grammar>()
+        .unwrap();
+    assert_eq!(bracketed(&plain, "x + x * x").len(), 2);
+}
+
+#[test]
+fn builder() {
+    let mut builder = Grammar::builder();
+    builder
+        .add_nonterminal("E")
+        .add_production("E", ["E", "+", "E"])
+        .add_production("E", ["E", "*", "E"])
+        .add_production("E", ["x"])
+        .add_precedence(Associativity::Left, ["+"])
+        .add_precedence(Associativity::Left, ["*"])
+        .set_start("E");
+    for t in ["+", "*", "x"] {
+        builder.add_terminal(t);
+    }
+    let grammar = builder.build().unwrap();
+    assert_eq!(
+        bracketed(&grammar, "x * x + x * x"),
+        ["[[x * x] + [x * x]]"]
+    );
+
+    builder.add_precedence(Associativity::Right, ["^"]);
+    assert_eq!(
+        builder.build().unwrap_err(),
+        GrammarError::UndeclaredPrecedence("^".to_string())
+    );
+}
+
+#[test]
+fn stratify() {
+    let grammar = EXPR.parse::<Grammar>().unwrap();
+    let stratified = transform::stratify_precedence(&grammar);
+    assert_eq!(stratified.precedence_levels().len(), 0);
+    let productions = stratified
+        .productions()
+        .iter()
+        .map(|p| p.display(&stratified).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        productions,
+        [
+            "E -> E + E_prec2",
+            "E -> E - E_prec2",
+            "E -> E_prec2",
+            "E_prec2 -> E_prec2 * E_prec3",
+            "E_prec2 -> E_prec3",
+            "E_prec3 -> E_prec4 ^ E_prec3",
+            "E_prec3 -> E_prec4",
+            "E_prec4 -> E_prec5 == E_prec5",
+            "E_prec4 -> E_prec5",
+            "E_prec5 -> - E_prec5",
+            "E_prec5 -> E_prec6",
+            "E_prec6 -> ( E )",
+            "E_prec6 -> x",
+        ]
+    );
+
+    for input in [
+        "x + x * x",
+        "x * x + x",
+        "x - x + x",
+        "x ^ x ^ x",
+        "- x ^ x",
+        "- x * x",
+        "x * - x",
+        "x == ( x == x )",
+    ] {
+        assert_eq!(
+            earley::parse(&stratified, &tokens(input)).len(),
+            1,
+            "{}",
+            input
+        );
+    }
+    assert!(earley::parse(&stratified, &tokens("x == x == x")).is_empty());
+
+    let (ll1_grammar, _) =
+        transform::left_factor(&transform::eliminate_left_recursion(&stratified));
+    for input in ["x + x * - x ^ x", "( x - x ) == x", "- - x"] {
+        assert!(
+            matches!(
+                ll1::parse(&ll1_grammar, &tokens(input)),
+                ll1::ParseResult::Parse(_)
+            ),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn stratify_leaves_other_nonterminals() {
+    let grammar = "%left \",\";\nL -> L \",\" L | I;\nI -> x | \"[\" L \"]\";"
+        .parse::<Grammar>()
+        .unwrap();
+    let stratified = transform::stratify_precedence(&grammar);
+    let productions = stratified
+        .productions()
+        .iter()
+        .map(|p| p.display(&stratified).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        productions,
+        [
+            "L -> L , L_prec2",
+            "L -> L_prec2",
+            "L_prec2 -> I",
+            "I -> x",
+            "I -> [ L ]",
+        ]
+    );
+    let rhs = stratified.productions()[0].rhs();
+    assert!(matches!(rhs[0], Symbol::NonTerminal(nt) if nt == stratified.start()));
+}
+
+#[test]
+fn yacc_round_trip() {
+    let grammar = EXPR.parse::<Grammar>().unwrap();
+    let bison = export::to_bison(&grammar);
+    assert!(bison.contains("%left '+' '-'\n%left '*'\n%right '^'\n%nonassoc \"==\"\n"));
+    assert!(bison.contains("'-' E %prec NEG {}"));
+    let (imported, warnings) = yacc::parse(&bison).unwrap();
+    assert_eq!(warnings, []);
+    assert_eq!(
+        imported.precedence_levels().len(),
+        grammar.precedence_levels().len()
+    );
+    assert_eq!(bracketed(&imported, "- x * x + x"), ["[[[- x] * x] + x]"]);
+}
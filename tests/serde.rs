@@ -0,0 +1,132 @@
+#![cfg(feature = "serde")]
+
+use parsing::{grammar::Grammar, ll1, parse_tree::OwnedParseTree};
+
+#[test]
+fn grammar_json() {
+    let grammar = "%terminal x;\nE -> T \"+\" E | T;\nT -> x;"
+        .parse::<Grammar>()
+        .unwrap();
+    let json = serde_json::to_value(&grammar).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "terminals": ["$", "+", "x"],
+            "nonterminals": ["E", "T"],
+            "declared_terminals": [1, 2],
+            "declared_nonterminals": [0, 1],
+            "productions": [
+                {"lhs": 0, "rhs": [{"nonterminal": 1}, {"terminal": 1}, {"nonterminal": 0}]},
+                {"lhs": 0, "rhs": [{"nonterminal": 1}]},
+                {"lhs": 1, "rhs": [{"terminal": 2}]},
+            ],
+            "start": 0,
+            "synthetic": [],
+        })
+    );
+    let back = serde_json::from_value::<Grammar>(json).unwrap();
+    assert_eq!(back.to_string(), grammar.to_string());
+
+    let bad = serde_json::json!({
+        "terminals": ["$"],
+        "nonterminals": ["S"],
+        "declared_terminals": [],
+        "declared_nonterminals": [0],
+        "productions": [{"lhs": 0, "rhs": [{"terminal": 1}]}],
+        "start": 0,
+        "synthetic": [],
+    });
+    assert!(serde_json::from_value::<Grammar>(bad).is_err());
+}
+
+#[test]
+fn table_and_tree_round_trip() {
+    let grammar = include_str!("../grammars/expr_ll1.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    let first = parsing::first_follow::create_first(&grammar);
+    let follow = parsing::first_follow::create_follow(&grammar, &first);
+    let table = ll1::create_table(&grammar, &first, &follow);
+
+    let stored_grammar = serde_json::to_string(&grammar).unwrap();
+    let stored_table = serde_json::to_string(&table).unwrap();
+    let grammar = serde_json::from_str::<Grammar>(&stored_grammar).unwrap();
+    let table = serde_json::from_str::<ll1::LL1Table>(&stored_table).unwrap();
+
+    let tokens = "w + x * ( y + z )".split_whitespace().collect::<Vec<_>>();
+    let ll1::ParseResult::Parse(tree) = ll1::parse_with_table(&grammar, &tokens, &table) else {
+        panic!();
+    };
+    let owned = tree.to_owned_tree();
+    let json = serde_json::to_string(&owned).unwrap();
+    assert!(json.starts_with(r#"{"nonterminal":["E",[{"nonterminal":["T""#));
+    let back = serde_json::from_str::<OwnedParseTree>(&json).unwrap();
+    assert_eq!(back, owned);
+    assert_eq!(back.to_string(), tree.to_string());
+}
+
+#[test]
+fn mismatched_table() {
+    let table_for = |grammar: &Grammar| {
+        let first = parsing::first_follow::create_first(grammar);
+        let follow = parsing::first_follow::create_follow(grammar, &first);
+        ll1::create_table(grammar, &first, &follow)
+    };
+    let grammar = "S -> T | b;\nT -> a;".parse::<Grammar>().unwrap();
+    let table = table_for(&grammar);
+    assert!(table.fits(&grammar));
+    let json = serde_json::to_value(&table).unwrap();
+    let width = grammar.terminal_count();
+    let a = grammar.terminal_id("a").unwrap().index();
+
+    let parse = |json: serde_json::Value| {
+        let table = serde_json::from_value::<ll1::LL1Table>(json).unwrap();
+        assert!(!table.fits(&grammar));
+        matches!(
+            ll1::parse_with_table(&grammar, &["a"], &table),
+            ll1::ParseResult::TableMismatch
+        )
+    };
+    let mut out_of_range = json.clone();
+    out_of_range["cells"][a] = serde_json::json!([7]);
+    assert!(parse(out_of_range));
+
+    // `S -> T` in the cell of `T` and `a`.
+    let mut wrong_lhs = json.clone();
+    wrong_lhs["cells"][width + a] = serde_json::json!([0]);
+    assert!(parse(wrong_lhs));
+
+    let mut wrong_width = json;
+    wrong_width["terminal_count"] = serde_json::json!(1);
+    assert!(parse(wrong_width));
+
+    let other = "S -> a | b;".parse::<Grammar>().unwrap();
+    assert!(parse(serde_json::to_value(table_for(&other)).unwrap()));
+}
+
+#[test]
+fn precedence_json() {
+    let grammar = "%left \"+\";\n%right NEG;\nE -> E \"+\" E | \"-\" E %prec NEG | x;"
+        .parse::<Grammar>()
+        .unwrap();
+    let json = serde_json::to_value(&grammar).unwrap();
+    assert_eq!(
+        json["precedence"],
+        serde_json::json!([["left", [1]], ["right", [4]]])
+    );
+    assert_eq!(json["prec"], serde_json::json!([[1, 4]]));
+    let back = serde_json::from_value::<Grammar>(json).unwrap();
+    assert_eq!(back.to_string(), grammar.to_string());
+}
+
+#[test]
+fn display_names_json() {
+    let grammar = "S -> list(x);\nlist(X) -> X list(X) | ε;"
+        .parse::<Grammar>()
+        .unwrap();
+    let json = serde_json::to_value(&grammar).unwrap();
+    assert_eq!(json["display_names"], serde_json::json!([[1, "list(x)"]]));
+    let back = serde_json::from_value::<Grammar>(json).unwrap();
+    let nt = back.nonterminal_id("list_x").unwrap();
+    assert_eq!(back.display_name(nt), "list(x)");
+}
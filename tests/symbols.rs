@@ -0,0 +1,58 @@
+use parsing::{
+    grammar::{Grammar, Symbol, TerminalId},
+    ll1,
+};
+
+#[test]
+fn ids_are_dense() {
+    let grammar = include_str!("../grammars/expr_ll1.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    let plus = grammar.terminal_id("+").unwrap();
+    assert_eq!(grammar.terminal(plus).0, "+");
+    assert_eq!(grammar.terminal(TerminalId::eoim()).0, "$");
+    assert!(grammar.terminal_id("-").is_none());
+    for &symbol in grammar.productions().iter().flat_map(|p| p.rhs()) {
+        match symbol {
+            Symbol::Terminal(t) => assert!(t.index() < grammar.terminal_count()),
+            Symbol::NonTerminal(nt) => assert!(nt.index() < grammar.nonterminal_count()),
+        }
+    }
+    let e = grammar.nonterminal_id("E'").unwrap();
+    assert_eq!(
+        grammar
+            .productions_from(e)
+            .map(|p| p.display(&grammar).to_string())
+            .collect::<Vec<_>>(),
+        ["E' -> + T E'", "E' -> "]
+    );
+}
+
+#[test]
+fn unknown_tokens_do_not_parse() {
+    let grammar = include_str!("../grammars/expr_ll1.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    assert!(matches!(
+        ll1::parse(&grammar, &["x", "-", "y"]),
+        ll1::ParseResult::NoParse
+    ));
+}
+
+#[test]
+fn nonterminal_facts() {
+    let grammar = "%terminal a b c;\nS -> A S b | c;\nA -> a | ε;\nB -> B a;"
+        .parse::<Grammar>()
+        .unwrap();
+    let nt = |name: &str| grammar.nonterminal_id(name).unwrap();
+    assert_eq!(grammar.production_indices(nt("S")), [0, 1]);
+    assert_eq!(grammar.productions_from(nt("A")).len(), 2);
+    assert!(grammar.is_nullable(nt("A")));
+    assert!(!grammar.is_nullable(nt("S")));
+    assert!(grammar.is_productive(nt("S")));
+    assert!(!grammar.is_productive(nt("B")));
+    // `S -> A S b` is left recursive because `A` is nullable.
+    assert!(grammar.is_left_recursive(nt("S")));
+    assert!(grammar.is_left_recursive(nt("B")));
+    assert!(!grammar.is_left_recursive(nt("A")));
+}
@@ -59,3 +59,29 @@ fn ll1() {
     };
     assert_eq!(ans.to_string(), setup.ans);
 }
+
+#[test]
+fn earley_nullable() {
+    // `A` is completed at position 0 before `S -> A . A x` predicts it again.
+    let grammar = "%terminal x;\nS -> A A x;\nA -> ε;"
+        .parse::<Grammar>()
+        .unwrap();
+    let ans = earley::parse(&grammar, &["x"]);
+    assert_eq!(ans.len(), 1);
+    assert_eq!(ans[0].to_string(), "S\tA\n\tA\n\tx");
+    assert!(earley::parse(&grammar, &[]).is_empty());
+}
+
+#[test]
+fn ll1_trailing_input() {
+    // The parser must reach the end of the input, not just empty its stack.
+    let grammar = "S -> a;".parse::<Grammar>().unwrap();
+    assert!(matches!(
+        ll1::parse(&grammar, &["a"]),
+        ll1::ParseResult::Parse(_)
+    ));
+    assert!(matches!(
+        ll1::parse(&grammar, &["a", "a"]),
+        ll1::ParseResult::NoParse
+    ));
+}
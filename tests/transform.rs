@@ -0,0 +1,272 @@
+mod common;
+
+use common::{productions, tokens};
+use parsing::{
+    earley,
+    grammar::{transform, Grammar},
+    ll1,
+};
+
+fn nonterminals(grammar: &Grammar) -> Vec<String> {
+    let mut names = grammar
+        .nonterminals()
+        .iter()
+        .map(|&nt| grammar.nonterminal(nt).to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[test]
+fn direct_left_recursion() {
+    let grammar = include_str!("../grammars/expr.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    let expected = include_str!("../grammars/expr_ll1.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    assert!(matches!(
+        ll1::parse(&grammar, &tokens("x")),
+        ll1::ParseResult::Conflict(_)
+    ));
+
+    let transformed = transform::eliminate_left_recursion(&grammar);
+    assert_eq!(productions(&transformed), productions(&expected));
+    assert_eq!(nonterminals(&transformed), nonterminals(&expected));
+
+    let ll1::ParseResult::Parse(tree) = ll1::parse(&transformed, &tokens("w + x * ( y + z )"))
+    else {
+        panic!();
+    };
+    let ll1::ParseResult::Parse(expected_tree) =
+        ll1::parse(&expected, &tokens("w + x * ( y + z )"))
+    else {
+        panic!();
+    };
+    assert_eq!(tree.to_string(), expected_tree.to_string());
+}
+
+#[test]
+fn indirect_left_recursion() {
+    let grammar = "%terminal a b c d e;\nS -> A a | b;\nA -> A c | S d | e;"
+        .parse::<Grammar>()
+        .unwrap();
+    let transformed = transform::eliminate_left_recursion(&grammar);
+    assert_eq!(
+        productions(&transformed),
+        [
+            "S -> A a",
+            "S -> b",
+            "A -> b d A'",
+            "A -> e A'",
+            "A' -> c A'",
+            "A' -> a d A'",
+            "A' -> ",
+        ]
+    );
+    for input in ["b", "e a", "b d a", "e c a d a", "b d c c a", "e d", "b a"] {
+        assert_eq!(
+            earley::parse(&grammar, &tokens(input)).len(),
+            earley::parse(&transformed, &tokens(input)).len()
+        );
+    }
+}
+
+#[test]
+fn left_factoring() {
+    let grammar = "%terminal a b c d e;\nA -> a b c | e | a b d | a b;"
+        .parse::<Grammar>()
+        .unwrap();
+    assert!(matches!(
+        ll1::parse(&grammar, &tokens("a b d")),
+        ll1::ParseResult::Conflict(_)
+    ));
+
+    let (factored, changed) = transform::left_factor(&grammar);
+    assert_eq!(
+        productions(&factored),
+        ["A -> a b A'", "A -> e", "A' -> c", "A' -> d", "A' -> "]
+    );
+    assert_eq!(changed, [grammar.start()]);
+    for input in ["a b c", "a b d", "a b", "e"] {
+        assert!(matches!(
+            ll1::parse(&factored, &tokens(input)),
+            ll1::ParseResult::Parse(_)
+        ));
+    }
+}
+
+#[test]
+fn repeated_left_factoring() {
+    let grammar = "%terminal if then else a b;\n\
+                   S -> if E then S | if E then S else S | if a | a;\n\
+                   E -> b;"
+        .parse::<Grammar>()
+        .unwrap();
+    let (factored, changed) = transform::left_factor(&grammar);
+    assert_eq!(
+        productions(&factored),
+        [
+            "S -> if S'",
+            "S -> a",
+            "S' -> E then S S''",
+            "S' -> a",
+            "S'' -> ",
+            "S'' -> else S",
+            "E -> b",
+        ]
+    );
+    assert_eq!(changed.len(), 1);
+}
+
+#[test]
+fn epsilon_removal() {
+    let grammar = "%terminal a b;\nS -> A S b | A;\nA -> a | ε;"
+        .parse::<Grammar>()
+        .unwrap();
+    let (transformed, map) = transform::remove_epsilon_productions(&grammar);
+    assert_eq!(
+        productions(&transformed),
+        [
+            "S' -> S",
+            "S' -> ",
+            "S -> A S b",
+            "S -> S b",
+            "S -> A b",
+            "S -> b",
+            "S -> A",
+            "A -> a",
+        ]
+    );
+    assert!(transformed.is_synthetic(transformed.start()));
+    assert_eq!(map[0], None);
+    let origin = map[4].as_ref().unwrap();
+    assert_eq!((origin.production, &origin.erased[..]), (0, &[1][..]));
+    for input in ["", "a", "b", "a b", "a a b b", "b b a"] {
+        assert_eq!(
+            earley::parse(&grammar, &tokens(input)).is_empty(),
+            earley::parse(&transformed, &tokens(input)).is_empty(),
+        );
+    }
+}
+
+#[test]
+fn epsilon_removal_long_rhs() {
+    // Every subset of the 17 optional symbols can be erased.
+    let rhs = (0..17).map(|i| format!("x{}?", i)).collect::<Vec<_>>();
+    let grammar = format!("S -> {};", rhs.join(" "))
+        .parse::<Grammar>()
+        .unwrap();
+    let (transformed, map) = transform::remove_epsilon_productions(&grammar);
+    assert!(transformed.productions().len() < 4 * 17 * 2);
+    assert!(transformed
+        .productions()
+        .iter()
+        .all(|production| !production.rhs().is_empty() || production.lhs() == transformed.start()));
+    for input in [
+        "x3",
+        "x0 x16",
+        "x2 x5 x7",
+        &(0..17)
+            .map(|i| format!("x{}", i))
+            .collect::<Vec<_>>()
+            .join(" "),
+    ] {
+        assert_eq!(
+            earley::parse(&transformed, &tokens(input)).len(),
+            1,
+            "{}",
+            input
+        );
+    }
+    for input in ["x16 x0", "x3 x3", "y"] {
+        assert!(
+            earley::parse(&transformed, &tokens(input)).is_empty(),
+            "{}",
+            input
+        );
+    }
+
+    // The links of the chain cover the rhs from where they start, and erasing
+    // a link erases the rest of the rhs.
+    let s = grammar.nonterminal_id("S").unwrap();
+    let origins = map
+        .iter()
+        .flatten()
+        .filter(|origin| grammar.productions()[origin.production].lhs() == s)
+        .collect::<Vec<_>>();
+    assert!(origins
+        .iter()
+        .any(|origin| origin.span == (0..1) && origin.erased == (1..17).collect::<Vec<_>>()));
+    assert!(origins.iter().any(|origin| origin.span == (15..17)));
+}
+
+#[test]
+fn unit_removal() {
+    let grammar = include_str!("../grammars/expr.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    let (transformed, map) = transform::remove_unit_productions(&grammar);
+    assert!(transformed
+        .productions()
+        .iter()
+        .all(|p| !matches!(p.rhs(), [parsing::grammar::Symbol::NonTerminal(_)])));
+    // `E -> w` comes from `ID -> w` through `E -> T`, `T -> F` and `F -> ID`.
+    let idx = transformed
+        .productions()
+        .iter()
+        .position(|p| p.display(&transformed).to_string() == "E -> w")
+        .unwrap();
+    let origin = map[idx].as_ref().unwrap();
+    let original = |i: usize| grammar.productions()[i].display(&grammar).to_string();
+    assert_eq!(original(origin.production), "ID -> w");
+    assert_eq!(
+        origin
+            .units
+            .iter()
+            .map(|&i| original(i))
+            .collect::<Vec<_>>(),
+        ["E -> T", "T -> F", "F -> ID"]
+    );
+    for input in ["w", "w + x * y", "( w + x ) * y", "w +"] {
+        assert_eq!(
+            earley::parse(&grammar, &tokens(input)).len(),
+            earley::parse(&transformed, &tokens(input)).len(),
+        );
+    }
+}
+
+#[test]
+fn reduce() {
+    let grammar = "%terminal a b c d;\n\
+                   S -> A B | a;\n\
+                   A -> a A;\n\
+                   B -> b;\n\
+                   C -> c;\n\
+                   D -> A d;"
+        .parse::<Grammar>()
+        .unwrap();
+    let (reduced, reduction) = grammar.reduce();
+    assert_eq!(productions(&reduced), ["S -> a"]);
+    let names =
+        |v: &[parsing::grammar::NonTerminal]| v.iter().map(|nt| nt.0.clone()).collect::<Vec<_>>();
+    assert_eq!(names(&reduction.unproductive), ["A", "D"]);
+    // `B` is only reachable through `S -> A B`, which was removed.
+    assert_eq!(names(&reduction.unreachable_nonterminals), ["B", "C"]);
+    assert_eq!(
+        reduction
+            .unreachable_terminals
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        ["b", "c", "d"]
+    );
+    assert_eq!(reduction.productions, [0, 2, 3, 4, 5]);
+    assert_eq!(reduced.nonterminals().len(), 1);
+    assert_eq!(reduced.terminals().len(), 1);
+    assert_eq!(reduced.validate(), []);
+
+    let (same, reduction) = reduced.reduce();
+    assert!(reduction.is_empty());
+    assert_eq!(same.productions(), reduced.productions());
+}
@@ -0,0 +1,72 @@
+use parsing::grammar::{
+    build_grammar,
+    validate::{Diagnostic, DiagnosticKind, Severity},
+    Grammar, NonTerminal, Terminal,
+};
+
+fn kinds(diagnostics: &[Diagnostic]) -> Vec<(DiagnosticKind, Option<usize>)> {
+    diagnostics
+        .iter()
+        .map(|d| (d.kind.clone(), d.production))
+        .collect()
+}
+
+#[test]
+fn clean_grammar() {
+    let grammar = include_str!("../grammars/expr_ll1.grammar")
+        .parse::<Grammar>()
+        .unwrap();
+    assert_eq!(grammar.validate(), []);
+}
+
+#[test]
+fn reports_mistakes() {
+    let grammar = build_grammar(
+        "S A B C D",
+        "a b c",
+        vec![
+            ("S", "A a | A a | S d"),
+            ("A", "a"),
+            ("B", "b"),
+            ("D", "D a"),
+        ],
+        "S",
+    );
+    let nt = |s: &str| NonTerminal(s.to_string());
+    assert_eq!(
+        kinds(&grammar.validate()),
+        [
+            (DiagnosticKind::DuplicateProduction(0), Some(1)),
+            (
+                DiagnosticKind::UndeclaredTerminal(Terminal("d".into())),
+                Some(2)
+            ),
+            (DiagnosticKind::NoProductions(nt("C")), None),
+            (DiagnosticKind::UnreachableNonTerminal(nt("B")), None),
+            (DiagnosticKind::UnreachableNonTerminal(nt("C")), None),
+            (DiagnosticKind::UnreachableNonTerminal(nt("D")), None),
+            (
+                DiagnosticKind::UnreachableTerminal(Terminal("b".into())),
+                None
+            ),
+            (
+                DiagnosticKind::UnreachableTerminal(Terminal("c".into())),
+                None
+            ),
+            (DiagnosticKind::UnproductiveNonTerminal(nt("D")), Some(5)),
+        ]
+    );
+}
+
+#[test]
+fn undefined_start() {
+    let grammar = "%terminal a;\n%start T;\nS -> a;"
+        .parse::<Grammar>()
+        .unwrap();
+    let diagnostics = grammar.validate();
+    assert_eq!(
+        diagnostics[0].kind,
+        DiagnosticKind::UndefinedStart(NonTerminal("T".into()))
+    );
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use common::productions;
+use parsing::{
+    earley,
+    grammar::{
+        export,
+        text::ParseErrorKind,
+        yacc::{self, WarningKind},
+        Grammar,
+    },
+};
+
+#[test]
+fn calc() {
+    let (grammar, warnings) = yacc::parse(include_str!("../grammars/calc.y")).unwrap();
+    assert_eq!(
+        productions(&grammar),
+        [
+            "input -> ",
+            "input -> input line",
+            "line -> \n",
+            "line -> exp \n",
+            "line -> error \n",
+            "exp -> NUM",
+            "exp -> exp + exp",
+            "exp -> exp - exp",
+            "exp -> exp * exp",
+            "exp -> exp / exp",
+            "exp -> - exp",
+            "exp -> exp POW exp",
+            "exp -> ( exp )",
+        ]
+    );
+    assert_eq!(grammar.nonterminal(grammar.start()).0, "input");
+    assert_eq!(
+        warnings
+            .iter()
+            .map(|w| (w.line, w.kind.clone()))
+            .collect::<Vec<_>>(),
+        [
+            (
+                15,
+                WarningKind::IgnoredDeclaration("precedence".to_string())
+            ),
+            (27, WarningKind::ErrorToken),
+            (36, WarningKind::Prec("NEG".to_string())),
+            (38, WarningKind::MidRuleAction),
+        ]
+    );
+    assert_eq!(
+        warnings[2].to_string(),
+        "36:12: warning: ignored `%prec NEG`, which has no precedence"
+    );
+
+    // `%left` resolves the ambiguities between the declared operators...
+    let tokens = ["NUM", "-", "NUM", "-", "NUM", "*", "NUM", "\n"];
+    let trees = earley::parse(&grammar, &tokens);
+    assert_eq!(trees.len(), 1);
+    // (NUM - NUM) - (NUM * NUM)
+    assert_eq!(
+        trees[0].to_string(),
+        "input\tinput\n\tline\texp\texp\texp\tNUM\n\t\t\t\t-\n\t\t\t\texp\tNUM\n\
+         \t\t\t-\n\t\t\texp\texp\tNUM\n\t\t\t\t*\n\t\t\t\texp\tNUM\n\t\t\n"
+    );
+    // ...but `**` has none.
+    let tokens = ["NUM", "-", "NUM", "POW", "NUM", "\n"];
+    assert_eq!(earley::parse(&grammar, &tokens).len(), 2);
+}
+
+#[test]
+fn bison_round_trip() {
+    let grammar = "%terminal id \"==\" \",\" \"'\";\n\
+                   S -> S' \",\" S | S';\n\
+                   S' -> id \"==\" id | \"'\" | ε;"
+        .parse::<Grammar>()
+        .unwrap();
+    let (imported, warnings) = yacc::parse(&export::to_bison(&grammar)).unwrap();
+    assert_eq!(warnings, []);
+    assert_eq!(
+        productions(&imported),
+        [
+            "S -> S_p , S",
+            "S -> S_p",
+            "S_p -> id TOK_3 id",
+            "S_p -> '",
+            "S_p -> ",
+        ]
+    );
+}
+
+#[test]
+fn aliases_and_undeclared_tokens() {
+    let source = "%token <op> EQ \"==\" NUM 258 \"number\"\n\
+                  %start cmp\n\
+                  %%\n\
+                  cmp[res] : NUM \"==\" operand ;\n\
+                  operand : \"number\" | ID\n\
+                  %%";
+    let (grammar, warnings) = yacc::parse(source).unwrap();
+    assert_eq!(
+        productions(&grammar),
+        ["cmp -> NUM EQ operand", "operand -> NUM", "operand -> ID"]
+    );
+    assert_eq!(
+        warnings.iter().map(|w| &w.kind).collect::<Vec<_>>(),
+        [&WarningKind::UndeclaredToken("ID".to_string())]
+    );
+}
+
+#[test]
+fn errors() {
+    let error = yacc::parse("%%\ns : 'a' { if (x) { y(); }\n").unwrap_err();
+    assert_eq!(
+        (error.line, error.column, error.kind),
+        (2, 9, ParseErrorKind::UnterminatedBlock)
+    );
+    let error = yacc::parse("%token a\n%%\ns : a ;\na : ;").unwrap_err();
+    assert_eq!(
+        (error.line, error.kind),
+        (4, ParseErrorKind::TerminalAndNonTerminal("a".to_string()))
+    );
+    let error = yacc::parse("%token a\n%%\n%%").unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::NoStart);
+    let error = yacc::parse("%token a\n%start foo\n%%\ns : a ;").unwrap_err();
+    assert_eq!(
+        (error.line, error.column, error.kind),
+        (2, 8, ParseErrorKind::StartWithoutRules("foo".to_string()))
+    );
+    let error = yacc::parse("%token a\n%start a\n%%\ns : a ;").unwrap_err();
+    assert_eq!(
+        (error.line, error.column, error.kind),
+        (
+            2,
+            8,
+            ParseErrorKind::TerminalAndNonTerminal("a".to_string())
+        )
+    );
+}